@@ -0,0 +1,34 @@
+//! Runs a saved circuit against a vector file and prints the pass/fail
+//! report.
+//!
+//! ```text
+//! cargo run --example testbench --features testbench -- circuit.data vectors.csv
+//! ```
+//!
+//! Exits with 0 when every vector passes, 1 when one fails and 2 when the
+//! files can't be read.
+
+use std::{env, process::ExitCode};
+
+use tc_save_parser::testbench::Testbench;
+
+fn main() -> ExitCode {
+    let args: Vec<_> = env::args().skip(1).collect();
+    let [circuit, vectors] = args.as_slice() else {
+        eprintln!("usage: testbench <circuit.data> <vectors.csv|vectors.toml>");
+        return ExitCode::from(2);
+    };
+    let report = match Testbench::load(vectors).and_then(|bench| bench.run_file(circuit)) {
+        Ok(report) => report,
+        Err(err) => {
+            eprintln!("{}", err);
+            return ExitCode::from(2);
+        }
+    };
+    println!("{}", report);
+    if report.passed() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
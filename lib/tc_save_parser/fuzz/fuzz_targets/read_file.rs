@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tc_save_parser::CircuitDataFile;
+
+// Whole files, snappy framing included. Anything that parses must write back.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(cdf) = CircuitDataFile::from_bytes(data) {
+        cdf.to_bytes().unwrap();
+    }
+});
@@ -0,0 +1,15 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tc_save_parser::CircuitDataFile;
+
+// Decompressed payloads, compressed here so the fuzzer doesn't have to
+// discover valid snappy framing before reaching the circuit parser.
+fuzz_target!(|input: (u8, &[u8])| {
+    let (version, payload) = input;
+    let mut file = vec![7 + version % 3];
+    file.extend(snap::raw::Encoder::new().compress_vec(payload).unwrap());
+    if let Ok(cdf) = CircuitDataFile::from_bytes(&file) {
+        cdf.to_bytes().unwrap();
+    }
+});
@@ -0,0 +1,24 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tc_save_parser::{CircuitDataFile, CircuitDataVersion, v9};
+
+fuzz_target!(|data: v9::CircuitData| {
+    let cdf = CircuitDataFile {
+        version: 9,
+        circuit: CircuitDataVersion::V9(data.clone()),
+    };
+    let bytes = cdf.to_bytes().unwrap();
+    let back = CircuitDataFile::from_bytes(&bytes).unwrap();
+    match back.circuit {
+        CircuitDataVersion::V9(back) => assert_eq!(back, data),
+        other => panic!("read back as {:?}", other),
+    }
+    assert_eq!(
+        CircuitDataFile::from_bytes(&bytes)
+            .unwrap()
+            .to_bytes()
+            .unwrap(),
+        bytes
+    );
+});
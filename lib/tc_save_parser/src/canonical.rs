@@ -0,0 +1,88 @@
+//! Putting circuits in a canonical form, so saving the same circuit twice
+//! writes the same bytes however the game last ordered it.
+//!
+//! Components are sorted by `permanent_id`, then position. Each wire starts
+//! at the lesser of its two ends, comparing `x` then `y`, and runs in as few
+//! segments as it can: collinear segments are merged, runs longer than
+//! [`MAX_SEGMENT_LENGTH`] split greedily, and zero-length segments dropped
+//! apart from the terminator. Wires are then sorted by their ends. Only the
+//! order and encoding change, never what is connected where.
+//!
+//! [`MAX_SEGMENT_LENGTH`]: crate::MAX_SEGMENT_LENGTH
+
+use crate::{
+    CDString, Point, Wire, WireBuilder, WireDirection, model, transform::Rotation, v7, v8, v9,
+};
+
+fn point_key(point: Point) -> (i16, i16) {
+    (point.x, point.y)
+}
+
+/// Ends, colour, comment and encoded segments.
+type WireKey = ((i16, i16), (i16, i16), u8, CDString, Vec<u8>);
+
+impl Wire {
+    /// Rewrites the wire in its canonical form, covering the same cells.
+    pub fn canonicalize(&mut self) {
+        let mut runs: Vec<(WireDirection, u32)> = Vec::new();
+        for segment in self.segments.iter().filter(|s| s.length() != 0) {
+            match runs.last_mut() {
+                Some((direction, length)) if *direction == segment.direction() => {
+                    *length += u32::from(segment.length());
+                }
+                _ => runs.push((segment.direction(), u32::from(segment.length()))),
+            }
+        }
+        let end = self.end();
+        if point_key(end) < point_key(self.start) {
+            self.start = end;
+            runs.reverse();
+            for (direction, _) in &mut runs {
+                *direction = direction.rotated(Rotation::R180);
+            }
+        }
+        let mut builder = WireBuilder::new(self.start);
+        for (direction, length) in runs {
+            builder = builder.run(direction, length);
+        }
+        self.segments = builder.build().segments;
+    }
+
+    /// Orders canonical wires by their ends, then by everything else.
+    fn sort_key(&self) -> WireKey {
+        (
+            point_key(self.start),
+            point_key(self.end()),
+            self.color,
+            self.comment.clone(),
+            self.segments
+                .iter()
+                .map(|s| s.into_bytes()[0])
+                .collect::<Vec<_>>(),
+        )
+    }
+}
+
+macro_rules! impl_canonicalize {
+    ($($circuit:ty),*) => {$(
+        impl $circuit {
+            /// Sorts components and wires and normalises every wire, leaving
+            /// the circuit unchanged apart from its order and encoding.
+            pub fn canonicalize(&mut self) {
+                self.components
+                    .sort_by_key(|c| (c.permanent_id, point_key(c.position)));
+                for wire in &mut self.wires {
+                    wire.canonicalize();
+                }
+                self.wires.sort_by_cached_key(Wire::sort_key);
+            }
+        }
+    )*};
+}
+
+impl_canonicalize!(
+    v7::CircuitData,
+    v8::CircuitData,
+    v9::CircuitData,
+    model::Circuit
+);
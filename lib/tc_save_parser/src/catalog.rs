@@ -0,0 +1,692 @@
+//! Static per-kind metadata: display name, category, footprint and pins.
+//!
+//! Every [`Kind`] variant carries an `#[info(...)]` attribute that the
+//! `kind_mapper` macro turns into [`Kind::info`]. Geometry is in grid cells
+//! relative to a component's `position`, for an unrotated component.
+//!
+//! The footprints and pin positions are approximate and haven't been checked
+//! against saves from the game. Kinds whose pins aren't mapped at all, such as
+//! `Console`, `PixelScreen`, `Assembler` and `FrontPanel`, use the empty
+//! `NONE` layout and have no pins, so nothing connects to them in a netlist.
+
+use crate::{Kind, Point};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Category {
+    Special,
+    Io,
+    Logic,
+    Arithmetic,
+    Memory,
+    Plumbing,
+    Probe,
+    Device,
+    Custom,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PinDirection {
+    Input,
+    Output,
+}
+
+/// Bit width of a pin, either fixed or derived from the component's
+/// `word_size`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Width {
+    Bits(u8),
+    Word,
+    /// `word_size` divided by this.
+    WordDiv(u8),
+    /// `word_size` multiplied by this.
+    WordMul(u8),
+}
+
+impl Width {
+    /// Resolves to a bit count for a component with `word_size`. Word sizes
+    /// outside `1..=64`, including the 0 of a component that never had one
+    /// set, are treated as 64, so a word pin never drops bits that a
+    /// narrower guess would mask off.
+    pub fn bits(self, word_size: i64) -> u32 {
+        let word = match word_size {
+            1..=64 => word_size as u32,
+            _ => 64,
+        };
+        match self {
+            Width::Bits(n) => u32::from(n),
+            Width::Word => word,
+            Width::WordDiv(n) => (word / u32::from(n)).max(1),
+            Width::WordMul(n) => word * u32::from(n),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pin {
+    pub name: &'static str,
+    pub offset: Point,
+    pub direction: PinDirection,
+    pub width: Width,
+}
+
+impl Pin {
+    const fn input(name: &'static str, x: i16, y: i16, width: Width) -> Self {
+        Self {
+            name,
+            offset: Point::new(x, y),
+            direction: PinDirection::Input,
+            width,
+        }
+    }
+
+    const fn output(name: &'static str, x: i16, y: i16, width: Width) -> Self {
+        Self {
+            name,
+            offset: Point::new(x, y),
+            direction: PinDirection::Output,
+            width,
+        }
+    }
+}
+
+/// The cells a component covers: `width` by `height` cells with the top left
+/// one at `origin`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Footprint {
+    pub origin: Point,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl Footprint {
+    const fn new(x: i16, y: i16, width: u16, height: u16) -> Self {
+        Self {
+            origin: Point::new(x, y),
+            width,
+            height,
+        }
+    }
+
+    pub fn contains(&self, offset: Point) -> bool {
+        let (x, y) = (offset.x - self.origin.x, offset.y - self.origin.y);
+        (0..self.width as i16).contains(&x) && (0..self.height as i16).contains(&y)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Layout {
+    pub footprint: Footprint,
+    pub pins: &'static [Pin],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KindInfo {
+    pub name: &'static str,
+    pub category: Category,
+    pub layout: &'static Layout,
+}
+
+impl KindInfo {
+    pub(crate) const UNMAPPED: KindInfo = KindInfo {
+        name: "Unknown",
+        category: Category::Special,
+        layout: &layout::NONE,
+    };
+}
+
+impl Kind {
+    pub fn name(&self) -> &'static str {
+        self.info().name
+    }
+
+    pub fn category(&self) -> Category {
+        self.info().category
+    }
+
+    pub fn footprint(&self) -> Footprint {
+        self.info().layout.footprint
+    }
+
+    /// Pins in a fixed order, inputs first. `Custom` components have none
+    /// here; their pins come from the inputs and outputs of the circuit they
+    /// refer to.
+    pub fn pins(&self) -> &'static [Pin] {
+        self.info().layout.pins
+    }
+
+    pub fn pin(&self, name: &str) -> Option<&'static Pin> {
+        self.pins().iter().find(|p| p.name == name)
+    }
+
+    /// The pins holding the value of an [input](Kind::is_input) or
+    /// [output](Kind::is_output) component, lowest bits first. A switched
+    /// output also has an `enable` pin, which isn't one of them; while it is
+    /// low the output has no value, which the exporters write as 0.
+    pub fn port_pins(&self) -> impl Iterator<Item = &'static Pin> {
+        self.pins().iter().filter(|p| p.name != "enable")
+    }
+}
+
+/// Pin layouts shared between kinds. Inputs sit on the left edge and outputs
+/// on the right, top to bottom.
+pub(crate) mod layout {
+    use super::{Footprint, Layout, Pin, Width};
+
+    /// A placeholder for kinds whose pins aren't mapped.
+    pub(crate) const NONE: Layout = Layout {
+        footprint: Footprint::new(-1, 0, 3, 1),
+        pins: &[],
+    };
+
+    pub(crate) const SOURCE_BIT: Layout = Layout {
+        footprint: Footprint::new(-1, 0, 3, 1),
+        pins: &[Pin::output("out", 1, 0, Width::Bits(1))],
+    };
+
+    pub(crate) const SOURCE_WORD: Layout = Layout {
+        footprint: Footprint::new(-1, 0, 3, 1),
+        pins: &[Pin::output("out", 1, 0, Width::Word)],
+    };
+
+    pub(crate) const SINK_BIT: Layout = Layout {
+        footprint: Footprint::new(-1, 0, 3, 1),
+        pins: &[Pin::input("in", -1, 0, Width::Bits(1))],
+    };
+
+    pub(crate) const SINK_WORD: Layout = Layout {
+        footprint: Footprint::new(-1, 0, 3, 1),
+        pins: &[Pin::input("in", -1, 0, Width::Word)],
+    };
+
+    pub(crate) const GATE1: Layout = Layout {
+        footprint: Footprint::new(-1, 0, 3, 1),
+        pins: &[
+            Pin::input("a", -1, 0, Width::Bits(1)),
+            Pin::output("out", 1, 0, Width::Bits(1)),
+        ],
+    };
+
+    pub(crate) const GATE2: Layout = Layout {
+        footprint: Footprint::new(-1, 0, 3, 2),
+        pins: &[
+            Pin::input("a", -1, 0, Width::Bits(1)),
+            Pin::input("b", -1, 1, Width::Bits(1)),
+            Pin::output("out", 1, 0, Width::Bits(1)),
+        ],
+    };
+
+    pub(crate) const GATE3: Layout = Layout {
+        footprint: Footprint::new(-1, -1, 3, 3),
+        pins: &[
+            Pin::input("a", -1, -1, Width::Bits(1)),
+            Pin::input("b", -1, 0, Width::Bits(1)),
+            Pin::input("c", -1, 1, Width::Bits(1)),
+            Pin::output("out", 1, -1, Width::Bits(1)),
+        ],
+    };
+
+    pub(crate) const SWITCH_BIT: Layout = Layout {
+        footprint: Footprint::new(-1, 0, 3, 2),
+        pins: &[
+            Pin::input("enable", -1, 0, Width::Bits(1)),
+            Pin::input("a", -1, 1, Width::Bits(1)),
+            Pin::output("out", 1, 0, Width::Bits(1)),
+        ],
+    };
+
+    pub(crate) const REGISTER_BIT: Layout = Layout {
+        footprint: Footprint::new(-1, -1, 3, 3),
+        pins: &[
+            Pin::input("load", -1, -1, Width::Bits(1)),
+            Pin::input("save", -1, 0, Width::Bits(1)),
+            Pin::input("value", -1, 1, Width::Bits(1)),
+            Pin::output("out", 1, -1, Width::Bits(1)),
+        ],
+    };
+
+    pub(crate) const FULL_ADDER: Layout = Layout {
+        footprint: Footprint::new(-1, -1, 3, 3),
+        pins: &[
+            Pin::input("a", -1, -1, Width::Bits(1)),
+            Pin::input("b", -1, 0, Width::Bits(1)),
+            Pin::input("carry_in", -1, 1, Width::Bits(1)),
+            Pin::output("sum", 1, -1, Width::Bits(1)),
+            Pin::output("carry_out", 1, 0, Width::Bits(1)),
+        ],
+    };
+
+    pub(crate) const MAKER_BIT2: Layout = Layout {
+        footprint: Footprint::new(-1, 0, 3, 2),
+        pins: &[
+            Pin::input("in0", -1, 0, Width::Bits(1)),
+            Pin::input("in1", -1, 1, Width::Bits(1)),
+            Pin::output("out", 1, 0, Width::Bits(2)),
+        ],
+    };
+
+    pub(crate) const MAKER_BIT4: Layout = Layout {
+        footprint: Footprint::new(-1, -1, 3, 4),
+        pins: &[
+            Pin::input("in0", -1, -1, Width::Bits(1)),
+            Pin::input("in1", -1, 0, Width::Bits(1)),
+            Pin::input("in2", -1, 1, Width::Bits(1)),
+            Pin::input("in3", -1, 2, Width::Bits(1)),
+            Pin::output("out", 1, -1, Width::Bits(4)),
+        ],
+    };
+
+    pub(crate) const MAKER_BIT8: Layout = Layout {
+        footprint: Footprint::new(-1, -3, 3, 8),
+        pins: &[
+            Pin::input("in0", -1, -3, Width::Bits(1)),
+            Pin::input("in1", -1, -2, Width::Bits(1)),
+            Pin::input("in2", -1, -1, Width::Bits(1)),
+            Pin::input("in3", -1, 0, Width::Bits(1)),
+            Pin::input("in4", -1, 1, Width::Bits(1)),
+            Pin::input("in5", -1, 2, Width::Bits(1)),
+            Pin::input("in6", -1, 3, Width::Bits(1)),
+            Pin::input("in7", -1, 4, Width::Bits(1)),
+            Pin::output("out", 1, -3, Width::Bits(8)),
+        ],
+    };
+
+    pub(crate) const SPLITTER_BIT2: Layout = Layout {
+        footprint: Footprint::new(-1, 0, 3, 2),
+        pins: &[
+            Pin::input("in", -1, 0, Width::Bits(2)),
+            Pin::output("out0", 1, 0, Width::Bits(1)),
+            Pin::output("out1", 1, 1, Width::Bits(1)),
+        ],
+    };
+
+    pub(crate) const SPLITTER_BIT4: Layout = Layout {
+        footprint: Footprint::new(-1, -1, 3, 4),
+        pins: &[
+            Pin::input("in", -1, -1, Width::Bits(4)),
+            Pin::output("out0", 1, -1, Width::Bits(1)),
+            Pin::output("out1", 1, 0, Width::Bits(1)),
+            Pin::output("out2", 1, 1, Width::Bits(1)),
+            Pin::output("out3", 1, 2, Width::Bits(1)),
+        ],
+    };
+
+    pub(crate) const SPLITTER_BIT8: Layout = Layout {
+        footprint: Footprint::new(-1, -3, 3, 8),
+        pins: &[
+            Pin::input("in", -1, -3, Width::Bits(8)),
+            Pin::output("out0", 1, -3, Width::Bits(1)),
+            Pin::output("out1", 1, -2, Width::Bits(1)),
+            Pin::output("out2", 1, -1, Width::Bits(1)),
+            Pin::output("out3", 1, 0, Width::Bits(1)),
+            Pin::output("out4", 1, 1, Width::Bits(1)),
+            Pin::output("out5", 1, 2, Width::Bits(1)),
+            Pin::output("out6", 1, 3, Width::Bits(1)),
+            Pin::output("out7", 1, 4, Width::Bits(1)),
+        ],
+    };
+
+    pub(crate) const WORD1: Layout = Layout {
+        footprint: Footprint::new(-1, 0, 3, 1),
+        pins: &[
+            Pin::input("a", -1, 0, Width::Word),
+            Pin::output("out", 1, 0, Width::Word),
+        ],
+    };
+
+    pub(crate) const WORD2: Layout = Layout {
+        footprint: Footprint::new(-1, 0, 3, 2),
+        pins: &[
+            Pin::input("a", -1, 0, Width::Word),
+            Pin::input("b", -1, 1, Width::Word),
+            Pin::output("out", 1, 0, Width::Word),
+        ],
+    };
+
+    pub(crate) const SWITCH_WORD: Layout = Layout {
+        footprint: Footprint::new(-1, 0, 3, 2),
+        pins: &[
+            Pin::input("enable", -1, 0, Width::Bits(1)),
+            Pin::input("a", -1, 1, Width::Word),
+            Pin::output("out", 1, 0, Width::Word),
+        ],
+    };
+
+    pub(crate) const COMPARE: Layout = Layout {
+        footprint: Footprint::new(-1, 0, 3, 2),
+        pins: &[
+            Pin::input("a", -1, 0, Width::Word),
+            Pin::input("b", -1, 1, Width::Word),
+            Pin::output("out", 1, 0, Width::Bits(1)),
+        ],
+    };
+
+    pub(crate) const ADD: Layout = Layout {
+        footprint: Footprint::new(-1, -1, 3, 3),
+        pins: &[
+            Pin::input("carry_in", -1, -1, Width::Bits(1)),
+            Pin::input("a", -1, 0, Width::Word),
+            Pin::input("b", -1, 1, Width::Word),
+            Pin::output("out", 1, -1, Width::Word),
+            Pin::output("carry_out", 1, 0, Width::Bits(1)),
+        ],
+    };
+
+    pub(crate) const MUL: Layout = Layout {
+        footprint: Footprint::new(-1, 0, 3, 2),
+        pins: &[
+            Pin::input("a", -1, 0, Width::Word),
+            Pin::input("b", -1, 1, Width::Word),
+            Pin::output("out", 1, 0, Width::Word),
+            Pin::output("high", 1, 1, Width::Word),
+        ],
+    };
+
+    pub(crate) const DIV: Layout = Layout {
+        footprint: Footprint::new(-1, 0, 3, 2),
+        pins: &[
+            Pin::input("a", -1, 0, Width::Word),
+            Pin::input("b", -1, 1, Width::Word),
+            Pin::output("quotient", 1, 0, Width::Word),
+            Pin::output("remainder", 1, 1, Width::Word),
+        ],
+    };
+
+    pub(crate) const COUNTER: Layout = Layout {
+        footprint: Footprint::new(-1, 0, 3, 2),
+        pins: &[
+            Pin::input("overwrite", -1, 0, Width::Bits(1)),
+            Pin::input("value", -1, 1, Width::Word),
+            Pin::output("out", 1, 0, Width::Word),
+        ],
+    };
+
+    pub(crate) const REGISTER_WORD: Layout = Layout {
+        footprint: Footprint::new(-1, -1, 3, 3),
+        pins: &[
+            Pin::input("load", -1, -1, Width::Bits(1)),
+            Pin::input("save", -1, 0, Width::Bits(1)),
+            Pin::input("value", -1, 1, Width::Word),
+            Pin::output("out", 1, -1, Width::Word),
+        ],
+    };
+
+    pub(crate) const MUX: Layout = Layout {
+        footprint: Footprint::new(-1, -1, 3, 3),
+        pins: &[
+            Pin::input("select", -1, -1, Width::Bits(1)),
+            Pin::input("a", -1, 0, Width::Word),
+            Pin::input("b", -1, 1, Width::Word),
+            Pin::output("out", 1, -1, Width::Word),
+        ],
+    };
+
+    pub(crate) const DECODER1: Layout = Layout {
+        footprint: Footprint::new(-1, 0, 3, 2),
+        pins: &[
+            Pin::input("a", -1, 0, Width::Bits(1)),
+            Pin::output("out0", 1, 0, Width::Bits(1)),
+            Pin::output("out1", 1, 1, Width::Bits(1)),
+        ],
+    };
+
+    pub(crate) const DECODER2: Layout = Layout {
+        footprint: Footprint::new(-1, -1, 3, 4),
+        pins: &[
+            Pin::input("a", -1, -1, Width::Bits(1)),
+            Pin::input("b", -1, 0, Width::Bits(1)),
+            Pin::output("out0", 1, -1, Width::Bits(1)),
+            Pin::output("out1", 1, 0, Width::Bits(1)),
+            Pin::output("out2", 1, 1, Width::Bits(1)),
+            Pin::output("out3", 1, 2, Width::Bits(1)),
+        ],
+    };
+
+    pub(crate) const DECODER3: Layout = Layout {
+        footprint: Footprint::new(-1, -3, 3, 8),
+        pins: &[
+            Pin::input("a", -1, -3, Width::Bits(1)),
+            Pin::input("b", -1, -2, Width::Bits(1)),
+            Pin::input("c", -1, -1, Width::Bits(1)),
+            Pin::output("out0", 1, -3, Width::Bits(1)),
+            Pin::output("out1", 1, -2, Width::Bits(1)),
+            Pin::output("out2", 1, -1, Width::Bits(1)),
+            Pin::output("out3", 1, 0, Width::Bits(1)),
+            Pin::output("out4", 1, 1, Width::Bits(1)),
+            Pin::output("out5", 1, 2, Width::Bits(1)),
+            Pin::output("out6", 1, 3, Width::Bits(1)),
+            Pin::output("out7", 1, 4, Width::Bits(1)),
+        ],
+    };
+
+    pub(crate) const MAKER_WORD2: Layout = Layout {
+        footprint: Footprint::new(-1, 0, 3, 2),
+        pins: &[
+            Pin::input("in0", -1, 0, Width::WordDiv(2)),
+            Pin::input("in1", -1, 1, Width::WordDiv(2)),
+            Pin::output("out", 1, 0, Width::Word),
+        ],
+    };
+
+    pub(crate) const MAKER_WORD4: Layout = Layout {
+        footprint: Footprint::new(-1, -1, 3, 4),
+        pins: &[
+            Pin::input("in0", -1, -1, Width::WordDiv(4)),
+            Pin::input("in1", -1, 0, Width::WordDiv(4)),
+            Pin::input("in2", -1, 1, Width::WordDiv(4)),
+            Pin::input("in3", -1, 2, Width::WordDiv(4)),
+            Pin::output("out", 1, -1, Width::Word),
+        ],
+    };
+
+    pub(crate) const MAKER_WORD8: Layout = Layout {
+        footprint: Footprint::new(-1, -3, 3, 8),
+        pins: &[
+            Pin::input("in0", -1, -3, Width::WordDiv(8)),
+            Pin::input("in1", -1, -2, Width::WordDiv(8)),
+            Pin::input("in2", -1, -1, Width::WordDiv(8)),
+            Pin::input("in3", -1, 0, Width::WordDiv(8)),
+            Pin::input("in4", -1, 1, Width::WordDiv(8)),
+            Pin::input("in5", -1, 2, Width::WordDiv(8)),
+            Pin::input("in6", -1, 3, Width::WordDiv(8)),
+            Pin::input("in7", -1, 4, Width::WordDiv(8)),
+            Pin::output("out", 1, -3, Width::Word),
+        ],
+    };
+
+    pub(crate) const SPLITTER_WORD2: Layout = Layout {
+        footprint: Footprint::new(-1, 0, 3, 2),
+        pins: &[
+            Pin::input("in", -1, 0, Width::Word),
+            Pin::output("out0", 1, 0, Width::WordDiv(2)),
+            Pin::output("out1", 1, 1, Width::WordDiv(2)),
+        ],
+    };
+
+    pub(crate) const SPLITTER_WORD4: Layout = Layout {
+        footprint: Footprint::new(-1, -1, 3, 4),
+        pins: &[
+            Pin::input("in", -1, -1, Width::Word),
+            Pin::output("out0", 1, -1, Width::WordDiv(4)),
+            Pin::output("out1", 1, 0, Width::WordDiv(4)),
+            Pin::output("out2", 1, 1, Width::WordDiv(4)),
+            Pin::output("out3", 1, 2, Width::WordDiv(4)),
+        ],
+    };
+
+    pub(crate) const SPLITTER_WORD8: Layout = Layout {
+        footprint: Footprint::new(-1, -3, 3, 8),
+        pins: &[
+            Pin::input("in", -1, -3, Width::Word),
+            Pin::output("out0", 1, -3, Width::WordDiv(8)),
+            Pin::output("out1", 1, -2, Width::WordDiv(8)),
+            Pin::output("out2", 1, -1, Width::WordDiv(8)),
+            Pin::output("out3", 1, 0, Width::WordDiv(8)),
+            Pin::output("out4", 1, 1, Width::WordDiv(8)),
+            Pin::output("out5", 1, 2, Width::WordDiv(8)),
+            Pin::output("out6", 1, 3, Width::WordDiv(8)),
+            Pin::output("out7", 1, 4, Width::WordDiv(8)),
+        ],
+    };
+
+    pub(crate) const CONCATENATOR2: Layout = Layout {
+        footprint: Footprint::new(-1, 0, 3, 2),
+        pins: &[
+            Pin::input("in0", -1, 0, Width::Word),
+            Pin::input("in1", -1, 1, Width::Word),
+            Pin::output("out", 1, 0, Width::WordMul(2)),
+        ],
+    };
+
+    pub(crate) const CONCATENATOR4: Layout = Layout {
+        footprint: Footprint::new(-1, -1, 3, 4),
+        pins: &[
+            Pin::input("in0", -1, -1, Width::Word),
+            Pin::input("in1", -1, 0, Width::Word),
+            Pin::input("in2", -1, 1, Width::Word),
+            Pin::input("in3", -1, 2, Width::Word),
+            Pin::output("out", 1, -1, Width::WordMul(4)),
+        ],
+    };
+
+    pub(crate) const CONCATENATOR8: Layout = Layout {
+        footprint: Footprint::new(-1, -3, 3, 8),
+        pins: &[
+            Pin::input("in0", -1, -3, Width::Word),
+            Pin::input("in1", -1, -2, Width::Word),
+            Pin::input("in2", -1, -1, Width::Word),
+            Pin::input("in3", -1, 0, Width::Word),
+            Pin::input("in4", -1, 1, Width::Word),
+            Pin::input("in5", -1, 2, Width::Word),
+            Pin::input("in6", -1, 3, Width::Word),
+            Pin::input("in7", -1, 4, Width::Word),
+            Pin::output("out", 1, -3, Width::WordMul(8)),
+        ],
+    };
+
+    pub(crate) const RAM: Layout = Layout {
+        footprint: Footprint::new(-2, -1, 5, 4),
+        pins: &[
+            Pin::input("load", -2, -1, Width::Bits(1)),
+            Pin::input("save", -2, 0, Width::Bits(1)),
+            Pin::input("address", -2, 1, Width::Word),
+            Pin::input("value", -2, 2, Width::Word),
+            Pin::output("out", 2, -1, Width::Word),
+        ],
+    };
+
+    pub(crate) const RAM_DUAL_LOAD: Layout = Layout {
+        footprint: Footprint::new(-2, -2, 5, 6),
+        pins: &[
+            Pin::input("load_a", -2, -2, Width::Bits(1)),
+            Pin::input("address_a", -2, -1, Width::Word),
+            Pin::input("load_b", -2, 0, Width::Bits(1)),
+            Pin::input("address_b", -2, 1, Width::Word),
+            Pin::input("save", -2, 2, Width::Bits(1)),
+            Pin::input("value", -2, 3, Width::Word),
+            Pin::output("out_a", 2, -2, Width::Word),
+            Pin::output("out_b", 2, -1, Width::Word),
+        ],
+    };
+
+    pub(crate) const ROM: Layout = Layout {
+        footprint: Footprint::new(-2, 0, 5, 2),
+        pins: &[
+            Pin::input("load", -2, 0, Width::Bits(1)),
+            Pin::input("address", -2, 1, Width::Word),
+            Pin::output("out", 2, 0, Width::Word),
+        ],
+    };
+
+    pub(crate) const FILE_LOADER: Layout = Layout {
+        footprint: Footprint::new(-2, 0, 5, 1),
+        pins: &[
+            Pin::input("address", -2, 0, Width::Word),
+            Pin::output("out", 2, 0, Width::Word),
+        ],
+    };
+
+    pub(crate) const SOURCE_PINS2: Layout = Layout {
+        footprint: Footprint::new(-1, 0, 3, 2),
+        pins: &[
+            Pin::output("out0", 1, 0, Width::Bits(1)),
+            Pin::output("out1", 1, 1, Width::Bits(1)),
+        ],
+    };
+
+    pub(crate) const SOURCE_PINS3: Layout = Layout {
+        footprint: Footprint::new(-1, -1, 3, 3),
+        pins: &[
+            Pin::output("out0", 1, -1, Width::Bits(1)),
+            Pin::output("out1", 1, 0, Width::Bits(1)),
+            Pin::output("out2", 1, 1, Width::Bits(1)),
+        ],
+    };
+
+    pub(crate) const SOURCE_PINS4: Layout = Layout {
+        footprint: Footprint::new(-1, -1, 3, 4),
+        pins: &[
+            Pin::output("out0", 1, -1, Width::Bits(1)),
+            Pin::output("out1", 1, 0, Width::Bits(1)),
+            Pin::output("out2", 1, 1, Width::Bits(1)),
+            Pin::output("out3", 1, 2, Width::Bits(1)),
+        ],
+    };
+
+    pub(crate) const SINK_PINS2: Layout = Layout {
+        footprint: Footprint::new(-1, 0, 3, 2),
+        pins: &[
+            Pin::input("in0", -1, 0, Width::Bits(1)),
+            Pin::input("in1", -1, 1, Width::Bits(1)),
+        ],
+    };
+
+    pub(crate) const SINK_PINS3: Layout = Layout {
+        footprint: Footprint::new(-1, -1, 3, 3),
+        pins: &[
+            Pin::input("in0", -1, -1, Width::Bits(1)),
+            Pin::input("in1", -1, 0, Width::Bits(1)),
+            Pin::input("in2", -1, 1, Width::Bits(1)),
+        ],
+    };
+
+    pub(crate) const SINK_PINS4: Layout = Layout {
+        footprint: Footprint::new(-1, -1, 3, 4),
+        pins: &[
+            Pin::input("in0", -1, -1, Width::Bits(1)),
+            Pin::input("in1", -1, 0, Width::Bits(1)),
+            Pin::input("in2", -1, 1, Width::Bits(1)),
+            Pin::input("in3", -1, 2, Width::Bits(1)),
+        ],
+    };
+
+    pub(crate) const SWITCHED_SINK: Layout = Layout {
+        footprint: Footprint::new(-1, 0, 3, 2),
+        pins: &[
+            Pin::input("enable", -1, 0, Width::Bits(1)),
+            Pin::input("value", -1, 1, Width::Word),
+        ],
+    };
+
+    pub(crate) const HALT: Layout = Layout {
+        footprint: Footprint::new(-1, 0, 3, 1),
+        pins: &[Pin::input("enable", -1, 0, Width::Bits(1))],
+    };
+
+    pub(crate) const KEYBOARD: Layout = Layout {
+        footprint: Footprint::new(-1, 0, 3, 1),
+        pins: &[
+            Pin::input("enable", -1, 0, Width::Bits(1)),
+            Pin::output("out", 1, 0, Width::Bits(8)),
+        ],
+    };
+
+    pub(crate) const TIME: Layout = Layout {
+        footprint: Footprint::new(-1, 0, 3, 1),
+        pins: &[Pin::output("out", 1, 0, Width::Bits(64))],
+    };
+
+    pub(crate) const SEGMENT_DISPLAY: Layout = Layout {
+        footprint: Footprint::new(-1, 0, 3, 1),
+        pins: &[Pin::input("in", -1, 0, Width::Bits(8))],
+    };
+}
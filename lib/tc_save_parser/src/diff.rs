@@ -0,0 +1,422 @@
+//! Semantic differences between two versions of a circuit.
+//!
+//! Components are matched by `permanent_id`. A matched pair whose kind
+//! differs counts as one component removed and another added. Connections
+//! come from the [netlist](crate::netlist) of each version rather than wire
+//! geometry, so rerouting a wire changes nothing while moving its end to
+//! another pin shows as that pin leaving one net and joining another. Pins
+//! are named by component id and pin name.
+//!
+//! A net is named by one of its pins: the first of those that stay together
+//! on the net holding most of them in the other version. Each pin that isn't
+//! with that pin in the other version has left the net, or joined it, so a
+//! change costs a line per pin that moved rather than one per pair of pins.
+//!
+//! [`CircuitDiff`] prints as one line per change, and serializes as JSON
+//! with the `serde` feature. [`diff_files`] and [`diff_saves`] compare saves
+//! of any version, upgraded to the latest.
+
+use std::{
+    cmp::Reverse,
+    collections::{BTreeSet, HashMap},
+    fmt,
+    path::Path,
+    rc::Rc,
+};
+
+use crate::{
+    CircuitDataFile, Error, Kind, Point,
+    netlist::{Netlist, NetlistBuilder, PinDef},
+    v9,
+};
+
+/// A pin, by the `permanent_id` of its component and its name.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PinId {
+    pub component: u64,
+    pub pin: String,
+}
+
+impl fmt::Display for PinId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}.{}", self.component, self.pin)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(tag = "change", rename_all = "snake_case"))]
+pub enum Change {
+    Added {
+        component: u64,
+        kind: Kind,
+        position: Point,
+    },
+    Removed {
+        component: u64,
+        kind: Kind,
+        position: Point,
+    },
+    Moved {
+        component: u64,
+        kind: Kind,
+        from: Point,
+        to: Point,
+    },
+    /// Rotations are in clockwise quarter turns, as stored.
+    Rotated {
+        component: u64,
+        kind: Kind,
+        from: u8,
+        to: u8,
+    },
+    CustomString {
+        component: u64,
+        kind: Kind,
+        from: String,
+        to: String,
+    },
+    Settings {
+        component: u64,
+        kind: Kind,
+        from: Vec<u64>,
+        to: Vec<u64>,
+    },
+    WordSize {
+        component: u64,
+        kind: Kind,
+        from: i64,
+        to: i64,
+    },
+    /// `pin` is now on the net named by `net`, and wasn't before.
+    Joined { pin: PinId, net: PinId },
+    /// `pin` was on the net named by `net`, and no longer is.
+    Left { pin: PinId, net: PinId },
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let subject = |component: &u64, kind: &Kind| format!("#{} {}", component, kind.name());
+        let point = |p: &Point| format!("({}, {})", p.x, p.y);
+        match self {
+            Change::Added {
+                component,
+                kind,
+                position,
+            } => write!(f, "+ {} at {}", subject(component, kind), point(position)),
+            Change::Removed {
+                component,
+                kind,
+                position,
+            } => write!(f, "- {} at {}", subject(component, kind), point(position)),
+            Change::Moved {
+                component,
+                kind,
+                from,
+                to,
+            } => write!(
+                f,
+                "~ {} moved from {} to {}",
+                subject(component, kind),
+                point(from),
+                point(to)
+            ),
+            Change::Rotated {
+                component,
+                kind,
+                from,
+                to,
+            } => write!(
+                f,
+                "~ {} rotated from {}° to {}°",
+                subject(component, kind),
+                u16::from(*from % 4) * 90,
+                u16::from(*to % 4) * 90
+            ),
+            Change::CustomString {
+                component,
+                kind,
+                from,
+                to,
+            } => write!(
+                f,
+                "~ {} custom_string {:?} -> {:?}",
+                subject(component, kind),
+                from,
+                to
+            ),
+            Change::Settings {
+                component,
+                kind,
+                from,
+                to,
+            } => write!(
+                f,
+                "~ {} settings {:?} -> {:?}",
+                subject(component, kind),
+                from,
+                to
+            ),
+            Change::WordSize {
+                component,
+                kind,
+                from,
+                to,
+            } => write!(
+                f,
+                "~ {} word_size {} -> {}",
+                subject(component, kind),
+                from,
+                to
+            ),
+            Change::Joined { pin, net } => write!(f, "+ {} joined the net of {}", pin, net),
+            Change::Left { pin, net } => write!(f, "- {} left the net of {}", pin, net),
+        }
+    }
+}
+
+/// Every change from one circuit to another: removed components first, then
+/// added ones, then changes to kept components in the new order, then pins
+/// leaving and joining nets.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CircuitDiff {
+    pub changes: Vec<Change>,
+}
+
+impl CircuitDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("diffs serialize as JSON")
+    }
+}
+
+impl fmt::Display for CircuitDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in &self.changes {
+            writeln!(f, "{}", change)?;
+        }
+        Ok(())
+    }
+}
+
+pub struct Differ<'a> {
+    old: &'a v9::CircuitData,
+    new: &'a v9::CircuitData,
+    old_netlist: NetlistBuilder<'a>,
+    new_netlist: NetlistBuilder<'a>,
+}
+
+impl<'a> Differ<'a> {
+    pub fn new(old: &'a v9::CircuitData, new: &'a v9::CircuitData) -> Self {
+        Self {
+            old,
+            new,
+            old_netlist: NetlistBuilder::new(old),
+            new_netlist: NetlistBuilder::new(new),
+        }
+    }
+
+    /// Supplies the unrotated pins of `Custom` components in both versions.
+    /// Without them, custom components have no connections to compare.
+    pub fn custom_pins(
+        mut self,
+        resolve: impl Fn(&v9::Component) -> Option<Vec<PinDef>> + 'a,
+    ) -> Self {
+        let resolve = Rc::new(resolve);
+        let shared = Rc::clone(&resolve);
+        self.old_netlist = self.old_netlist.custom_pins(move |c| resolve(c));
+        self.new_netlist = self.new_netlist.custom_pins(move |c| shared(c));
+        self
+    }
+
+    pub fn diff(self) -> CircuitDiff {
+        let old_by_id = by_id(self.old);
+        let new_by_id = by_id(self.new);
+        // Pairs with the same id but a different kind are replacements.
+        let matched = |id: u64| match (old_by_id.get(&id), new_by_id.get(&id)) {
+            (Some(old), Some(new)) => old.kind == new.kind,
+            _ => false,
+        };
+
+        let mut changes = Vec::new();
+        for old in &self.old.components {
+            if !matched(old.permanent_id) {
+                changes.push(Change::Removed {
+                    component: old.permanent_id,
+                    kind: old.kind,
+                    position: old.position,
+                });
+            }
+        }
+        for new in &self.new.components {
+            if !matched(new.permanent_id) {
+                changes.push(Change::Added {
+                    component: new.permanent_id,
+                    kind: new.kind,
+                    position: new.position,
+                });
+            }
+        }
+        for new in &self.new.components {
+            if matched(new.permanent_id) {
+                component_changes(old_by_id[&new.permanent_id], new, &mut changes);
+            }
+        }
+
+        let old_nets = Nets::new(self.old, &self.old_netlist.build());
+        let new_nets = Nets::new(self.new, &self.new_netlist.build());
+        for (pin, net) in old_nets.moved(&new_nets) {
+            changes.push(Change::Left { pin, net });
+        }
+        for (pin, net) in new_nets.moved(&old_nets) {
+            changes.push(Change::Joined { pin, net });
+        }
+        CircuitDiff { changes }
+    }
+}
+
+/// Compares `old` and `new` without resolving custom component pins.
+pub fn diff(old: &v9::CircuitData, new: &v9::CircuitData) -> CircuitDiff {
+    Differ::new(old, new).diff()
+}
+
+/// Compares two saves, upgrading older versions to the latest first.
+pub fn diff_saves(old: CircuitDataFile, new: CircuitDataFile) -> Result<CircuitDiff, Error> {
+    let (old, _) = old.into_latest()?;
+    let (new, _) = new.into_latest()?;
+    Ok(diff(&old, &new))
+}
+
+/// Compares the saves at two paths, like [`diff_saves`].
+pub fn diff_files(old: impl AsRef<Path>, new: impl AsRef<Path>) -> Result<CircuitDiff, Error> {
+    diff_saves(CircuitDataFile::load(old)?, CircuitDataFile::load(new)?)
+}
+
+/// Components by `permanent_id`. Saves from the game never repeat an id; if
+/// one does, the first component with it wins.
+fn by_id(circuit: &v9::CircuitData) -> HashMap<u64, &v9::Component> {
+    let mut components = HashMap::new();
+    for component in &circuit.components {
+        components
+            .entry(component.permanent_id)
+            .or_insert(component);
+    }
+    components
+}
+
+fn component_changes(old: &v9::Component, new: &v9::Component, changes: &mut Vec<Change>) {
+    let (component, kind) = (new.permanent_id, new.kind);
+    if old.position != new.position {
+        changes.push(Change::Moved {
+            component,
+            kind,
+            from: old.position,
+            to: new.position,
+        });
+    }
+    if old.rotation() != new.rotation() {
+        changes.push(Change::Rotated {
+            component,
+            kind,
+            from: old.rotation,
+            to: new.rotation,
+        });
+    }
+    if old.custom_string != new.custom_string {
+        changes.push(Change::CustomString {
+            component,
+            kind,
+            from: old.custom_string.to_string_lossy().into_owned(),
+            to: new.custom_string.to_string_lossy().into_owned(),
+        });
+    }
+    if old.settings != new.settings {
+        changes.push(Change::Settings {
+            component,
+            kind,
+            from: old.settings.clone(),
+            to: new.settings.clone(),
+        });
+    }
+    if old.word_size != new.word_size {
+        changes.push(Change::WordSize {
+            component,
+            kind,
+            from: old.word_size,
+            to: new.word_size,
+        });
+    }
+}
+
+/// The pins of every net, sorted, and the net of every pin.
+struct Nets {
+    nets: Vec<Vec<PinId>>,
+    net_of: HashMap<PinId, usize>,
+}
+
+impl Nets {
+    fn new(circuit: &v9::CircuitData, netlist: &Netlist) -> Self {
+        let mut nets = Vec::new();
+        let mut net_of = HashMap::new();
+        for net in &netlist.nets {
+            let mut pins: Vec<PinId> = net
+                .pins
+                .iter()
+                .map(|&pin| PinId {
+                    component: circuit.components[pin.component].permanent_id,
+                    pin: netlist.pin(pin).name.clone(),
+                })
+                .collect();
+            pins.sort();
+            pins.dedup();
+            for pin in &pins {
+                net_of.insert(pin.clone(), nets.len());
+            }
+            nets.push(pins);
+        }
+        Self { nets, net_of }
+    }
+
+    /// Whether `a` and `b` are on the same net.
+    fn together(&self, a: &PinId, b: &PinId) -> bool {
+        matches!((self.net_of.get(a), self.net_of.get(b)), (Some(x), Some(y)) if x == y)
+    }
+
+    /// Pins that `other` no longer has with the pin naming their net, each
+    /// with that pin.
+    fn moved(&self, other: &Nets) -> BTreeSet<(PinId, PinId)> {
+        let mut moved = BTreeSet::new();
+        for pins in &self.nets {
+            // The net carries on as the net of `other` holding most of its
+            // pins, the first such net on a tie.
+            let mut counts: HashMap<usize, usize> = HashMap::new();
+            for pin in pins {
+                if let Some(&net) = other.net_of.get(pin) {
+                    *counts.entry(net).or_default() += 1;
+                }
+            }
+            let kept = counts
+                .into_iter()
+                .max_by_key(|&(net, count)| (count, Reverse(net)))
+                .map(|(net, _)| net);
+            let name = kept
+                .and_then(|net| pins.iter().find(|pin| other.net_of.get(*pin) == Some(&net)))
+                .or(pins.first());
+            let Some(name) = name else {
+                continue;
+            };
+            for pin in pins {
+                if pin != name && !other.together(pin, name) {
+                    moved.insert((pin.clone(), name.clone()));
+                }
+            }
+        }
+        moved
+    }
+}
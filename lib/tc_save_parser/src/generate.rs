@@ -0,0 +1,329 @@
+//! Generators for structurally valid circuits, used by the fuzz targets and
+//! property tests.
+//!
+//! Everything generated here survives a write/read cycle unchanged: strings
+//! fit their `u16` length prefix, wire segment lists end in exactly one
+//! terminator, and fields a component's kind never serializes are left at
+//! their defaults.
+
+use crate::{CDString, Kind, Point, Wire, WireDirection, WireSegment, v9};
+
+/// Longest string generated, in bytes.
+const MAX_STRING: usize = 64;
+/// Most items generated for any list.
+const MAX_ITEMS: usize = 8;
+
+fn truncate(mut s: String) -> String {
+    if s.len() > MAX_STRING {
+        let mut end = MAX_STRING;
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        s.truncate(end);
+    }
+    s
+}
+
+fn segment(direction: u8, length: u8) -> WireSegment {
+    let direction = match direction % 8 {
+        0 => WireDirection::Right,
+        1 => WireDirection::DownRight,
+        2 => WireDirection::Down,
+        3 => WireDirection::DownLeft,
+        4 => WireDirection::Left,
+        5 => WireDirection::UpLeft,
+        6 => WireDirection::Up,
+        _ => WireDirection::UpRight,
+    };
+    let mut length = length % 32;
+    // A zero-length step to the right is the list terminator.
+    if direction == WireDirection::Right && length == 0 {
+        length = 1;
+    }
+    WireSegment::new()
+        .with_direction(direction)
+        .with_length(length)
+}
+
+fn terminated(mut segments: Vec<WireSegment>) -> Vec<WireSegment> {
+    segments.push(WireSegment::new());
+    segments
+}
+
+/// Clears the sections `component.kind` doesn't serialize.
+fn normalize(mut component: v9::Component) -> v9::Component {
+    if !component.kind.has_linked_components() {
+        component.linked_components = v9::LinkedComponents::default();
+    }
+    if !component.kind.is_custom() {
+        component.custom = v9::CustomInfo::default();
+    }
+    component
+}
+
+#[cfg(feature = "arbitrary")]
+mod arbitrary_impls {
+    use arbitrary::{Arbitrary, Result, Unstructured};
+
+    use super::*;
+
+    fn list<'a, T>(
+        u: &mut Unstructured<'a>,
+        mut item: impl FnMut(&mut Unstructured<'a>) -> Result<T>,
+    ) -> Result<Vec<T>> {
+        let len = u.int_in_range(0..=MAX_ITEMS)?;
+        (0..len).map(|_| item(u)).collect()
+    }
+
+    impl<'a> Arbitrary<'a> for CDString {
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+            // Mostly text, with the odd string that isn't UTF-8.
+            if u.ratio(1, 4)? {
+                let mut bytes = Vec::<u8>::arbitrary(u)?;
+                bytes.truncate(MAX_STRING);
+                Ok(CDString::from(bytes))
+            } else {
+                Ok(CDString::from(truncate(String::arbitrary(u)?)))
+            }
+        }
+    }
+
+    impl<'a> Arbitrary<'a> for Point {
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+            Ok(Point::new(u.arbitrary()?, u.arbitrary()?))
+        }
+    }
+
+    impl<'a> Arbitrary<'a> for Kind {
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+            // Mostly known kinds, with the odd unmapped one.
+            Ok(Kind::from(u.int_in_range(0..=130)?))
+        }
+    }
+
+    impl<'a> Arbitrary<'a> for WireSegment {
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+            Ok(segment(u.arbitrary()?, u.arbitrary()?))
+        }
+    }
+
+    impl<'a> Arbitrary<'a> for Wire {
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+            Ok(Wire {
+                color: u.arbitrary()?,
+                comment: u.arbitrary()?,
+                start: u.arbitrary()?,
+                segments: terminated(list(u, WireSegment::arbitrary)?),
+            })
+        }
+    }
+
+    impl<'a> Arbitrary<'a> for v9::Component {
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+            let component = v9::Component {
+                kind: u.arbitrary()?,
+                position: u.arbitrary()?,
+                rotation: u.arbitrary()?,
+                permanent_id: u.arbitrary()?,
+                custom_string: u.arbitrary()?,
+                settings: list(u, u64::arbitrary)?,
+                buffer_size: u.arbitrary()?,
+                ui_order: u.arbitrary()?,
+                word_size: u.arbitrary()?,
+                linked_components: v9::LinkedComponents {
+                    linked_components: list(u, u64::arbitrary)?,
+                    dummy0: u.arbitrary()?,
+                },
+                watched_components: list(u, |u| {
+                    Ok(v9::WatchedComponent {
+                        permanent_id: u.arbitrary()?,
+                        inner_id: u.arbitrary()?,
+                        name: u.arbitrary()?,
+                    })
+                })?,
+                selected_programs: list(u, |u| {
+                    Ok(v9::SelectedProgram {
+                        level: u.arbitrary()?,
+                        program: u.arbitrary()?,
+                    })
+                })?,
+                custom: v9::CustomInfo {
+                    id: u.arbitrary()?,
+                    explicit_word_sizes: list(u, |u| {
+                        Ok(v9::ExplicitWordSize {
+                            a: u.arbitrary()?,
+                            b: u.arbitrary()?,
+                        })
+                    })?,
+                },
+            };
+            Ok(normalize(component))
+        }
+    }
+
+    impl<'a> Arbitrary<'a> for v9::CircuitData {
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+            Ok(v9::CircuitData {
+                custom_id: u.arbitrary()?,
+                hub_id: u.arbitrary()?,
+                gate: u.arbitrary()?,
+                delay: u.arbitrary()?,
+                menu_visible: u.arbitrary()?,
+                clock_speed: u.arbitrary()?,
+                dependencies: list(u, i64::arbitrary)?,
+                description: u.arbitrary()?,
+                camera_position: u.arbitrary()?,
+                synced: u.arbitrary()?,
+                dummy0: u.arbitrary()?,
+                player_data: list(u, u8::arbitrary)?,
+                components: list(u, v9::Component::arbitrary)?,
+                wires: list(u, Wire::arbitrary)?,
+            })
+        }
+    }
+}
+
+#[cfg(feature = "proptest")]
+pub mod strategy {
+    //! [proptest] strategies producing the same shapes as the `Arbitrary`
+    //! impls.
+
+    use proptest::{collection::vec, prelude::*};
+
+    use super::*;
+
+    pub fn cd_string() -> impl Strategy<Value = CDString> {
+        prop_oneof![
+            3 => any::<String>().prop_map(|s| CDString::from(truncate(s))),
+            1 => vec(any::<u8>(), 0..=MAX_STRING).prop_map(CDString::from),
+        ]
+    }
+
+    pub fn point() -> impl Strategy<Value = Point> {
+        any::<(i16, i16)>().prop_map(|(x, y)| Point::new(x, y))
+    }
+
+    pub fn kind() -> impl Strategy<Value = Kind> {
+        (0u16..=130).prop_map(Kind::from)
+    }
+
+    pub fn wire_segment() -> impl Strategy<Value = WireSegment> {
+        (0u8..8, 0u8..32).prop_map(|(direction, length)| segment(direction, length))
+    }
+
+    pub fn wire() -> impl Strategy<Value = Wire> {
+        (
+            any::<u8>(),
+            cd_string(),
+            point(),
+            vec(wire_segment(), 0..=MAX_ITEMS),
+        )
+            .prop_map(|(color, comment, start, segments)| Wire {
+                color,
+                comment,
+                start,
+                segments: terminated(segments),
+            })
+    }
+
+    fn watched_component() -> impl Strategy<Value = v9::WatchedComponent> {
+        (any::<u64>(), any::<u64>(), cd_string()).prop_map(|(permanent_id, inner_id, name)| {
+            v9::WatchedComponent {
+                permanent_id,
+                inner_id,
+                name,
+            }
+        })
+    }
+
+    fn selected_program() -> impl Strategy<Value = v9::SelectedProgram> {
+        (cd_string(), cd_string())
+            .prop_map(|(level, program)| v9::SelectedProgram { level, program })
+    }
+
+    pub fn component() -> impl Strategy<Value = v9::Component> {
+        let body = (
+            kind(),
+            point(),
+            any::<u8>(),
+            any::<u64>(),
+            cd_string(),
+            vec(any::<u64>(), 0..=MAX_ITEMS),
+            any::<(i64, i16, i64)>(),
+        );
+        let extra = (
+            vec(any::<u64>(), 0..=MAX_ITEMS),
+            any::<u64>(),
+            vec(watched_component(), 0..=MAX_ITEMS),
+            vec(selected_program(), 0..=MAX_ITEMS),
+            any::<u64>(),
+            vec(any::<(i64, i64)>(), 0..=MAX_ITEMS),
+        );
+        (body, extra).prop_map(|(body, extra)| {
+            let (kind, position, rotation, permanent_id, custom_string, settings, sizes) = body;
+            let (linked, linked_dummy0, watched, selected, custom_id, word_sizes) = extra;
+            let (buffer_size, ui_order, word_size) = sizes;
+            normalize(v9::Component {
+                kind,
+                position,
+                rotation,
+                permanent_id,
+                custom_string,
+                settings,
+                buffer_size,
+                ui_order,
+                word_size,
+                linked_components: v9::LinkedComponents {
+                    linked_components: linked,
+                    dummy0: linked_dummy0,
+                },
+                watched_components: watched,
+                selected_programs: selected,
+                custom: v9::CustomInfo {
+                    id: custom_id,
+                    explicit_word_sizes: word_sizes
+                        .into_iter()
+                        .map(|(a, b)| v9::ExplicitWordSize { a, b })
+                        .collect(),
+                },
+            })
+        })
+    }
+
+    pub fn circuit_data() -> impl Strategy<Value = v9::CircuitData> {
+        let header = (
+            any::<(u64, u32, i64, i64, bool, u64)>(),
+            vec(any::<i64>(), 0..=MAX_ITEMS),
+            cd_string(),
+            point(),
+            any::<(u8, u32)>(),
+            vec(any::<u8>(), 0..=MAX_ITEMS),
+        );
+        (
+            header,
+            vec(component(), 0..=MAX_ITEMS),
+            vec(wire(), 0..=MAX_ITEMS),
+        )
+            .prop_map(|(header, components, wires)| {
+                let (ids, dependencies, description, camera_position, sync, player_data) = header;
+                let (custom_id, hub_id, gate, delay, menu_visible, clock_speed) = ids;
+                let (synced, dummy0) = sync;
+                v9::CircuitData {
+                    custom_id,
+                    hub_id,
+                    gate,
+                    delay,
+                    menu_visible,
+                    clock_speed,
+                    dependencies,
+                    description,
+                    camera_position,
+                    synced,
+                    dummy0,
+                    player_data,
+                    components,
+                    wires,
+                }
+            })
+    }
+}
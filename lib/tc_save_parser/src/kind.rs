@@ -1,164 +1,327 @@
-#![feature(trace_macros)]
-
-use std::default;
-
 use binrw::binrw;
 use tc_save_parser_macros::kind_mapper;
 
+use crate::catalog::{Category, KindInfo, layout::*};
+
 #[kind_mapper]
 #[binrw]
 #[br(little,repr=u16)]
 #[bw(little,repr=u16)]
-#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u16)]
 pub enum Kind {
     #[default]
+    #[info(name = "None", category = Special, layout = NONE)]
     None = 0,
+    #[info(name = "Off", category = Io, layout = SOURCE_BIT)]
     Off = 1,
+    #[info(name = "On", category = Io, layout = SOURCE_BIT)]
     On = 2,
+    #[info(name = "NOT", category = Logic, layout = GATE1)]
     NotBit = 3,
+    #[info(name = "AND", category = Logic, layout = GATE2)]
     AndBit = 4,
+    #[info(name = "3-input AND", category = Logic, layout = GATE3)]
     And3Bit = 5,
+    #[info(name = "NAND", category = Logic, layout = GATE2)]
     NandBit = 6,
+    #[info(name = "OR", category = Logic, layout = GATE2)]
     OrBit = 7,
+    #[info(name = "3-input OR", category = Logic, layout = GATE3)]
     Or3Bit = 8,
+    #[info(name = "NOR", category = Logic, layout = GATE2)]
     NorBit = 9,
+    #[info(name = "XOR", category = Logic, layout = GATE2)]
     XorBit = 10,
+    #[info(name = "XNOR", category = Logic, layout = GATE2)]
     XnorBit = 11,
+    #[info(name = "Bit switch", category = Plumbing, layout = SWITCH_BIT)]
     SwitchBit = 12,
+    #[info(name = "Bit delay line", category = Memory, layout = GATE1)]
     DelayLineBit = 13,
+    #[info(name = "Bit register", category = Memory, layout = REGISTER_BIT)]
     RegisterBit = 14,
+    #[info(name = "Full adder", category = Arithmetic, layout = FULL_ADDER)]
     FullAdder = 15,
+    #[info(name = "8-bit maker", category = Plumbing, layout = MAKER_BIT8)]
     MakerBit8 = 16,
+    #[info(name = "8-bit splitter", category = Plumbing, layout = SPLITTER_BIT8)]
     SplitterBit8 = 17,
+    #[info(name = "Word NOT", category = Logic, layout = WORD1)]
     NotWord = 18,
+    #[info(name = "Word OR", category = Logic, layout = WORD2)]
     OrWord = 19,
+    #[info(name = "Word AND", category = Logic, layout = WORD2)]
     AndWord = 20,
+    #[info(name = "Word NAND", category = Logic, layout = WORD2)]
     NandWord = 21,
+    #[info(name = "Word NOR", category = Logic, layout = WORD2)]
     NorWord = 22,
+    #[info(name = "Word XOR", category = Logic, layout = WORD2)]
     XorWord = 23,
+    #[info(name = "Word XNOR", category = Logic, layout = WORD2)]
     XnorWord = 24,
+    #[info(name = "Word switch", category = Plumbing, layout = SWITCH_WORD)]
     SwitchWord = 25,
+    #[info(name = "Equal", category = Arithmetic, layout = COMPARE)]
     Equal = 26,
+    #[info(name = "Less (unsigned)", category = Arithmetic, layout = COMPARE)]
     LessU = 27,
+    #[info(name = "Less (signed)", category = Arithmetic, layout = COMPARE)]
     LessS = 28,
+    #[info(name = "Negate", category = Arithmetic, layout = WORD1)]
     Neg = 29,
+    #[info(name = "Add", category = Arithmetic, layout = ADD)]
     Add = 30,
+    #[info(name = "Multiply", category = Arithmetic, layout = MUL)]
     Mul = 31,
+    #[info(name = "Divide", category = Arithmetic, layout = DIV)]
     Div = 32,
+    #[info(name = "Shift left", category = Arithmetic, layout = WORD2)]
     Lsl = 33,
+    #[info(name = "Shift right", category = Arithmetic, layout = WORD2)]
     Lsr = 34,
+    #[info(name = "Rotate left", category = Arithmetic, layout = WORD2)]
     Rol = 35,
+    #[info(name = "Rotate right", category = Arithmetic, layout = WORD2)]
     Ror = 36,
+    #[info(name = "Arithmetic shift right", category = Arithmetic, layout = WORD2)]
     Asr = 37,
+    #[info(name = "Counter", category = Memory, layout = COUNTER)]
     Counter = 38,
+    #[info(name = "Word register", category = Memory, layout = REGISTER_WORD)]
     RegisterWord = 39,
+    #[info(name = "Immediate word register", category = Memory, layout = REGISTER_WORD)]
     ImmRegisterWord = 40,
+    #[info(name = "Immediate bit delay line", category = Memory, layout = GATE1)]
     ImmDelayLineBit = 41,
+    #[info(name = "Multiplexer", category = Plumbing, layout = MUX)]
     Mux = 42,
+    #[info(name = "1-bit decoder", category = Plumbing, layout = DECODER1)]
     Decoder1 = 43,
+    #[info(name = "2-bit decoder", category = Plumbing, layout = DECODER2)]
     Decoder2 = 44,
+    #[info(name = "3-bit decoder", category = Plumbing, layout = DECODER3)]
     Decoder3 = 45,
+    #[info(name = "Constant", category = Io, layout = SOURCE_WORD)]
     Constant = 46,
+    #[info(name = "Word splitter (2)", category = Plumbing, layout = SPLITTER_WORD2)]
     SplitterWord2 = 47,
+    #[info(name = "Word maker (2)", category = Plumbing, layout = MAKER_WORD2)]
     MakerWord2 = 48,
+    #[info(name = "Front panel", category = Special, layout = NONE)]
     FrontPanel = 49,
+    #[info(name = "Assembler", category = Special, layout = NONE)]
     Assembler = 50,
+    #[info(name = "Seven-segment display", category = Device, layout = SEGMENT_DISPLAY)]
     Ssd = 51,
+    #[info(name = "RAM", category = Memory, layout = RAM)]
     Ram = 52,
+    #[info(name = "Latency RAM", category = Memory, layout = RAM)]
     RamLatency = 53,
+    #[info(name = "Fast RAM", category = Memory, layout = RAM)]
     RamFast = 54,
+    #[info(name = "Word delay line", category = Memory, layout = WORD1)]
     DelayLineWord = 55,
+    #[info(name = "Dual load RAM", category = Memory, layout = RAM_DUAL_LOAD)]
     RamDualLoad = 56,
+    #[info(name = "File loader", category = Memory, layout = FILE_LOADER)]
     FileLoader = 57,
+    #[info(name = "Custom level output", category = Io, layout = SINK_WORD)]
     CcLevelOutput = 58,
+    #[info(name = "Level gate", category = Special, layout = NONE)]
     LevelGate = 59,
+    #[info(name = "Level input", category = Io, layout = SOURCE_BIT)]
     LevelInput1 = 60,
+    #[info(name = "Level word input", category = Io, layout = SOURCE_WORD)]
     LevelInputWord = 61,
+    #[info(name = "Level switched input", category = Io, layout = SOURCE_WORD)]
     LevelInputSwitched = 62,
+    #[info(name = "Level 2-pin input", category = Io, layout = SOURCE_PINS2)]
     LevelInput2Pin = 63,
+    #[info(name = "Level 3-pin input", category = Io, layout = SOURCE_PINS3)]
     LevelInput3Pin = 64,
+    #[info(name = "Level 4-pin input", category = Io, layout = SOURCE_PINS4)]
     LevelInput4Pin = 65,
+    #[info(name = "Level custom input", category = Io, layout = SOURCE_WORD)]
     LevelInputCustom = 66,
+    #[info(name = "Level architecture input", category = Io, layout = SOURCE_WORD)]
     LevelInputArch = 67,
+    #[info(name = "Level output", category = Io, layout = SINK_BIT)]
     LevelOutput1 = 68,
+    #[info(name = "Level word output", category = Io, layout = SINK_WORD)]
     LevelOutputWord = 69,
+    #[info(name = "Level switched output", category = Io, layout = SWITCHED_SINK)]
     LevelOutputSwitched = 70,
+    #[info(name = "Level sum output", category = Io, layout = SINK_BIT)]
     LevelOutput1Sum = 71,
+    #[info(name = "Level carry output", category = Io, layout = SINK_BIT)]
     LevelOutput1Car = 72,
+    #[info(name = "Level 2-pin output", category = Io, layout = SINK_PINS2)]
     LevelOutput2Pin = 73,
+    #[info(name = "Level 3-pin output", category = Io, layout = SINK_PINS3)]
     LevelOutput3Pin = 74,
+    #[info(name = "Level 4-pin output", category = Io, layout = SINK_PINS4)]
     LevelOutput4Pin = 75,
+    #[info(name = "Level architecture output", category = Io, layout = SINK_WORD)]
     LevelOutputArch = 76,
+    #[info(name = "Level counter output", category = Io, layout = SINK_WORD)]
     LevelOutputCounter = 77,
+    #[info(name = "Custom component", category = Custom, layout = NONE)]
     Custom = 78,
+    #[info(name = "Input", category = Io, layout = SOURCE_WORD)]
     CcInput = 79,
+    #[info(name = "Buffered input", category = Io, layout = SOURCE_WORD)]
     CcInputBuffer = 80,
+    #[info(name = "Output", category = Io, layout = SINK_WORD)]
     CcOutput = 81,
+    #[info(name = "Bit memory probe", category = Probe, layout = NONE)]
     ProbeMemoryBit = 82,
+    #[info(name = "Word memory probe", category = Probe, layout = NONE)]
     ProbeMemoryWord = 83,
+    #[info(name = "Bit wire probe", category = Probe, layout = SINK_BIT)]
     ProbeWireBit = 84,
+    #[info(name = "Word wire probe", category = Probe, layout = SINK_WORD)]
     ProbeWireWord = 85,
+    #[info(name = "Configurable delay", category = Memory, layout = GATE1)]
     ConfigDelay = 86,
+    #[info(name = "Halt", category = Device, layout = HALT)]
     Halt = 87,
+    #[info(name = "Console", category = Device, layout = NONE)]
     Console = 88,
+    #[info(name = "Segment display", category = Device, layout = SEGMENT_DISPLAY)]
     SegmentDisplay = 89,
+    #[info(name = "Static value", category = Io, layout = SOURCE_WORD)]
     StaticValue = 90,
+    #[info(name = "Pixel screen", category = Device, layout = NONE)]
     PixelScreen = 91,
+    #[info(name = "Time", category = Device, layout = TIME)]
     Time = 92,
+    #[info(name = "Keyboard", category = Device, layout = KEYBOARD)]
     Keyboard = 93,
+    #[info(name = "Static evaluation", category = Special, layout = SINK_WORD)]
     StaticEval = 94,
+    #[info(name = "Verilog input", category = Io, layout = SOURCE_WORD)]
     VerilogInput = 95,
+    #[info(name = "Verilog output", category = Io, layout = SINK_WORD)]
     VerilogOutput = 96,
+    #[info(name = "Word maker (4)", category = Plumbing, layout = MAKER_WORD4)]
     MakerWord4 = 97,
+    #[info(name = "Word maker (8)", category = Plumbing, layout = MAKER_WORD8)]
     MakerWord8 = 98,
+    #[info(name = "Word splitter (4)", category = Plumbing, layout = SPLITTER_WORD4)]
     SplitterWord4 = 99,
+    #[info(name = "Word splitter (8)", category = Plumbing, layout = SPLITTER_WORD8)]
     SplitterWord8 = 100,
+    #[info(name = "Static indexer", category = Io, layout = SOURCE_WORD)]
     StaticIndexer = 101,
+    #[info(name = "Immediate bit memory probe", category = Probe, layout = NONE)]
     ImmProbeMemoryBit = 102,
+    #[info(name = "Immediate word delay line", category = Memory, layout = WORD1)]
     ImmDelayLineWord = 103,
+    #[info(name = "Increment", category = Arithmetic, layout = WORD1)]
     Inc = 104,
+    #[info(name = "Custom level custom input", category = Io, layout = SOURCE_WORD)]
     CcLevelInputCustom = 105,
+    #[info(name = "Custom level input", category = Io, layout = SOURCE_WORD)]
     CcLevelInput = 106,
+    #[info(name = "Immediate bit register", category = Memory, layout = REGISTER_BIT)]
     ImmRegisterBit = 107,
+    #[info(name = "Modulo", category = Arithmetic, layout = WORD2)]
     Mod = 108,
+    #[info(name = "2-bit splitter", category = Plumbing, layout = SPLITTER_BIT2)]
     SplitterBit2 = 109,
+    #[info(name = "4-bit splitter", category = Plumbing, layout = SPLITTER_BIT4)]
     SplitterBit4 = 110,
+    #[info(name = "2-bit maker", category = Plumbing, layout = MAKER_BIT2)]
     MakerBit2 = 111,
+    #[info(name = "4-bit maker", category = Plumbing, layout = MAKER_BIT4)]
     MakerBit4 = 112,
+    #[info(name = "Immediate word memory probe", category = Probe, layout = NONE)]
     ImmProbeMemoryWord = 113,
+    #[info(name = "Concatenator (2)", category = Plumbing, layout = CONCATENATOR2)]
     Concatenator2 = 114,
+    #[info(name = "Concatenator (4)", category = Plumbing, layout = CONCATENATOR4)]
     Concatenator4 = 115,
+    #[info(name = "Concatenator (8)", category = Plumbing, layout = CONCATENATOR8)]
     Concatenator8 = 116,
+    #[info(name = "Static indexer config", category = Io, layout = SOURCE_WORD)]
     StaticIndexerConfig = 117,
+    #[info(name = "ROM", category = Memory, layout = ROM)]
     Rom = 118,
 }
 
 impl Kind {
+    /// Components that carry program selections and watched components in
+    /// version 7 saves. Later versions moved this data into
+    /// `AssemblerInfo` and the component body itself.
     pub fn is_memory(&self) -> bool {
-        match self {
-            // Kind::MemoryBit => true,
-            _ => false,
-        }
+        matches!(
+            self,
+            Kind::Assembler
+                | Kind::Ram
+                | Kind::RamFast
+                | Kind::RamLatency
+                | Kind::RamDualLoad
+                | Kind::Rom
+        )
+    }
+    /// Components driven from outside the circuit: inputs the simulator
+    /// sets and the exporters turn into module inputs.
+    pub fn is_input(&self) -> bool {
+        matches!(
+            self,
+            Kind::CcInput
+                | Kind::CcInputBuffer
+                | Kind::CcLevelInput
+                | Kind::CcLevelInputCustom
+                | Kind::LevelInput1
+                | Kind::LevelInputWord
+                | Kind::LevelInputSwitched
+                | Kind::LevelInput2Pin
+                | Kind::LevelInput3Pin
+                | Kind::LevelInput4Pin
+                | Kind::LevelInputCustom
+                | Kind::LevelInputArch
+                | Kind::VerilogInput
+        )
+    }
+    /// Components read from outside the circuit: outputs the simulator
+    /// reports and the exporters turn into module outputs.
+    pub fn is_output(&self) -> bool {
+        matches!(
+            self,
+            Kind::CcOutput
+                | Kind::CcLevelOutput
+                | Kind::LevelOutput1
+                | Kind::LevelOutputWord
+                | Kind::LevelOutputSwitched
+                | Kind::LevelOutput1Sum
+                | Kind::LevelOutput1Car
+                | Kind::LevelOutput2Pin
+                | Kind::LevelOutput3Pin
+                | Kind::LevelOutput4Pin
+                | Kind::LevelOutputArch
+                | Kind::LevelOutputCounter
+                | Kind::VerilogOutput
+        )
     }
     pub fn is_custom(&self) -> bool {
-        match self {
-            Kind::Custom => true,
-            _ => false,
-        }
+        matches!(self, Kind::Custom)
     }
     pub fn has_linked_components(&self) -> bool {
-        match self {
-            Kind::Console => true,
-            Kind::PixelScreen => true,
-            Kind::RamDualLoad => true,
-            Kind::RamLatency => true,
-            Kind::RamFast => true,
-            Kind::Ram => true,
-            Kind::Rom => true,
-            Kind::ProbeMemoryBit => true,
-            Kind::ProbeMemoryWord => true,
-            _ => false,
-        }
+        matches!(
+            self,
+            Kind::Console
+                | Kind::PixelScreen
+                | Kind::RamDualLoad
+                | Kind::RamLatency
+                | Kind::RamFast
+                | Kind::Ram
+                | Kind::Rom
+                | Kind::ProbeMemoryBit
+                | Kind::ProbeMemoryWord
+        )
     }
 }
@@ -1,8 +1,31 @@
 pub use kind::*;
+pub use settings::ComponentSettings;
 pub use shared::*;
 pub use versions::*;
+pub use wire::*;
 
+pub mod canonical;
+pub mod catalog;
+pub mod diff;
+#[cfg(any(feature = "arbitrary", feature = "proptest"))]
+pub mod generate;
 pub mod kind;
+pub mod migrate;
+pub mod model;
+pub mod netlist;
+pub mod render;
+pub mod settings;
 pub mod shared;
+pub mod sim;
+#[cfg(feature = "testbench")]
+pub mod testbench;
+#[cfg(feature = "serde")]
+pub mod text;
+pub mod transform;
+pub mod verilog;
+pub mod wire;
+#[cfg(feature = "yosys")]
+pub mod yosys;
 
+mod located;
 mod versions;
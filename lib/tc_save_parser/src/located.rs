@@ -0,0 +1,168 @@
+//! Helpers that let parse failures report where in the data they happened.
+//!
+//! Failures are raised as binrw custom errors carrying a [`Failure`]. Structs
+//! marked `#[located]` push a [`Field`] backtrace frame for a failing field,
+//! and [`located_vec`] an [`Index`] frame for a failing element. When the
+//! error is finally converted into [`crate::Error`], these frames are joined
+//! into a path such as `components[12].custom.explicit_word_sizes`.
+
+use std::{
+    fmt,
+    io::{Read, Seek, SeekFrom},
+};
+
+use binrw::{
+    BinRead, BinResult, Endian,
+    error::{BacktraceFrame, ContextExt},
+};
+
+#[derive(Debug, Clone)]
+pub(crate) enum Failure {
+    Decompress(snap::Error),
+    Truncated,
+    InvalidLength(u64),
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Failure::Decompress(e) => write!(f, "decompression failed: {}", e),
+            Failure::Truncated => write!(f, "unexpected end of data"),
+            Failure::InvalidLength(len) => write!(f, "invalid length {}", len),
+        }
+    }
+}
+
+impl Failure {
+    pub(crate) fn at(self, pos: u64) -> binrw::Error {
+        binrw::Error::Custom {
+            pos,
+            err: Box::new(self),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Field(pub &'static str);
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "While parsing field {}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Index(pub usize);
+
+impl fmt::Display for Index {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "While parsing element {}", self.0)
+    }
+}
+
+/// Reads `count` items. Every item takes at least a byte, so a count larger
+/// than the bytes left in the reader is rejected up front, and no more is
+/// allocated in advance than those bytes would hold. A failing item records
+/// its index.
+pub(crate) fn located_vec<R, T>(
+    reader: &mut R,
+    endian: Endian,
+    (count,): (u64,),
+) -> BinResult<Vec<T>>
+where
+    R: Read + Seek,
+    T: for<'a> BinRead<Args<'a> = ()>,
+{
+    let pos = reader.stream_position()?;
+    let end = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(pos))?;
+    let remaining = end.saturating_sub(pos);
+    if count > remaining {
+        return Err(Failure::InvalidLength(count).at(pos));
+    }
+    let capacity = count.min(remaining / std::mem::size_of::<T>().max(1) as u64);
+    let mut items = Vec::with_capacity(capacity as usize);
+    for i in 0..count as usize {
+        let item = T::read_options(reader, endian, ())
+            .map_err(|e| e.with_context(BacktraceFrame::Custom(Box::new(Index(i)))))?;
+        items.push(item);
+    }
+    Ok(items)
+}
+
+/// Wraps a reader to remember where the last read started, so running out of
+/// data can be reported at the field that needed it.
+pub(crate) struct Tracking<R> {
+    inner: R,
+    last_read: u64,
+}
+
+impl<R: Read + Seek> Tracking<R> {
+    pub(crate) fn new(inner: R) -> Self {
+        Self {
+            inner,
+            last_read: 0,
+        }
+    }
+
+    /// Reads a `T`, turning an end-of-data error into a located
+    /// [`Failure::Truncated`].
+    pub(crate) fn read<T>(&mut self) -> BinResult<T>
+    where
+        T: for<'a> BinRead<Args<'a> = ()>,
+    {
+        T::read_le(self).map_err(|e| self.locate_eof(e))
+    }
+
+    fn locate_eof(&self, err: binrw::Error) -> binrw::Error {
+        match err {
+            binrw::Error::Backtrace(mut bt) if bt.error.is_eof() => {
+                *bt.error = Failure::Truncated.at(self.last_read);
+                binrw::Error::Backtrace(bt)
+            }
+            err if err.is_eof() => Failure::Truncated.at(self.last_read),
+            err => err,
+        }
+    }
+}
+
+impl<R: Read + Seek> Read for Tracking<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.last_read = self.inner.stream_position()?;
+        self.inner.read(buf)
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> std::io::Result<()> {
+        self.last_read = self.inner.stream_position()?;
+        self.inner.read_exact(buf)
+    }
+}
+
+impl<R: Seek> Seek for Tracking<R> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+/// Builds a field path like `components[12].custom` from an error's
+/// backtrace. Frames are stored innermost first.
+pub(crate) fn field_path(err: &binrw::Error) -> String {
+    let binrw::Error::Backtrace(bt) = err else {
+        return String::new();
+    };
+    let mut path = String::new();
+    for frame in bt.frames.iter().rev() {
+        let BacktraceFrame::Custom(custom) = frame else {
+            continue;
+        };
+        if let Some(Field(name)) = custom.downcast_ref::<Field>() {
+            if !path.is_empty() {
+                path.push('.');
+            }
+            path.push_str(name);
+        } else if let Some(Index(i)) = custom.downcast_ref::<Index>() {
+            path.push_str(&format!("[{}]", i));
+        }
+    }
+    path
+}
@@ -0,0 +1,115 @@
+use std::fmt;
+
+use crate::{Kind, model::Circuit, v7, v8, v9};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldChange {
+    /// The source held a value the target version has no place for.
+    Dropped(String),
+    /// The target version has a field the source could not supply.
+    Defaulted(String),
+}
+
+impl fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldChange::Dropped(path) => write!(f, "dropped {}", path),
+            FieldChange::Defaulted(path) => write!(f, "defaulted {}", path),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MigrationReport {
+    pub changes: Vec<FieldChange>,
+}
+
+impl MigrationReport {
+    pub fn is_lossless(&self) -> bool {
+        self.changes
+            .iter()
+            .all(|c| !matches!(c, FieldChange::Dropped(_)))
+    }
+
+    pub fn dropped(&self) -> impl Iterator<Item = &str> {
+        self.changes.iter().filter_map(|c| match c {
+            FieldChange::Dropped(path) => Some(path.as_str()),
+            _ => None,
+        })
+    }
+
+    pub fn defaulted(&self) -> impl Iterator<Item = &str> {
+        self.changes.iter().filter_map(|c| match c {
+            FieldChange::Defaulted(path) => Some(path.as_str()),
+            _ => None,
+        })
+    }
+
+    pub fn merge(&mut self, other: MigrationReport) {
+        self.changes.extend(other.changes);
+    }
+
+    pub(crate) fn drop_field(&mut self, path: impl Into<String>) {
+        self.changes.push(FieldChange::Dropped(path.into()));
+    }
+
+    pub(crate) fn default_field(&mut self, path: impl Into<String>) {
+        self.changes.push(FieldChange::Defaulted(path.into()));
+    }
+}
+
+// The conversions themselves live in the model, which holds every field of
+// every version. What the model can't see is the source version, so the
+// fields it leaves at their defaults are reported here.
+
+impl v7::CircuitData {
+    pub fn upgrade(self) -> (v8::CircuitData, MigrationReport) {
+        let defaulted = default_linked_components(&self.components);
+        let (out, mut report) = Circuit::from(self).into_v8();
+        report.merge(defaulted);
+        (out, report)
+    }
+}
+
+impl v8::CircuitData {
+    pub fn upgrade(self) -> (v9::CircuitData, MigrationReport) {
+        Circuit::from(self).into_v9()
+    }
+
+    pub fn downgrade(self) -> (v7::CircuitData, MigrationReport) {
+        let defaulted = default_memory(&self.components);
+        let (out, mut report) = Circuit::from(self).into_v7();
+        report.merge(defaulted);
+        (out, report)
+    }
+}
+
+impl v9::CircuitData {
+    pub fn downgrade(self) -> (v8::CircuitData, MigrationReport) {
+        Circuit::from(self).into_v8()
+    }
+}
+
+/// Version 7 has no linked components, so every kind that can have them
+/// starts with none.
+pub(crate) fn default_linked_components(components: &[v7::Component]) -> MigrationReport {
+    let mut report = MigrationReport::default();
+    for (i, c) in components.iter().enumerate() {
+        if c.kind.has_linked_components() {
+            report.default_field(format!("components[{}].linked_components", i));
+        }
+    }
+    report
+}
+
+/// Version 8 only keeps programs and watched components for assemblers, so
+/// other memories start with none in version 7.
+pub(crate) fn default_memory(components: &[v8::Component]) -> MigrationReport {
+    let mut report = MigrationReport::default();
+    for (i, c) in components.iter().enumerate() {
+        if c.kind.is_memory() && c.kind != Kind::Assembler {
+            report.default_field(format!("components[{}].memory", i));
+        }
+    }
+    report
+}
@@ -0,0 +1,594 @@
+//! Version-independent view of a circuit.
+//!
+//! Every on-disk version converts into [`Circuit`] without losing anything, and
+//! converting back to the same version reproduces the original data. Converting
+//! to a different version reports whatever the target can't hold.
+
+use crate::{
+    CDString, CircuitDataFile, CircuitDataVersion, Error, Kind, Point, Wire,
+    migrate::MigrationReport, v7, v8, v9,
+};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Circuit {
+    pub custom_id: u64,
+    pub hub_id: u32,
+    pub gate: i64,
+    pub delay: i64,
+    pub menu_visible: bool,
+    pub clock_speed: u64,
+    pub dependencies: Vec<i64>,
+    pub description: CDString,
+    pub camera_position: Point,
+    pub synced: u8,
+    /// Of unknown meaning. Version 7 stores it as a `u16`.
+    pub dummy0: u32,
+    pub player_data: Vec<u8>,
+    /// Only stored by version 7.
+    pub hub_description: CDString,
+    pub components: Vec<Component>,
+    pub wires: Vec<Wire>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Component {
+    pub kind: Kind,
+    pub position: Point,
+    pub rotation: u8,
+    pub permanent_id: u64,
+    pub custom_string: CDString,
+    pub settings: Vec<u64>,
+    pub buffer_size: i64,
+    pub ui_order: i16,
+    pub word_size: i64,
+    /// Version 7's `dummy0`, of unknown meaning.
+    pub v7_dummy0: i64,
+    /// Version 8's `dummy0`, of unknown meaning.
+    pub v8_dummy0: u16,
+    pub linked_components: Vec<u64>,
+    /// The `dummy0` after the linked ids in versions 8 and 9, of unknown
+    /// meaning.
+    pub linked_dummy0: u64,
+    pub watched_components: Vec<WatchedComponent>,
+    pub selected_programs: Vec<SelectedProgram>,
+    pub custom: CustomInfo,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CustomInfo {
+    pub id: u64,
+    pub explicit_word_sizes: Vec<ExplicitWordSize>,
+    /// Version 7's `dummy0`, of unknown meaning.
+    pub v7_dummy0: u16,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExplicitWordSize {
+    pub a: i64,
+    pub b: i64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WatchedComponent {
+    pub permanent_id: u64,
+    pub inner_id: u64,
+    pub name: CDString,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SelectedProgram {
+    pub level: CDString,
+    pub program: CDString,
+}
+
+impl Circuit {
+    pub fn component(&self, permanent_id: u64) -> Option<&Component> {
+        self.components
+            .iter()
+            .find(|c| c.permanent_id == permanent_id)
+    }
+
+    pub fn component_mut(&mut self, permanent_id: u64) -> Option<&mut Component> {
+        self.components
+            .iter_mut()
+            .find(|c| c.permanent_id == permanent_id)
+    }
+
+    /// Converts into the given on-disk version.
+    pub fn into_version(self, version: u8) -> Result<(CircuitDataVersion, MigrationReport), Error> {
+        match version {
+            7 => {
+                let (data, report) = self.into_v7();
+                Ok((CircuitDataVersion::V7(data), report))
+            }
+            8 => {
+                let (data, report) = self.into_v8();
+                Ok((CircuitDataVersion::V8(data), report))
+            }
+            9 => {
+                let (data, report) = self.into_v9();
+                Ok((CircuitDataVersion::V9(data), report))
+            }
+            _ => Err(Error::UnsupportedVersion(version, vec![])),
+        }
+    }
+
+    pub fn into_v7(self) -> (v7::CircuitData, MigrationReport) {
+        let mut report = MigrationReport::default();
+        let dummy0 = u16::try_from(self.dummy0).unwrap_or_else(|_| {
+            report.drop_field("dummy0");
+            0
+        });
+        let components = self
+            .components
+            .into_iter()
+            .enumerate()
+            .map(|(i, c)| {
+                if !c.linked_components.is_empty() {
+                    report.drop_field(format!("components[{}].linked_components", i));
+                }
+                if c.linked_dummy0 != 0 {
+                    report.drop_field(format!("components[{}].linked_components.dummy0", i));
+                }
+                if c.v8_dummy0 != 0 {
+                    report.drop_field(format!("components[{}].dummy0", i));
+                }
+                if !c.kind.is_memory() {
+                    drop_programs(&mut report, i, &c);
+                }
+                c.into()
+            })
+            .collect();
+        let out = v7::CircuitData {
+            custom_id: self.custom_id as i64,
+            hub_id: self.hub_id,
+            gate: self.gate,
+            delay: self.delay,
+            menu_visible: self.menu_visible,
+            clock_speed: self.clock_speed,
+            dependencies: self.dependencies,
+            description: self.description,
+            camera_position: self.camera_position,
+            synced: self.synced,
+            dummy0,
+            player_data: self.player_data,
+            hub_description: self.hub_description,
+            components,
+            wires: self.wires,
+        };
+        (out, report)
+    }
+
+    pub fn into_v8(self) -> (v8::CircuitData, MigrationReport) {
+        let mut report = MigrationReport::default();
+        if !self.hub_description.is_empty() {
+            report.drop_field("hub_description");
+        }
+        let components = self
+            .components
+            .into_iter()
+            .enumerate()
+            .map(|(i, c)| {
+                drop_v7_only(&mut report, i, &c);
+                if c.kind != Kind::Assembler {
+                    drop_programs(&mut report, i, &c);
+                }
+                c.into()
+            })
+            .collect();
+        let out = v8::CircuitData {
+            custom_id: self.custom_id,
+            hub_id: self.hub_id,
+            gate: self.gate,
+            delay: self.delay,
+            menu_visible: self.menu_visible,
+            clock_speed: self.clock_speed,
+            dependencies: self.dependencies,
+            description: self.description,
+            camera_position: self.camera_position,
+            synced: self.synced,
+            dummy0: self.dummy0,
+            player_data: self.player_data,
+            components,
+            wires: self.wires,
+        };
+        (out, report)
+    }
+
+    pub fn into_v9(self) -> (v9::CircuitData, MigrationReport) {
+        let mut report = MigrationReport::default();
+        if !self.hub_description.is_empty() {
+            report.drop_field("hub_description");
+        }
+        let components = self
+            .components
+            .into_iter()
+            .enumerate()
+            .map(|(i, c)| {
+                drop_v7_only(&mut report, i, &c);
+                if c.v8_dummy0 != 0 {
+                    report.drop_field(format!("components[{}].dummy0", i));
+                }
+                c.into()
+            })
+            .collect();
+        let out = v9::CircuitData {
+            custom_id: self.custom_id,
+            hub_id: self.hub_id,
+            gate: self.gate,
+            delay: self.delay,
+            menu_visible: self.menu_visible,
+            clock_speed: self.clock_speed,
+            dependencies: self.dependencies,
+            description: self.description,
+            camera_position: self.camera_position,
+            synced: self.synced,
+            dummy0: self.dummy0,
+            player_data: self.player_data,
+            components,
+            wires: self.wires,
+        };
+        (out, report)
+    }
+}
+
+fn drop_v7_only(report: &mut MigrationReport, index: usize, c: &Component) {
+    if c.v7_dummy0 != 0 {
+        report.drop_field(format!("components[{}].dummy0", index));
+    }
+    if c.custom.v7_dummy0 != 0 {
+        report.drop_field(format!("components[{}].custom.dummy0", index));
+    }
+}
+
+fn drop_programs(report: &mut MigrationReport, index: usize, c: &Component) {
+    if !c.watched_components.is_empty() {
+        report.drop_field(format!("components[{}].watched_components", index));
+    }
+    if !c.selected_programs.is_empty() {
+        report.drop_field(format!("components[{}].selected_programs", index));
+    }
+}
+
+impl TryFrom<CircuitDataFile> for Circuit {
+    type Error = Error;
+
+    fn try_from(value: CircuitDataFile) -> Result<Self, Self::Error> {
+        match value.circuit {
+            CircuitDataVersion::V7(data) => Ok(data.into()),
+            CircuitDataVersion::V8(data) => Ok(data.into()),
+            CircuitDataVersion::V9(data) => Ok(data.into()),
+            CircuitDataVersion::Unknown(data) => {
+                Err(Error::UnsupportedVersion(value.version, data))
+            }
+        }
+    }
+}
+
+impl From<v7::CircuitData> for Circuit {
+    fn from(value: v7::CircuitData) -> Self {
+        Self {
+            custom_id: value.custom_id as u64,
+            hub_id: value.hub_id,
+            gate: value.gate,
+            delay: value.delay,
+            menu_visible: value.menu_visible,
+            clock_speed: value.clock_speed,
+            dependencies: value.dependencies,
+            description: value.description,
+            camera_position: value.camera_position,
+            synced: value.synced,
+            dummy0: u32::from(value.dummy0),
+            player_data: value.player_data,
+            hub_description: value.hub_description,
+            components: value.components.into_iter().map(Into::into).collect(),
+            wires: value.wires,
+        }
+    }
+}
+
+impl From<v8::CircuitData> for Circuit {
+    fn from(value: v8::CircuitData) -> Self {
+        Self {
+            custom_id: value.custom_id,
+            hub_id: value.hub_id,
+            gate: value.gate,
+            delay: value.delay,
+            menu_visible: value.menu_visible,
+            clock_speed: value.clock_speed,
+            dependencies: value.dependencies,
+            description: value.description,
+            camera_position: value.camera_position,
+            synced: value.synced,
+            dummy0: value.dummy0,
+            player_data: value.player_data,
+            hub_description: CDString::default(),
+            components: value.components.into_iter().map(Into::into).collect(),
+            wires: value.wires,
+        }
+    }
+}
+
+impl From<v9::CircuitData> for Circuit {
+    fn from(value: v9::CircuitData) -> Self {
+        Self {
+            custom_id: value.custom_id,
+            hub_id: value.hub_id,
+            gate: value.gate,
+            delay: value.delay,
+            menu_visible: value.menu_visible,
+            clock_speed: value.clock_speed,
+            dependencies: value.dependencies,
+            description: value.description,
+            camera_position: value.camera_position,
+            synced: value.synced,
+            dummy0: value.dummy0,
+            player_data: value.player_data,
+            hub_description: CDString::default(),
+            components: value.components.into_iter().map(Into::into).collect(),
+            wires: value.wires,
+        }
+    }
+}
+
+fn convert<T, U: From<T>>(items: Vec<T>) -> Vec<U> {
+    items.into_iter().map(U::from).collect()
+}
+
+// The list items have the same fields in every version, apart from the
+// signedness of ids in version 7.
+macro_rules! impl_item_conversions {
+    ($($version:ident),*) => {$(
+        impl From<$version::ExplicitWordSize> for ExplicitWordSize {
+            fn from(value: $version::ExplicitWordSize) -> Self {
+                Self { a: value.a, b: value.b }
+            }
+        }
+
+        impl From<ExplicitWordSize> for $version::ExplicitWordSize {
+            fn from(value: ExplicitWordSize) -> Self {
+                Self { a: value.a, b: value.b }
+            }
+        }
+
+        impl From<$version::WatchedComponent> for WatchedComponent {
+            fn from(value: $version::WatchedComponent) -> Self {
+                Self {
+                    permanent_id: value.permanent_id as u64,
+                    inner_id: value.inner_id as u64,
+                    name: value.name,
+                }
+            }
+        }
+
+        impl From<WatchedComponent> for $version::WatchedComponent {
+            fn from(value: WatchedComponent) -> Self {
+                Self {
+                    permanent_id: value.permanent_id as _,
+                    inner_id: value.inner_id as _,
+                    name: value.name,
+                }
+            }
+        }
+
+        impl From<$version::SelectedProgram> for SelectedProgram {
+            fn from(value: $version::SelectedProgram) -> Self {
+                Self {
+                    level: value.level,
+                    program: value.program,
+                }
+            }
+        }
+
+        impl From<SelectedProgram> for $version::SelectedProgram {
+            fn from(value: SelectedProgram) -> Self {
+                Self {
+                    level: value.level,
+                    program: value.program,
+                }
+            }
+        }
+    )*};
+}
+
+impl_item_conversions!(v7, v8, v9);
+
+impl From<v7::Component> for Component {
+    fn from(value: v7::Component) -> Self {
+        let custom = if value.kind.is_custom() {
+            CustomInfo {
+                id: value.custom.id as u64,
+                explicit_word_sizes: convert(value.custom.explicit_word_sizes),
+                v7_dummy0: value.custom.dummy0,
+            }
+        } else {
+            CustomInfo::default()
+        };
+        let memory = if value.kind.is_memory() {
+            value.memory
+        } else {
+            v7::MemoryInfo::default()
+        };
+        Self {
+            kind: value.kind,
+            position: value.position,
+            rotation: value.rotation,
+            permanent_id: value.permanent_id as u64,
+            custom_string: value.custom_string,
+            settings: value.settings,
+            buffer_size: value.buffer_size,
+            ui_order: value.ui_order,
+            word_size: value.word_size,
+            v7_dummy0: value.dummy0,
+            watched_components: convert(memory.watched_components),
+            selected_programs: convert(memory.selected_programs),
+            custom,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<v8::Component> for Component {
+    fn from(value: v8::Component) -> Self {
+        let linked = if value.kind.has_linked_components() {
+            value.linked_components
+        } else {
+            v8::LinkedComponents::default()
+        };
+        let custom = if value.kind.is_custom() {
+            CustomInfo {
+                id: value.custom.id,
+                explicit_word_sizes: convert(value.custom.explicit_word_sizes),
+                v7_dummy0: 0,
+            }
+        } else {
+            CustomInfo::default()
+        };
+        let assembler = if value.kind == Kind::Assembler {
+            value.assembler_info
+        } else {
+            v8::AssemblerInfo::default()
+        };
+        Self {
+            kind: value.kind,
+            position: value.position,
+            rotation: value.rotation,
+            permanent_id: value.permanent_id,
+            custom_string: value.custom_string,
+            settings: value.settings,
+            buffer_size: value.buffer_size,
+            ui_order: value.ui_order,
+            word_size: value.word_size,
+            v8_dummy0: value.dummy0,
+            linked_components: linked.linked_components,
+            linked_dummy0: linked.dummy0,
+            watched_components: convert(assembler.watched_components),
+            selected_programs: convert(assembler.selected_programs),
+            custom,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<v9::Component> for Component {
+    fn from(value: v9::Component) -> Self {
+        let linked = if value.kind.has_linked_components() {
+            value.linked_components
+        } else {
+            v9::LinkedComponents::default()
+        };
+        let custom = if value.kind.is_custom() {
+            CustomInfo {
+                id: value.custom.id,
+                explicit_word_sizes: convert(value.custom.explicit_word_sizes),
+                v7_dummy0: 0,
+            }
+        } else {
+            CustomInfo::default()
+        };
+        Self {
+            kind: value.kind,
+            position: value.position,
+            rotation: value.rotation,
+            permanent_id: value.permanent_id,
+            custom_string: value.custom_string,
+            settings: value.settings,
+            buffer_size: value.buffer_size,
+            ui_order: value.ui_order,
+            word_size: value.word_size,
+            linked_components: linked.linked_components,
+            linked_dummy0: linked.dummy0,
+            watched_components: convert(value.watched_components),
+            selected_programs: convert(value.selected_programs),
+            custom,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<Component> for v7::Component {
+    fn from(value: Component) -> Self {
+        Self {
+            kind: value.kind,
+            position: value.position,
+            rotation: value.rotation,
+            permanent_id: value.permanent_id as i64,
+            custom_string: value.custom_string,
+            settings: value.settings,
+            buffer_size: value.buffer_size,
+            ui_order: value.ui_order,
+            word_size: value.word_size,
+            dummy0: value.v7_dummy0,
+            custom: v7::CustomInfo {
+                id: value.custom.id as i64,
+                explicit_word_sizes: convert(value.custom.explicit_word_sizes),
+                dummy0: value.custom.v7_dummy0,
+            },
+            memory: v7::MemoryInfo {
+                selected_programs: convert(value.selected_programs),
+                watched_components: convert(value.watched_components),
+            },
+        }
+    }
+}
+
+impl From<Component> for v8::Component {
+    fn from(value: Component) -> Self {
+        Self {
+            kind: value.kind,
+            position: value.position,
+            rotation: value.rotation,
+            permanent_id: value.permanent_id,
+            custom_string: value.custom_string,
+            settings: value.settings,
+            buffer_size: value.buffer_size,
+            ui_order: value.ui_order,
+            word_size: value.word_size,
+            linked_components: v8::LinkedComponents {
+                linked_components: value.linked_components,
+                dummy0: value.linked_dummy0,
+            },
+            dummy0: value.v8_dummy0,
+            custom: v8::CustomInfo {
+                id: value.custom.id,
+                explicit_word_sizes: convert(value.custom.explicit_word_sizes),
+            },
+            assembler_info: v8::AssemblerInfo {
+                watched_components: convert(value.watched_components),
+                selected_programs: convert(value.selected_programs),
+            },
+        }
+    }
+}
+
+impl From<Component> for v9::Component {
+    fn from(value: Component) -> Self {
+        Self {
+            kind: value.kind,
+            position: value.position,
+            rotation: value.rotation,
+            permanent_id: value.permanent_id,
+            custom_string: value.custom_string,
+            settings: value.settings,
+            buffer_size: value.buffer_size,
+            ui_order: value.ui_order,
+            word_size: value.word_size,
+            linked_components: v9::LinkedComponents {
+                linked_components: value.linked_components,
+                dummy0: value.linked_dummy0,
+            },
+            watched_components: convert(value.watched_components),
+            selected_programs: convert(value.selected_programs),
+            custom: v9::CustomInfo {
+                id: value.custom.id,
+                explicit_word_sizes: convert(value.custom.explicit_word_sizes),
+            },
+        }
+    }
+}
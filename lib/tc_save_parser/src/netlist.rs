@@ -0,0 +1,255 @@
+//! Connectivity extraction: which component pins are joined by which wires.
+//!
+//! Wires join each other where their endpoints share a cell; a wire passing
+//! through a cell without ending there connects nothing. A pin is connected
+//! when a wire ends on its cell. Pin cells come from the [`Kind`] catalog,
+//! rotated with the component; `Custom` components take theirs from
+//! [`NetlistBuilder::custom_pins`].
+//!
+//! [`Kind`]: crate::Kind
+
+use std::collections::HashMap;
+
+use crate::{
+    Point,
+    catalog::{PinDirection, Width},
+    transform::Rotation,
+    v9,
+};
+
+/// A pin definition relative to an unrotated component, as supplied for
+/// custom components.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PinDef {
+    pub name: String,
+    pub offset: Point,
+    pub direction: PinDirection,
+    pub width: Width,
+}
+
+impl From<&crate::catalog::Pin> for PinDef {
+    fn from(pin: &crate::catalog::Pin) -> Self {
+        Self {
+            name: pin.name.to_string(),
+            offset: pin.offset,
+            direction: pin.direction,
+            width: pin.width,
+        }
+    }
+}
+
+/// A pin placed in the circuit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetPin {
+    pub name: String,
+    pub position: Point,
+    pub direction: PinDirection,
+    pub width: Width,
+}
+
+/// A pin, by component index and index into that component's pins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PinRef {
+    pub component: usize,
+    pub pin: usize,
+}
+
+/// One end of a wire, by wire index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WireEnd {
+    pub wire: usize,
+    pub at: Point,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Net {
+    pub pins: Vec<PinRef>,
+    pub wires: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Netlist {
+    /// Placed pins of every component, indexed like `CircuitData::components`.
+    pub pins: Vec<Vec<NetPin>>,
+    /// Every group of joined wires, with the pins they reach.
+    pub nets: Vec<Net>,
+    /// Wire ends touching neither a pin nor another wire's end.
+    pub floating_wire_ends: Vec<WireEnd>,
+    /// Pins no wire ends on.
+    pub unconnected_pins: Vec<PinRef>,
+    /// `Custom` components the resolver had no pins for.
+    pub unresolved_components: Vec<usize>,
+    net_of: HashMap<PinRef, usize>,
+}
+
+impl Netlist {
+    pub fn pin(&self, pin: PinRef) -> &NetPin {
+        &self.pins[pin.component][pin.pin]
+    }
+
+    /// Finds a pin of `component` by name.
+    pub fn pin_ref(&self, component: usize, name: &str) -> Option<PinRef> {
+        let pin = self
+            .pins
+            .get(component)?
+            .iter()
+            .position(|p| p.name == name)?;
+        Some(PinRef { component, pin })
+    }
+
+    /// Index into [`Self::nets`] of the net `pin` belongs to.
+    pub fn net_of(&self, pin: PinRef) -> Option<usize> {
+        self.net_of.get(&pin).copied()
+    }
+}
+
+type CustomPins<'a> = Box<dyn Fn(&v9::Component) -> Option<Vec<PinDef>> + 'a>;
+
+pub struct NetlistBuilder<'a> {
+    circuit: &'a v9::CircuitData,
+    custom_pins: Option<CustomPins<'a>>,
+}
+
+impl<'a> NetlistBuilder<'a> {
+    pub fn new(circuit: &'a v9::CircuitData) -> Self {
+        Self {
+            circuit,
+            custom_pins: None,
+        }
+    }
+
+    /// Supplies the unrotated pins of `Custom` components, usually looked up
+    /// by `component.custom.id`.
+    pub fn custom_pins(
+        mut self,
+        resolve: impl Fn(&v9::Component) -> Option<Vec<PinDef>> + 'a,
+    ) -> Self {
+        self.custom_pins = Some(Box::new(resolve));
+        self
+    }
+
+    pub fn build(self) -> Netlist {
+        let mut netlist = Netlist::default();
+        for (i, component) in self.circuit.components.iter().enumerate() {
+            let defs = if component.kind.is_custom() {
+                match self.custom_pins.as_ref().and_then(|f| f(component)) {
+                    Some(defs) => defs,
+                    None => {
+                        netlist.unresolved_components.push(i);
+                        Vec::new()
+                    }
+                }
+            } else {
+                component.kind.pins().iter().map(PinDef::from).collect()
+            };
+            netlist.pins.push(place(component, defs));
+        }
+
+        let wires = &self.circuit.wires;
+        let mut sets = DisjointSets::new(wires.len());
+        let mut ends_at: HashMap<Point, Vec<usize>> = HashMap::new();
+        for (i, wire) in wires.iter().enumerate() {
+            let (start, end) = wire.endpoints();
+            ends_at.entry(start).or_default().push(i);
+            if end != start {
+                ends_at.entry(end).or_default().push(i);
+            }
+        }
+        for touching in ends_at.values() {
+            for &other in &touching[1..] {
+                sets.union(touching[0], other);
+            }
+        }
+
+        let mut pins_at: HashMap<Point, Vec<PinRef>> = HashMap::new();
+        for (component, pins) in netlist.pins.iter().enumerate() {
+            for (pin, placed) in pins.iter().enumerate() {
+                let pin = PinRef { component, pin };
+                pins_at.entry(placed.position).or_default().push(pin);
+                if !ends_at.contains_key(&placed.position) {
+                    netlist.unconnected_pins.push(pin);
+                }
+            }
+        }
+
+        for (i, wire) in wires.iter().enumerate() {
+            let (start, end) = wire.endpoints();
+            let ends = if start == end {
+                &[start][..]
+            } else {
+                &[start, end][..]
+            };
+            for &at in ends {
+                let joined = ends_at[&at].iter().any(|&other| other != i);
+                if !joined && !pins_at.contains_key(&at) {
+                    netlist.floating_wire_ends.push(WireEnd { wire: i, at });
+                }
+            }
+        }
+
+        let mut net_index = HashMap::new();
+        for i in 0..wires.len() {
+            let root = sets.find(i);
+            let net = *net_index.entry(root).or_insert_with(|| {
+                netlist.nets.push(Net::default());
+                netlist.nets.len() - 1
+            });
+            netlist.nets[net].wires.push(i);
+        }
+        for (at, wires_here) in &ends_at {
+            let Some(pins) = pins_at.get(at) else {
+                continue;
+            };
+            let net = net_index[&sets.find(wires_here[0])];
+            for &pin in pins {
+                netlist.net_of.insert(pin, net);
+            }
+        }
+        for (&pin, &net) in &netlist.net_of {
+            netlist.nets[net].pins.push(pin);
+        }
+        for net in &mut netlist.nets {
+            net.pins.sort();
+        }
+        netlist
+    }
+}
+
+fn place(component: &v9::Component, defs: Vec<PinDef>) -> Vec<NetPin> {
+    let rotation = Rotation::from(component.rotation);
+    defs.into_iter()
+        .map(|def| {
+            let offset = rotation.apply(def.offset);
+            NetPin {
+                name: def.name,
+                position: Point::new(
+                    component.position.x.wrapping_add(offset.x),
+                    component.position.y.wrapping_add(offset.y),
+                ),
+                direction: def.direction,
+                width: def.width,
+            }
+        })
+        .collect()
+}
+
+struct DisjointSets(Vec<usize>);
+
+impl DisjointSets {
+    fn new(len: usize) -> Self {
+        Self((0..len).collect())
+    }
+
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.0[i] != i {
+            self.0[i] = self.0[self.0[i]];
+            i = self.0[i];
+        }
+        i
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        self.0[a] = b;
+    }
+}
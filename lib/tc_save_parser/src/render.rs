@@ -0,0 +1,331 @@
+//! Drawing circuits as SVG, and as PNG with the `png` feature, without the
+//! game.
+//!
+//! One grid cell is one SVG user unit, so the cell `(x, y)` covers
+//! `x..x + 1` by `y..y + 1` and wires run through cell centres. Components
+//! are drawn as their footprint, coloured by category, with pin markers and
+//! their label. `Custom` components have no footprint of their own in the
+//! catalog and are drawn at the default size. Wires are drawn in their
+//! colour from [`WIRE_PALETTE`], with their comment next to their middle.
+
+use std::fmt::Write;
+
+use crate::{
+    Point,
+    catalog::{Category, PinDirection},
+    v9,
+};
+
+/// Wire colours by `Wire::color`. The colours are arbitrary, not the
+/// game's. Larger values wrap around.
+pub const WIRE_PALETTE: [&str; 12] = [
+    "#e6b422", "#e23d3d", "#ef8a2e", "#f2d43a", "#4fbf4f", "#3cc8c8", "#3d7be2", "#9a55e0",
+    "#e05cb4", "#b0b0b0", "#f0f0f0", "#7a5230",
+];
+
+const BACKGROUND: &str = "#1f2024";
+
+fn category_fill(category: Category) -> &'static str {
+    match category {
+        Category::Special => "#505058",
+        Category::Io => "#3a6ea5",
+        Category::Logic => "#4a4a6a",
+        Category::Arithmetic => "#5a4a7a",
+        Category::Memory => "#6a4a3a",
+        Category::Plumbing => "#3a5a4a",
+        Category::Probe => "#6a6a3a",
+        Category::Device => "#3a5a6a",
+        Category::Custom => "#5a3a5a",
+    }
+}
+
+/// Which cells to draw.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Viewport {
+    /// Everything in the circuit, with a cell of margin.
+    #[default]
+    Fit,
+    /// The cells from `min` to `max`, inclusive.
+    Crop { min: Point, max: Point },
+    /// `width` by `height` cells centred on the circuit's
+    /// `camera_position`.
+    Camera { width: u16, height: u16 },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderOptions {
+    pub viewport: Viewport,
+    /// Pixels per grid cell.
+    pub scale: f32,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            viewport: Viewport::Fit,
+            scale: 16.0,
+        }
+    }
+}
+
+/// A grid cell, in `i32` so that footprints, margins and viewports reaching
+/// past the edge of the `i16` grid don't overflow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct Cell {
+    x: i32,
+    y: i32,
+}
+
+impl Cell {
+    fn new(x: i32, y: i32) -> Self {
+        Self { x, y }
+    }
+
+    /// The cell `offset` away from `at`.
+    fn offset(at: Point, offset: Point) -> Self {
+        Self::new(
+            i32::from(at.x) + i32::from(offset.x),
+            i32::from(at.y) + i32::from(offset.y),
+        )
+    }
+
+    fn min(self, other: Cell) -> Cell {
+        Cell::new(self.x.min(other.x), self.y.min(other.y))
+    }
+
+    fn max(self, other: Cell) -> Cell {
+        Cell::new(self.x.max(other.x), self.y.max(other.y))
+    }
+}
+
+impl From<Point> for Cell {
+    fn from(p: Point) -> Self {
+        Cell::new(i32::from(p.x), i32::from(p.y))
+    }
+}
+
+/// The cells a component covers, as inclusive corners.
+fn component_bounds(component: &v9::Component) -> (Cell, Cell) {
+    let footprint = component.kind.footprint();
+    let rotation = component.rotation();
+    let far = Point::new(
+        footprint.origin.x + footprint.width as i16 - 1,
+        footprint.origin.y + footprint.height as i16 - 1,
+    );
+    let at = component.position;
+    let a = Cell::offset(at, rotation.apply(footprint.origin));
+    let b = Cell::offset(at, rotation.apply(far));
+    (a.min(b), a.max(b))
+}
+
+/// The inclusive corners of the cells `viewport` shows.
+fn view(circuit: &v9::CircuitData, viewport: Viewport) -> (Cell, Cell) {
+    match viewport {
+        Viewport::Crop { min, max } => {
+            let (min, max) = (Cell::from(min), Cell::from(max));
+            (min.min(max), min.max(max))
+        }
+        Viewport::Camera { width, height } => {
+            let camera = Cell::from(circuit.camera_position);
+            let min = Cell::new(
+                camera.x - i32::from(width / 2),
+                camera.y - i32::from(height / 2),
+            );
+            let max = Cell::new(
+                min.x + i32::from(width.max(1)) - 1,
+                min.y + i32::from(height.max(1)) - 1,
+            );
+            (min, max)
+        }
+        Viewport::Fit => {
+            let corners = circuit
+                .components
+                .iter()
+                .flat_map(|c| {
+                    let (min, max) = component_bounds(c);
+                    [min, max]
+                })
+                .chain(
+                    circuit
+                        .wires
+                        .iter()
+                        .flat_map(|w| w.cells().into_iter().map(Cell::from)),
+                );
+            let mut bounds: Option<(Cell, Cell)> = None;
+            for p in corners {
+                bounds = Some(match bounds {
+                    None => (p, p),
+                    Some((min, max)) => (min.min(p), max.max(p)),
+                });
+            }
+            let (min, max) = bounds.unwrap_or_default();
+            (
+                Cell::new(min.x - 1, min.y - 1),
+                Cell::new(max.x + 1, max.y + 1),
+            )
+        }
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Draws `circuit` as an SVG document.
+pub fn to_svg(circuit: &v9::CircuitData, options: &RenderOptions) -> String {
+    let (min, max) = view(circuit, options.viewport);
+    let (width, height) = (max.x - min.x + 1, max.y - min.y + 1);
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="{} {} {} {}" font-family="sans-serif">"##,
+        (width as f32 * options.scale).ceil().max(1.0),
+        (height as f32 * options.scale).ceil().max(1.0),
+        min.x,
+        min.y,
+        width,
+        height
+    )
+    .unwrap();
+    writeln!(
+        svg,
+        r##"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"##,
+        min.x, min.y, width, height, BACKGROUND
+    )
+    .unwrap();
+
+    let mut labels = Vec::new();
+    writeln!(
+        svg,
+        r##"<g id="components" stroke="#101010" stroke-width="0.08">"##
+    )
+    .unwrap();
+    for component in &circuit.components {
+        let (a, b) = component_bounds(component);
+        writeln!(
+            svg,
+            r##"<rect x="{}" y="{}" width="{}" height="{}" rx="0.2" fill="{}"/>"##,
+            a.x as f32 + 0.05,
+            a.y as f32 + 0.05,
+            (b.x - a.x) as f32 + 0.9,
+            (b.y - a.y) as f32 + 0.9,
+            category_fill(component.kind.category())
+        )
+        .unwrap();
+        for pin in component.kind.pins() {
+            let at = Cell::offset(component.position, component.rotation().apply(pin.offset));
+            let fill = match pin.direction {
+                PinDirection::Input => "#d0d0d0",
+                PinDirection::Output => "#f0a030",
+            };
+            writeln!(
+                svg,
+                r##"<circle cx="{}" cy="{}" r="0.2" fill="{}"/>"##,
+                at.x as f32 + 0.5,
+                at.y as f32 + 0.5,
+                fill
+            )
+            .unwrap();
+        }
+        let label = component.custom_string.to_string_lossy();
+        if !label.is_empty() {
+            labels.push(format!(
+                r##"<text x="{}" y="{}" font-size="0.6" fill="#ffffff" text-anchor="middle" dominant-baseline="central">{}</text>"##,
+                (a.x as f32 + b.x as f32 + 1.0) / 2.0,
+                (a.y as f32 + b.y as f32 + 1.0) / 2.0,
+                escape(&label)
+            ));
+        }
+    }
+    writeln!(svg, "</g>").unwrap();
+
+    writeln!(
+        svg,
+        r##"<g id="wires" fill="none" stroke-width="0.25" stroke-linecap="round" stroke-linejoin="round">"##
+    )
+    .unwrap();
+    for wire in &circuit.wires {
+        let color = WIRE_PALETTE[usize::from(wire.color) % WIRE_PALETTE.len()];
+        let points: Vec<_> = wire
+            .corners()
+            .iter()
+            .map(|p| format!("{},{}", f32::from(p.x) + 0.5, f32::from(p.y) + 0.5))
+            .collect();
+        writeln!(
+            svg,
+            r##"<polyline points="{}" stroke="{}"/>"##,
+            points.join(" "),
+            color
+        )
+        .unwrap();
+        let comment = wire.comment.to_string_lossy();
+        if !comment.is_empty() {
+            let cells = wire.cells();
+            let middle = cells[cells.len() / 2];
+            labels.push(format!(
+                r##"<text x="{}" y="{}" font-size="0.5" fill="{}" text-anchor="middle">{}</text>"##,
+                f32::from(middle.x) + 0.5,
+                f32::from(middle.y) + 0.1,
+                color,
+                escape(&comment)
+            ));
+        }
+    }
+    writeln!(svg, "</g>").unwrap();
+
+    writeln!(svg, r##"<g id="labels">"##).unwrap();
+    for label in labels {
+        writeln!(svg, "{}", label).unwrap();
+    }
+    writeln!(svg, "</g>").unwrap();
+    writeln!(svg, "</svg>").unwrap();
+    svg
+}
+
+/// Draws `circuit` as a PNG image, on the CPU. Labels and comments use a
+/// sans-serif system font, and are left out where there is none.
+#[cfg(feature = "png")]
+pub fn to_png(circuit: &v9::CircuitData, options: &RenderOptions) -> Result<Vec<u8>, crate::Error> {
+    use resvg::{tiny_skia, usvg};
+
+    use crate::Error;
+
+    let svg = to_svg(circuit, options);
+    let mut usvg_options = usvg::Options::default();
+    let fonts = usvg_options.fontdb_mut();
+    fonts.load_system_fonts();
+    // The generic family defaults to Arial; fall back to any sans face.
+    let sans = fonts
+        .faces()
+        .flat_map(|face| &face.families)
+        .map(|(family, _)| family)
+        .find(|family| family.contains("Sans") && !family.contains("Mono"))
+        .cloned();
+    if let Some(family) = sans {
+        fonts.set_sans_serif_family(family);
+    }
+    let tree =
+        usvg::Tree::from_str(&svg, &usvg_options).map_err(|e| Error::Render(e.to_string()))?;
+    let size = tree.size().to_int_size();
+    let mut pixmap = tiny_skia::Pixmap::new(size.width(), size.height()).ok_or_else(|| {
+        Error::Render(format!(
+            "can't allocate a {}x{} image",
+            size.width(),
+            size.height()
+        ))
+    })?;
+    resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
+    pixmap
+        .encode_png()
+        .map_err(|e| Error::Render(e.to_string()))
+}
@@ -0,0 +1,109 @@
+//! Typed views of a component's `settings` list.
+//!
+//! The save format stores settings as a bare `Vec<u64>` whose meaning depends
+//! on the component's [`Kind`]. [`ComponentSettings`] decodes the layouts we
+//! know and encodes them back, and keeps anything else as [`Raw`] so nothing
+//! is lost.
+//!
+//! [`Raw`]: ComponentSettings::Raw
+
+use crate::{Kind, model, v7, v8, v9};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ComponentSettings {
+    /// `Constant`: the value driven on the output.
+    Constant { value: u64 },
+    /// `Counter`: added to the count every tick.
+    Counter { increment: u64 },
+    /// `Ram`, `RamFast`, `RamLatency`, `RamDualLoad` and `Rom`: the memory
+    /// size in bytes.
+    Memory { size: u64 },
+    /// `ConfigDelay`: the configured delay in ticks.
+    ConfigDelay { delay: u64 },
+    /// `StaticValue`: the stored value.
+    StaticValue { value: u64 },
+    /// `FileLoader`: the byte offset loading starts from. The file name is
+    /// the component's `custom_string`.
+    FileLoader { offset: u64 },
+    /// `StaticIndexer` and `StaticIndexerConfig`: the selected index.
+    StaticIndexer { index: u64 },
+    /// Any other kind, or a list that doesn't match its kind's layout.
+    Raw(Vec<u64>),
+}
+
+impl ComponentSettings {
+    /// Decodes `settings` as stored for a component of `kind`. Lists whose
+    /// length doesn't match the kind's layout decode as [`Self::Raw`].
+    pub fn decode(kind: Kind, settings: &[u64]) -> Self {
+        let &[first] = settings else {
+            return ComponentSettings::Raw(settings.to_vec());
+        };
+        match kind {
+            Kind::Constant => ComponentSettings::Constant { value: first },
+            Kind::Counter => ComponentSettings::Counter { increment: first },
+            Kind::Ram | Kind::RamFast | Kind::RamLatency | Kind::RamDualLoad | Kind::Rom => {
+                ComponentSettings::Memory { size: first }
+            }
+            Kind::ConfigDelay => ComponentSettings::ConfigDelay { delay: first },
+            Kind::StaticValue => ComponentSettings::StaticValue { value: first },
+            Kind::FileLoader => ComponentSettings::FileLoader { offset: first },
+            Kind::StaticIndexer | Kind::StaticIndexerConfig => {
+                ComponentSettings::StaticIndexer { index: first }
+            }
+            _ => ComponentSettings::Raw(settings.to_vec()),
+        }
+    }
+
+    /// Encodes back into the list stored in the save.
+    pub fn encode(&self) -> Vec<u64> {
+        match self {
+            ComponentSettings::Constant { value } | ComponentSettings::StaticValue { value } => {
+                vec![*value]
+            }
+            ComponentSettings::Counter { increment } => vec![*increment],
+            ComponentSettings::Memory { size } => vec![*size],
+            ComponentSettings::ConfigDelay { delay } => vec![*delay],
+            ComponentSettings::FileLoader { offset } => vec![*offset],
+            ComponentSettings::StaticIndexer { index } => vec![*index],
+            ComponentSettings::Raw(settings) => settings.clone(),
+        }
+    }
+
+    /// Whether this layout is the one `kind` stores. [`Self::Raw`] fits every
+    /// kind.
+    pub fn fits(&self, kind: Kind) -> bool {
+        match self {
+            ComponentSettings::Raw(_) => true,
+            typed => ComponentSettings::decode(kind, &typed.encode()) == *typed,
+        }
+    }
+}
+
+macro_rules! impl_typed_settings {
+    ($($component:ty),*) => {$(
+        impl $component {
+            /// Decodes `settings` according to this component's kind.
+            pub fn typed_settings(&self) -> ComponentSettings {
+                ComponentSettings::decode(self.kind, &self.settings)
+            }
+
+            /// Encodes `value` into `settings`. Returns `false` and leaves
+            /// `settings` untouched if `value` isn't a layout this
+            /// component's kind stores.
+            pub fn set_typed_settings(&mut self, value: &ComponentSettings) -> bool {
+                if !value.fits(self.kind) {
+                    return false;
+                }
+                self.settings = value.encode();
+                true
+            }
+        }
+    )*};
+}
+
+impl_typed_settings!(
+    v7::Component,
+    v8::Component,
+    v9::Component,
+    model::Component
+);
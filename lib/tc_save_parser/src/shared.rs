@@ -1,19 +1,139 @@
-use std::io::{Cursor, Read, Seek};
+use std::{
+    borrow::Cow,
+    fmt,
+    io::{Cursor, Read, Write},
+    path::Path,
+};
 
 use binrw::{BinRead, BinResult, BinWrite, binrw, parser, writer};
+use tc_save_parser_macros::located;
 
-use crate::{v7, v8, v9};
+use crate::{
+    located::{Failure, Tracking, field_path, located_vec},
+    migrate::{self, MigrationReport},
+    model::Circuit,
+    v7, v8, v9,
+};
 
 #[derive(Debug)]
 pub enum Error {
     Io(std::io::Error),
     Binrw(binrw::Error),
     UnsupportedVersion(u8, Vec<u8>),
+    /// The compressed payload starting at `offset` in the file is not valid
+    /// snappy data.
+    Decompress {
+        offset: u64,
+        source: snap::Error,
+    },
+    /// The data ended at `offset` in the decompressed payload while reading
+    /// the field at `path`.
+    Truncated {
+        offset: u64,
+        path: String,
+    },
+    /// The field at `path` has a length prefix of `len`, more than the data
+    /// remaining at `offset` can hold.
+    InvalidLength {
+        offset: u64,
+        path: String,
+        len: u64,
+    },
+    /// A wire can only run horizontally, vertically or diagonally between
+    /// `from` and `to`.
+    NotOctilinear {
+        from: Point,
+        to: Point,
+    },
+    /// No input or output component is labelled with this name.
+    NoSuchPort(String),
+    /// Several input or several output components share this label.
+    DuplicatePort(String),
+    /// The component at this index isn't a simulated memory or keyboard.
+    NoSuchDevice(usize),
+    /// No net carries this signal name.
+    NoSuchSignal(String),
+    /// A yosys netlist couldn't be read or doesn't describe a circuit.
+    InvalidNetlist(String),
+    /// The yosys netlist has no module with this name.
+    NoSuchModule(String),
+    /// A yosys cell has a type with no equivalent component.
+    UnsupportedCell {
+        cell: String,
+        cell_type: String,
+    },
+    /// A testbench vector file is malformed at `line`.
+    InvalidVectors {
+        line: usize,
+        message: String,
+    },
+    /// A rendered image couldn't be rasterised.
+    Render(String),
+    /// A save in text form couldn't be read.
+    InvalidText(String),
 }
 
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Binrw(e) => write!(f, "{}", e),
+            Error::UnsupportedVersion(version, _) => {
+                write!(f, "unsupported save version {}", version)
+            }
+            Error::Decompress { offset, source } => {
+                write!(f, "decompression failed at 0x{:x}: {}", offset, source)
+            }
+            Error::Truncated { offset, path } => {
+                write!(f, "data ended at 0x{:x} while reading {}", offset, path)
+            }
+            Error::InvalidLength { offset, path, len } => {
+                write!(f, "invalid length {} for {} at 0x{:x}", len, path, offset)
+            }
+            Error::NotOctilinear { from, to } => write!(
+                f,
+                "no straight or diagonal wire from ({}, {}) to ({}, {})",
+                from.x, from.y, to.x, to.y
+            ),
+            Error::NoSuchPort(label) => write!(f, "no input or output labelled {:?}", label),
+            Error::DuplicatePort(label) => {
+                write!(f, "more than one input or output labelled {:?}", label)
+            }
+            Error::NoSuchDevice(index) => {
+                write!(f, "component {} is not a simulated device", index)
+            }
+            Error::NoSuchSignal(name) => write!(f, "no signal named {:?}", name),
+            Error::InvalidNetlist(message) => write!(f, "invalid netlist: {}", message),
+            Error::NoSuchModule(name) => write!(f, "no module named {:?}", name),
+            Error::UnsupportedCell { cell, cell_type } => {
+                write!(f, "cell {} has unsupported type {}", cell, cell_type)
+            }
+            Error::InvalidVectors { line, message } => {
+                write!(f, "invalid test vectors on line {}: {}", line, message)
+            }
+            Error::Render(message) => write!(f, "rendering failed: {}", message),
+            Error::InvalidText(message) => write!(f, "invalid circuit text: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
 impl From<binrw::Error> for Error {
     fn from(e: binrw::Error) -> Self {
-        Self::Binrw(e)
+        let failure = match (e.root_cause(), e.custom_err::<Failure>()) {
+            (binrw::Error::Custom { pos, .. }, Some(failure)) => Some((*pos, failure.clone())),
+            _ => None,
+        };
+        let Some((offset, failure)) = failure else {
+            return Self::Binrw(e);
+        };
+        let path = field_path(&e);
+        match failure {
+            Failure::Decompress(source) => Self::Decompress { offset, source },
+            Failure::Truncated => Self::Truncated { offset, path },
+            Failure::InvalidLength(len) => Self::InvalidLength { offset, path, len },
+        }
     }
 }
 
@@ -24,8 +144,9 @@ impl From<std::io::Error> for Error {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CircuitDataVersion {
-    // V7(v7::CircuitData),
+    V7(v7::CircuitData),
     V8(v8::CircuitData),
     V9(v9::CircuitData),
     Unknown(Vec<u8>),
@@ -40,27 +161,36 @@ impl Default for CircuitDataVersion {
 impl CircuitDataVersion {
     #[parser(reader)]
     fn parse(version: u8) -> BinResult<Self> {
+        let pos = reader.stream_position()?;
         let mut data = Vec::new();
         reader.read_to_end(&mut data)?;
-        let data = snap::raw::Decoder::new().decompress_vec(&data).unwrap();
-        let mut cursor = Cursor::new(&data);
+        let data = snap::raw::Decoder::new()
+            .decompress_vec(&data)
+            .map_err(|e| Failure::Decompress(e).at(pos))?;
+        let mut reader = Tracking::new(Cursor::new(&data));
         match version {
-            // 7 => Ok(Self::V7(v7::CircuitData::read(&mut cursor)?)),
-            8 => Ok(Self::V8(v8::CircuitData::read(&mut cursor)?)),
-            9 => Ok(Self::V9(v9::CircuitData::read(&mut cursor)?)),
+            7 => Ok(Self::V7(reader.read()?)),
+            8 => Ok(Self::V8(reader.read()?)),
+            9 => Ok(Self::V9(reader.read()?)),
             _ => Ok(Self::Unknown(data)),
         }
     }
 
-    #[writer(writer)]
-    fn write(&self) -> BinResult<()> {
-        let data = match self {
-            // Self::V7(data) => data.get_bytes(),
+    /// The payload as stored before compression.
+    pub(crate) fn payload(&self) -> BinResult<Vec<u8>> {
+        match self {
+            Self::V7(data) => data.get_bytes(),
             Self::V8(data) => data.get_bytes(),
             Self::V9(data) => data.get_bytes(),
-            Self::Unknown(data) => data.clone(),
-        };
-        let data = snap::raw::Encoder::new().compress_vec(&data).unwrap();
+            Self::Unknown(data) => Ok(data.clone()),
+        }
+    }
+
+    #[writer(writer)]
+    fn write(&self) -> BinResult<()> {
+        let data = snap::raw::Encoder::new()
+            .compress_vec(&self.payload()?)
+            .map_err(std::io::Error::from)?;
         writer.write_all(&data)?;
         Ok(())
     }
@@ -70,6 +200,7 @@ impl CircuitDataVersion {
 #[br(little)]
 #[bw(little)]
 #[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CircuitDataFile {
     pub version: u8,
     #[br(parse_with = CircuitDataVersion::parse, args (version))]
@@ -78,57 +209,222 @@ pub struct CircuitDataFile {
 }
 
 impl CircuitDataFile {
-    pub fn load(path: &str) -> Result<Self, Error> {
-        let mut fh = std::fs::File::open(path)?;
-        let cdf = Self::read(&mut fh)?;
-        Ok(cdf)
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let fh = std::fs::File::open(path)?;
+        Self::from_reader(std::io::BufReader::new(fh))
     }
 
-    pub fn save(&self, path: &str) -> Result<(), Error> {
-        let mut fh = std::fs::File::create(path)?;
-        self.write(&mut fh)?;
-        Ok(())
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let fh = std::fs::File::create(path)?;
+        self.to_writer(std::io::BufWriter::new(fh))
     }
 
-    pub fn debug_dump(path: &str) -> Result<Vec<u8>, Error> {
-        let mut fh = std::fs::File::open(path)?;
+    /// Reads a whole file from `reader`. The reader doesn't need to be
+    /// seekable, so stdin and decompressing streams work.
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self, Error> {
         let mut data = Vec::new();
-        fh.seek(std::io::SeekFrom::Start(1))?;
-        fh.read_to_end(&mut data)?;
-        let data = snap::raw::Decoder::new().decompress_vec(&data).unwrap();
-        Ok(data)
+        reader.read_to_end(&mut data)?;
+        Self::from_bytes(&data)
+    }
+
+    pub fn to_writer<W: Write>(&self, mut writer: W) -> Result<(), Error> {
+        writer.write_all(&self.to_bytes()?)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self, Error> {
+        let cdf = Tracking::new(Cursor::new(data)).read()?;
+        Ok(cdf)
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let mut buf = Cursor::new(Vec::new());
+        self.write(&mut buf)?;
+        Ok(buf.into_inner())
+    }
+
+    /// Converts the circuit to `version` directly through the
+    /// [model](crate::model), so nothing is lost to an intermediate version.
+    pub fn convert_to(self, version: u8) -> Result<(Self, MigrationReport), Error> {
+        if !(7..=9).contains(&version) {
+            return Err(Error::UnsupportedVersion(version, vec![]));
+        }
+        if self.version == version && !matches!(self.circuit, CircuitDataVersion::Unknown(_)) {
+            return Ok((self, MigrationReport::default()));
+        }
+        let defaulted = match &self.circuit {
+            CircuitDataVersion::V7(data) => migrate::default_linked_components(&data.components),
+            CircuitDataVersion::V8(data) if version == 7 => {
+                migrate::default_memory(&data.components)
+            }
+            _ => MigrationReport::default(),
+        };
+        let (circuit, mut report) = Circuit::try_from(self)?.into_version(version)?;
+        report.merge(defaulted);
+        Ok((Self { version, circuit }, report))
+    }
+
+    pub fn into_latest(self) -> Result<(v9::CircuitData, MigrationReport), Error> {
+        match self.convert_to(9)? {
+            (
+                CircuitDataFile {
+                    circuit: CircuitDataVersion::V9(data),
+                    ..
+                },
+                report,
+            ) => Ok((data, report)),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn debug_dump(path: impl AsRef<Path>) -> Result<Vec<u8>, Error> {
+        let data = std::fs::read(path)?;
+        Self::debug_dump_bytes(&data)
+    }
+
+    /// Returns the decompressed payload of a file, without the version byte.
+    pub fn debug_dump_bytes(data: &[u8]) -> Result<Vec<u8>, Error> {
+        snap::raw::Decoder::new()
+            .decompress_vec(data.get(1..).unwrap_or_default())
+            .map_err(|source| Error::Decompress { offset: 1, source })
     }
 }
 
+/// A length-prefixed string. The game doesn't check that strings are UTF-8,
+/// so the bytes are kept as stored and only decoded on request.
+///
+/// The string used to be a public `value: String` field. Read it with
+/// [`CDString::value`] or convert into a `String`, and build one from a
+/// `&str` or `String` with `From`.
+#[located]
 #[binrw]
 #[br(little)]
 #[bw(little)]
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct CDString {
-    #[bw(try_calc(u16::try_from(value.len())))]
+    #[bw(try_calc(u16::try_from(bytes.len())))]
     len: u16,
-    #[br(count = len)]
-    #[br(map = |s: Vec<u8>| String::from_utf8_lossy(&s).to_string())]
-    #[bw(map = |s| s.clone().into_bytes())]
-    pub value: String,
+    #[br(parse_with = located_vec, args(u64::from(len)))]
+    bytes: Vec<u8>,
+}
+
+impl CDString {
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// The string, if it is valid UTF-8.
+    pub fn as_str(&self) -> Option<&str> {
+        std::str::from_utf8(&self.bytes).ok()
+    }
+
+    /// The string, with invalid UTF-8 replaced by U+FFFD.
+    pub fn to_string_lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.bytes)
+    }
+
+    /// The string as text, like the `value` field it replaces. Invalid UTF-8
+    /// is replaced by U+FFFD; use [`Self::as_bytes`] to see it as stored.
+    pub fn value(&self) -> Cow<'_, str> {
+        self.to_string_lossy()
+    }
+
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
 }
 
 impl From<&str> for CDString {
     fn from(value: &str) -> Self {
         Self {
-            value: value.to_string(),
+            bytes: value.as_bytes().to_vec(),
+        }
+    }
+}
+
+impl From<String> for CDString {
+    fn from(value: String) -> Self {
+        Self {
+            bytes: value.into_bytes(),
+        }
+    }
+}
+
+/// Replaces invalid UTF-8 by U+FFFD, like [`CDString::value`].
+impl From<CDString> for String {
+    fn from(value: CDString) -> Self {
+        match String::from_utf8(value.bytes) {
+            Ok(text) => text,
+            Err(e) => String::from_utf8_lossy(e.as_bytes()).into_owned(),
+        }
+    }
+}
+
+impl From<Vec<u8>> for CDString {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self { bytes }
+    }
+}
+
+impl PartialEq<str> for CDString {
+    fn eq(&self, other: &str) -> bool {
+        self.bytes == other.as_bytes()
+    }
+}
+
+impl PartialEq<&str> for CDString {
+    fn eq(&self, other: &&str) -> bool {
+        self.bytes == other.as_bytes()
+    }
+}
+
+/// Valid UTF-8 serializes as a string and anything else as a list of bytes,
+/// so no string changes on the way through.
+#[cfg(feature = "serde")]
+impl serde::Serialize for CDString {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.as_str() {
+            Some(text) => serializer.serialize_str(text),
+            None => self.bytes.serialize(serializer),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for CDString {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Text(String),
+            Bytes(Vec<u8>),
         }
+        Ok(match Repr::deserialize(deserializer)? {
+            Repr::Text(text) => text.into(),
+            Repr::Bytes(bytes) => bytes.into(),
+        })
     }
 }
 
-#[derive(BinRead, BinWrite, Debug, Default, Clone)]
+#[located]
+#[derive(BinRead, BinWrite, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Point {
     pub x: i16,
     pub y: i16,
 }
 
 impl Point {
-    pub fn new(x: i16, y: i16) -> Self {
+    pub const fn new(x: i16, y: i16) -> Self {
         Self { x, y }
     }
 }
@@ -0,0 +1,302 @@
+//! Models for memories and I/O devices.
+//!
+//! Memories hold words of the component's `word_size`, sized by their
+//! [`ComponentSettings::Memory`] setting in bytes, up to
+//! [`MAX_MEMORY_WORDS`]. Memories linked to each other, directly or through
+//! other memories and whichever side holds the link, share one storage, and
+//! consoles and pixel screens display the memory they are linked to.
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, VecDeque},
+    rc::Rc,
+};
+
+use super::{Model, Pins};
+use crate::{ComponentSettings, Kind, catalog::Width, v9};
+
+/// Size of a memory without a size setting, in bytes.
+pub const DEFAULT_MEMORY_BYTES: u64 = 1 << 16;
+
+/// Largest memory simulated, in words. Larger size settings are cut down to
+/// this, keeping a single memory to 8 MiB.
+pub const MAX_MEMORY_WORDS: u64 = 1 << 20;
+
+/// Storage of a memory component, shared with every component linked to it.
+#[derive(Debug, Clone)]
+pub struct Memory {
+    words: Rc<RefCell<Vec<u64>>>,
+    word_bits: u32,
+}
+
+impl Memory {
+    fn new(component: &v9::Component) -> Self {
+        let word_bits = Width::Word.bits(component.word_size);
+        let bytes = match component.typed_settings() {
+            ComponentSettings::Memory { size } => size,
+            _ => DEFAULT_MEMORY_BYTES,
+        };
+        let word_bytes = u64::from(word_bits.div_ceil(8));
+        let len = bytes.div_ceil(word_bytes).clamp(1, MAX_MEMORY_WORDS);
+        Self {
+            words: Rc::new(RefCell::new(vec![0; len as usize])),
+            word_bits,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn word_bits(&self) -> u32 {
+        self.word_bits
+    }
+
+    /// Reads a word. Addresses wrap around the memory's size.
+    pub fn read(&self, address: u64) -> u64 {
+        let words = self.words.borrow();
+        words[(address % words.len() as u64) as usize]
+    }
+
+    pub fn write(&self, address: u64, value: u64) {
+        let mut words = self.words.borrow_mut();
+        let len = words.len() as u64;
+        words[(address % len) as usize] = value & super::mask(self.word_bits);
+    }
+
+    pub fn words(&self) -> Vec<u64> {
+        self.words.borrow().clone()
+    }
+
+    /// The contents as little-endian bytes, one word after another.
+    pub fn bytes(&self) -> Vec<u8> {
+        let word_bytes = self.word_bits.div_ceil(8) as usize;
+        self.words
+            .borrow()
+            .iter()
+            .flat_map(|w| w.to_le_bytes().into_iter().take(word_bytes))
+            .collect()
+    }
+
+    /// Fills memory from little-endian bytes, starting at address 0. Data
+    /// past the end of memory is ignored.
+    pub fn load(&self, bytes: &[u8]) {
+        let word_bytes = self.word_bits.div_ceil(8) as usize;
+        let mut words = self.words.borrow_mut();
+        for (word, chunk) in words.iter_mut().zip(bytes.chunks(word_bytes)) {
+            let mut buf = [0; 8];
+            buf[..chunk.len()].copy_from_slice(chunk);
+            *word = u64::from_le_bytes(buf);
+        }
+    }
+}
+
+/// Key presses waiting to be read by a keyboard.
+pub(super) type Keys = Rc<RefCell<VecDeque<u8>>>;
+
+/// Device state the simulator keeps to give access to memories, keyboards
+/// and the halt flag.
+#[derive(Default)]
+pub(super) struct Devices {
+    pub(super) memories: HashMap<usize, Memory>,
+    pub(super) keyboards: HashMap<usize, Keys>,
+    pub(super) halted: Rc<Cell<bool>>,
+    /// The memory a console or screen displays.
+    pub(super) displays: HashMap<usize, Memory>,
+}
+
+impl Devices {
+    /// Creates the memories of `circuit`, sharing storage between linked
+    /// ones, and attaches displays to the memories they link to.
+    pub(super) fn new(circuit: &v9::CircuitData) -> Self {
+        let mut devices = Devices::default();
+        let by_id: HashMap<u64, usize> = circuit
+            .components
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (c.permanent_id, i))
+            .collect();
+        let linked_memories = |c: &v9::Component| {
+            c.linked_components
+                .linked_components
+                .iter()
+                .filter_map(|id| by_id.get(id).copied())
+                .filter(|&i| circuit.components[i].kind.is_memory())
+                .collect::<Vec<_>>()
+        };
+
+        // Group linked memories, each group under its lowest index, so the
+        // direction of a link doesn't matter.
+        let mut group: Vec<usize> = (0..circuit.components.len()).collect();
+        let memories: Vec<_> = circuit
+            .components
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.kind.is_memory())
+            .map(|(i, _)| i)
+            .collect();
+        for &i in &memories {
+            for other in linked_memories(&circuit.components[i]) {
+                let (a, b) = (root(&mut group, i), root(&mut group, other));
+                group[a.max(b)] = a.min(b);
+            }
+        }
+        // The lowest index comes first, so its storage exists by the time
+        // the rest of its group looks for it.
+        for &i in &memories {
+            let first = root(&mut group, i);
+            let memory = match devices.memories.get(&first) {
+                Some(shared) => shared.clone(),
+                None => Memory::new(&circuit.components[first]),
+            };
+            devices.memories.insert(i, memory);
+        }
+
+        let linked = |c: &v9::Component| linked_memories(c).first().copied();
+        for (i, component) in circuit.components.iter().enumerate() {
+            if matches!(component.kind, Kind::Console | Kind::PixelScreen)
+                && let Some(memory) = linked(component).and_then(|l| devices.memories.get(&l))
+            {
+                devices.displays.insert(i, memory.clone());
+            }
+        }
+        devices
+    }
+
+    pub(super) fn model_for(&mut self, index: usize, kind: Kind) -> Option<Box<dyn Model>> {
+        let memory = || self.memories.get(&index).cloned();
+        match kind {
+            Kind::Ram | Kind::RamFast | Kind::Rom => Some(Box::new(Ram(memory()?))),
+            Kind::RamLatency => Some(Box::new(LatencyRam {
+                memory: memory()?,
+                ready: None,
+            })),
+            Kind::RamDualLoad => Some(Box::new(DualLoadRam(memory()?))),
+            Kind::Keyboard => {
+                let keys = self.keyboards.entry(index).or_default().clone();
+                Some(Box::new(Keyboard(keys)))
+            }
+            Kind::Time => Some(Box::new(Time(0))),
+            Kind::Halt => Some(Box::new(Halt(self.halted.clone()))),
+            _ => None,
+        }
+    }
+}
+
+/// The component heading `i`'s group of linked memories.
+fn root(group: &mut [usize], mut i: usize) -> usize {
+    while group[i] != i {
+        group[i] = group[group[i]];
+        i = group[i];
+    }
+    i
+}
+
+/// Reads while `load` is high and writes at the end of a tick where `save`
+/// is high. A `Rom` has no `save` pin and is only written by loading it.
+struct Ram(Memory);
+
+impl Model for Ram {
+    fn eval(&self, pins: &mut Pins) {
+        if pins.get_bit("load") {
+            pins.set("out", self.0.read(pins.get("address")));
+        }
+    }
+
+    fn latch(&mut self, pins: &Pins) {
+        if pins.has("save") && pins.get_bit("save") {
+            self.0.write(pins.get("address"), pins.get("value"));
+        }
+    }
+}
+
+/// Like [`Ram`], but a read shows on the output one tick later.
+struct LatencyRam {
+    memory: Memory,
+    ready: Option<u64>,
+}
+
+impl Model for LatencyRam {
+    fn eval(&self, pins: &mut Pins) {
+        if let Some(value) = self.ready {
+            pins.set("out", value);
+        }
+    }
+
+    fn latch(&mut self, pins: &Pins) {
+        let address = pins.get("address");
+        self.ready = pins.get_bit("load").then(|| self.memory.read(address));
+        if pins.get_bit("save") {
+            self.memory.write(address, pins.get("value"));
+        }
+    }
+}
+
+/// Two read ports; writes go to `address_a`.
+struct DualLoadRam(Memory);
+
+impl Model for DualLoadRam {
+    fn eval(&self, pins: &mut Pins) {
+        if pins.get_bit("load_a") {
+            pins.set("out_a", self.0.read(pins.get("address_a")));
+        }
+        if pins.get_bit("load_b") {
+            pins.set("out_b", self.0.read(pins.get("address_b")));
+        }
+    }
+
+    fn latch(&mut self, pins: &Pins) {
+        if pins.get_bit("save") {
+            self.0.write(pins.get("address_a"), pins.get("value"));
+        }
+    }
+}
+
+/// Outputs the next queued key while `enable` is high, consuming it at the
+/// end of the tick. Reads 0 when no key is waiting.
+struct Keyboard(Keys);
+
+impl Model for Keyboard {
+    fn eval(&self, pins: &mut Pins) {
+        if pins.get_bit("enable") {
+            let key = self.0.borrow().front().copied().unwrap_or(0);
+            pins.set("out", u64::from(key));
+        }
+    }
+
+    fn latch(&mut self, pins: &Pins) {
+        if pins.get_bit("enable") {
+            self.0.borrow_mut().pop_front();
+        }
+    }
+}
+
+/// Outputs the number of ticks run, so runs are reproducible.
+struct Time(u64);
+
+impl Model for Time {
+    fn eval(&self, pins: &mut Pins) {
+        pins.set("out", self.0);
+    }
+
+    fn latch(&mut self, _pins: &Pins) {
+        self.0 += 1;
+    }
+}
+
+/// Halts the simulation at the end of a tick where `enable` is high.
+struct Halt(Rc<Cell<bool>>);
+
+impl Model for Halt {
+    fn eval(&self, _pins: &mut Pins) {}
+
+    fn latch(&mut self, pins: &Pins) {
+        if pins.get_bit("enable") {
+            self.0.set(true);
+        }
+    }
+}
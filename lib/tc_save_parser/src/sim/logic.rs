@@ -0,0 +1,362 @@
+//! Models for gates, arithmetic, plumbing and the sequential building blocks.
+
+use std::collections::VecDeque;
+
+use super::{Model, Pins, mask};
+use crate::{ComponentSettings, Kind, v9};
+
+/// A stateless component computed by a plain function.
+struct Gate(fn(&mut Pins));
+
+impl Model for Gate {
+    fn eval(&self, pins: &mut Pins) {
+        (self.0)(pins)
+    }
+}
+
+/// Drives a fixed value.
+struct Constant(u64);
+
+impl Model for Constant {
+    fn eval(&self, pins: &mut Pins) {
+        pins.set("out", self.0);
+    }
+}
+
+/// Outputs the stored value while `load` is high, and stores `value` at the
+/// end of a tick where `save` is high.
+#[derive(Default)]
+struct Register(u64);
+
+impl Model for Register {
+    fn eval(&self, pins: &mut Pins) {
+        if pins.get_bit("load") {
+            pins.set("out", self.0);
+        }
+    }
+
+    fn latch(&mut self, pins: &Pins) {
+        if pins.get_bit("save") {
+            self.0 = pins.get("value");
+        }
+    }
+}
+
+/// Outputs its input from `delay` ticks ago.
+struct DelayLine(VecDeque<u64>);
+
+impl DelayLine {
+    fn new(delay: u64) -> Self {
+        Self(VecDeque::from(vec![0; delay.clamp(1, 1 << 16) as usize]))
+    }
+}
+
+impl Model for DelayLine {
+    fn eval(&self, pins: &mut Pins) {
+        pins.set("out", self.0[0]);
+    }
+
+    fn latch(&mut self, pins: &Pins) {
+        self.0.pop_front();
+        self.0.push_back(pins.get("a"));
+    }
+}
+
+/// Outputs the count, then adds the increment or loads `value` when
+/// `overwrite` is high.
+struct Counter {
+    count: u64,
+    increment: u64,
+}
+
+impl Model for Counter {
+    fn eval(&self, pins: &mut Pins) {
+        pins.set("out", self.count);
+    }
+
+    fn latch(&mut self, pins: &Pins) {
+        self.count = if pins.get_bit("overwrite") {
+            pins.get("value")
+        } else {
+            self.count.wrapping_add(self.increment) & mask(pins.width("out"))
+        };
+    }
+}
+
+/// Spreads the value set through the simulator over the
+/// [port pins](Kind::port_pins).
+struct Input(Kind);
+
+impl Model for Input {
+    fn eval(&self, pins: &mut Pins) {
+        let mut value = pins.external();
+        for pin in self.0.port_pins() {
+            pins.set(pin.name, value);
+            value = value.checked_shr(pins.width(pin.name)).unwrap_or(0);
+        }
+    }
+}
+
+/// Drives nothing; the simulator reads its inputs.
+struct Output;
+
+impl Model for Output {
+    fn eval(&self, _pins: &mut Pins) {}
+}
+
+fn not_bit(p: &mut Pins) {
+    p.set("out", !p.get("a"));
+}
+fn and_bit(p: &mut Pins) {
+    p.set("out", p.get("a") & p.get("b"));
+}
+fn and3_bit(p: &mut Pins) {
+    p.set("out", p.get("a") & p.get("b") & p.get("c"));
+}
+fn nand(p: &mut Pins) {
+    p.set("out", !(p.get("a") & p.get("b")));
+}
+fn or(p: &mut Pins) {
+    p.set("out", p.get("a") | p.get("b"));
+}
+fn or3_bit(p: &mut Pins) {
+    p.set("out", p.get("a") | p.get("b") | p.get("c"));
+}
+fn nor(p: &mut Pins) {
+    p.set("out", !(p.get("a") | p.get("b")));
+}
+fn xor(p: &mut Pins) {
+    p.set("out", p.get("a") ^ p.get("b"));
+}
+fn xnor(p: &mut Pins) {
+    p.set("out", !(p.get("a") ^ p.get("b")));
+}
+fn switch(p: &mut Pins) {
+    if p.get_bit("enable") {
+        p.set("out", p.get("a"));
+    }
+}
+fn full_adder(p: &mut Pins) {
+    let sum = p.get("a") + p.get("b") + p.get("carry_in");
+    p.set("sum", sum);
+    p.set("carry_out", sum >> 1);
+}
+
+/// Sign-extends the low `bits` of `value`.
+fn signed(value: u64, bits: u32) -> i64 {
+    let shift = 64 - bits.clamp(1, 64);
+    ((value << shift) as i64) >> shift
+}
+
+fn equal(p: &mut Pins) {
+    p.set("out", u64::from(p.get("a") == p.get("b")));
+}
+fn less_u(p: &mut Pins) {
+    p.set("out", u64::from(p.get("a") < p.get("b")));
+}
+fn less_s(p: &mut Pins) {
+    let bits = p.width("a");
+    p.set(
+        "out",
+        u64::from(signed(p.get("a"), bits) < signed(p.get("b"), bits)),
+    );
+}
+fn neg(p: &mut Pins) {
+    p.set("out", p.get("a").wrapping_neg());
+}
+fn inc(p: &mut Pins) {
+    p.set("out", p.get("a").wrapping_add(1));
+}
+fn add(p: &mut Pins) {
+    let bits = p.width("out");
+    let sum = u128::from(p.get("a")) + u128::from(p.get("b")) + u128::from(p.get("carry_in"));
+    p.set("out", sum as u64);
+    p.set("carry_out", (sum >> bits) as u64);
+}
+fn mul(p: &mut Pins) {
+    let bits = p.width("out");
+    let product = u128::from(p.get("a")) * u128::from(p.get("b"));
+    p.set("out", product as u64);
+    p.set("high", (product >> bits) as u64);
+}
+fn div(p: &mut Pins) {
+    let (a, b) = (p.get("a"), p.get("b"));
+    p.set("quotient", a.checked_div(b).unwrap_or(0));
+    p.set("remainder", a.checked_rem(b).unwrap_or(0));
+}
+fn modulo(p: &mut Pins) {
+    p.set("out", p.get("a").checked_rem(p.get("b")).unwrap_or(0));
+}
+fn lsl(p: &mut Pins) {
+    let a = p.get("a");
+    let shifted = u32::try_from(p.get("b"))
+        .ok()
+        .and_then(|s| a.checked_shl(s));
+    p.set("out", shifted.unwrap_or(0));
+}
+fn lsr(p: &mut Pins) {
+    let a = p.get("a");
+    let shifted = u32::try_from(p.get("b"))
+        .ok()
+        .and_then(|s| a.checked_shr(s));
+    p.set("out", shifted.unwrap_or(0));
+}
+fn asr(p: &mut Pins) {
+    let bits = p.width("a");
+    let shift = p.get("b").min(63) as u32;
+    p.set("out", (signed(p.get("a"), bits) >> shift) as u64);
+}
+fn rotate(p: &mut Pins, left: bool) {
+    let bits = p.width("a");
+    let (a, by) = (p.get("a"), (p.get("b") % u64::from(bits)) as u32);
+    let by = if left { by } else { (bits - by) % bits };
+    if by == 0 {
+        p.set("out", a);
+    } else {
+        p.set("out", (a << by) | (a >> (bits - by)));
+    }
+}
+fn rol(p: &mut Pins) {
+    rotate(p, true)
+}
+fn ror(p: &mut Pins) {
+    rotate(p, false)
+}
+fn mux(p: &mut Pins) {
+    let value = if p.get_bit("select") {
+        p.get("b")
+    } else {
+        p.get("a")
+    };
+    p.set("out", value);
+}
+fn decoder(p: &mut Pins, inputs: &[&str]) {
+    let selected = inputs
+        .iter()
+        .enumerate()
+        .map(|(i, name)| (p.get(name) & 1) << i)
+        .sum::<u64>();
+    for i in 0..1 << inputs.len() {
+        p.set(&format!("out{}", i), u64::from(i == selected));
+    }
+}
+fn decoder1(p: &mut Pins) {
+    decoder(p, &["a"])
+}
+fn decoder2(p: &mut Pins) {
+    decoder(p, &["a", "b"])
+}
+fn decoder3(p: &mut Pins) {
+    decoder(p, &["a", "b", "c"])
+}
+
+/// Joins `in0..` into `out`, lowest part first.
+fn make(p: &mut Pins) {
+    let parts = p.defs.iter().filter(|d| d.name.starts_with("in")).count();
+    let mut value = 0u64;
+    for i in (0..parts).rev() {
+        let name = format!("in{}", i);
+        value = value.checked_shl(p.width(&name)).unwrap_or(0) | p.get(&name);
+    }
+    p.set("out", value);
+}
+
+/// Splits `in` across `out0..`, lowest part first.
+fn split(p: &mut Pins) {
+    let parts = p.defs.iter().filter(|d| d.name.starts_with("out")).count();
+    let mut value = p.get("in");
+    for i in 0..parts {
+        let name = format!("out{}", i);
+        let bits = p.width(&name);
+        p.set(&name, value);
+        value = value.checked_shr(bits).unwrap_or(0);
+    }
+}
+
+fn setting(component: &v9::Component) -> u64 {
+    component.settings.first().copied().unwrap_or(0)
+}
+
+/// Picks the model for a component, or `None` for kinds that aren't
+/// simulated.
+pub(super) fn model_for(component: &v9::Component) -> Option<Box<dyn Model>> {
+    let gate = |f: fn(&mut Pins)| Some(Box::new(Gate(f)) as Box<dyn Model>);
+    match component.kind {
+        Kind::Off => Some(Box::new(Constant(0))),
+        Kind::On => Some(Box::new(Constant(1))),
+        Kind::Constant | Kind::StaticValue => {
+            let value = match component.typed_settings() {
+                ComponentSettings::Constant { value }
+                | ComponentSettings::StaticValue { value } => value,
+                _ => setting(component),
+            };
+            Some(Box::new(Constant(value)))
+        }
+        Kind::NotBit | Kind::NotWord => gate(not_bit),
+        Kind::AndBit | Kind::AndWord => gate(and_bit),
+        Kind::And3Bit => gate(and3_bit),
+        Kind::NandBit | Kind::NandWord => gate(nand),
+        Kind::OrBit | Kind::OrWord => gate(or),
+        Kind::Or3Bit => gate(or3_bit),
+        Kind::NorBit | Kind::NorWord => gate(nor),
+        Kind::XorBit | Kind::XorWord => gate(xor),
+        Kind::XnorBit | Kind::XnorWord => gate(xnor),
+        Kind::SwitchBit | Kind::SwitchWord => gate(switch),
+        Kind::FullAdder => gate(full_adder),
+        Kind::Equal => gate(equal),
+        Kind::LessU => gate(less_u),
+        Kind::LessS => gate(less_s),
+        Kind::Neg => gate(neg),
+        Kind::Inc => gate(inc),
+        Kind::Add => gate(add),
+        Kind::Mul => gate(mul),
+        Kind::Div => gate(div),
+        Kind::Mod => gate(modulo),
+        Kind::Lsl => gate(lsl),
+        Kind::Lsr => gate(lsr),
+        Kind::Asr => gate(asr),
+        Kind::Rol => gate(rol),
+        Kind::Ror => gate(ror),
+        Kind::Mux => gate(mux),
+        Kind::Decoder1 => gate(decoder1),
+        Kind::Decoder2 => gate(decoder2),
+        Kind::Decoder3 => gate(decoder3),
+        Kind::MakerBit2
+        | Kind::MakerBit4
+        | Kind::MakerBit8
+        | Kind::MakerWord2
+        | Kind::MakerWord4
+        | Kind::MakerWord8
+        | Kind::Concatenator2
+        | Kind::Concatenator4
+        | Kind::Concatenator8 => gate(make),
+        Kind::SplitterBit2
+        | Kind::SplitterBit4
+        | Kind::SplitterBit8
+        | Kind::SplitterWord2
+        | Kind::SplitterWord4
+        | Kind::SplitterWord8 => gate(split),
+        Kind::RegisterBit | Kind::RegisterWord => Some(Box::<Register>::default()),
+        Kind::DelayLineBit | Kind::DelayLineWord => Some(Box::new(DelayLine::new(1))),
+        Kind::ConfigDelay => {
+            let delay = match component.typed_settings() {
+                ComponentSettings::ConfigDelay { delay } => delay,
+                _ => 1,
+            };
+            Some(Box::new(DelayLine::new(delay)))
+        }
+        Kind::Counter => {
+            let increment = match component.typed_settings() {
+                ComponentSettings::Counter { increment } => increment,
+                _ => 1,
+            };
+            Some(Box::new(Counter {
+                count: 0,
+                increment,
+            }))
+        }
+        kind if kind.is_input() => Some(Box::new(Input(kind))),
+        kind if kind.is_output() => Some(Box::new(Output)),
+        _ => None,
+    }
+}
@@ -0,0 +1,451 @@
+//! Tick-based simulation of a saved circuit.
+//!
+//! Every tick runs in two phases. First the combinational logic settles:
+//! each component drives its outputs from the current value of its input
+//! nets, and this repeats until no net changes. A net driven by several
+//! outputs takes the OR of them; an undriven net reads 0. Then sequential
+//! components (registers, delay lines, counters) latch their inputs, and the
+//! new state shows on their outputs next tick.
+//!
+//! Inputs are set on `CcInput`/`LevelInput*` components and outputs read
+//! from `CcOutput`/`LevelOutput*` components, by their label
+//! (`custom_string`) or component index. A label shared by several inputs,
+//! or by several outputs, can't be used to address them. Memories, consoles, screens and
+//! keyboards are reached by component index.
+//!
+//! Nets carry up to 64 bits. Components with a wider pin, and the immediate
+//! registers and delay lines, aren't simulated; like any other kind without
+//! a model they drive nothing and are listed by [`Simulator::unsupported`].
+
+mod devices;
+mod logic;
+pub mod vcd;
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+
+pub use devices::{DEFAULT_MEMORY_BYTES, MAX_MEMORY_WORDS, Memory};
+
+use crate::{
+    Error, Kind,
+    netlist::{NetPin, Netlist, NetlistBuilder, PinRef},
+    v9,
+};
+
+/// Behaviour of one simulated component.
+pub trait Model {
+    /// Drives outputs from the inputs and the current state.
+    fn eval(&self, pins: &mut Pins);
+
+    /// Updates state at the end of a tick, once the inputs have settled.
+    fn latch(&mut self, _pins: &Pins) {}
+}
+
+/// A component's view of its pins during a tick. A pin the component doesn't
+/// have, as can happen with a netlist built for another layout, reads 0 and
+/// ignores what is driven on it, like an unconnected pin.
+pub struct Pins<'a> {
+    defs: &'a [NetPin],
+    values: Vec<u64>,
+    drives: Vec<Option<u64>>,
+    word_size: i64,
+    external: u64,
+}
+
+impl Pins<'_> {
+    fn index(&self, name: &str) -> Option<usize> {
+        self.defs.iter().position(|p| p.name == name)
+    }
+
+    /// Width of a pin in bits, or 0 for a pin the component doesn't have.
+    pub fn width(&self, name: &str) -> u32 {
+        self.index(name)
+            .map_or(0, |i| self.defs[i].width.bits(self.word_size))
+    }
+
+    /// The value on a pin's net, masked to the pin's width.
+    pub fn get(&self, name: &str) -> u64 {
+        self.index(name).map_or(0, |i| self.values[i])
+    }
+
+    /// Whether the component has a pin called `name`.
+    pub fn has(&self, name: &str) -> bool {
+        self.index(name).is_some()
+    }
+
+    pub fn get_bit(&self, name: &str) -> bool {
+        self.get(name) & 1 != 0
+    }
+
+    /// Drives an output pin, masking `value` to the pin's width.
+    pub fn set(&mut self, name: &str, value: u64) {
+        let Some(i) = self.index(name) else {
+            return;
+        };
+        let bits = self.defs[i].width.bits(self.word_size);
+        self.drives[i] = Some(value & mask(bits));
+    }
+
+    pub fn word_size(&self) -> u32 {
+        crate::catalog::Width::Word.bits(self.word_size)
+    }
+
+    /// The value set on an input component through the simulator.
+    pub fn external(&self) -> u64 {
+        self.external
+    }
+}
+
+fn pins_of<'a>(
+    netlist: &'a Netlist,
+    nets: &[u64],
+    component: usize,
+    word_size: i64,
+    external: u64,
+) -> Pins<'a> {
+    let defs = &netlist.pins[component][..];
+    let values = defs
+        .iter()
+        .enumerate()
+        .map(|(pin, def)| {
+            let value = netlist
+                .net_of(PinRef { component, pin })
+                .map_or(0, |net| nets[net]);
+            value & mask(def.width.bits(word_size))
+        })
+        .collect();
+    Pins {
+        defs,
+        values,
+        drives: vec![None; defs.len()],
+        word_size,
+        external,
+    }
+}
+
+pub(crate) fn mask(bits: u32) -> u64 {
+    if bits >= 64 {
+        u64::MAX
+    } else {
+        (1 << bits) - 1
+    }
+}
+
+/// An input or output component the simulator exposes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Port {
+    pub component: usize,
+    pub kind: Kind,
+    pub label: String,
+    pub permanent_id: u64,
+}
+
+pub struct Simulator {
+    netlist: Netlist,
+    word_sizes: Vec<i64>,
+    models: Vec<Option<Box<dyn Model>>>,
+    nets: Vec<u64>,
+    external: Vec<u64>,
+    inputs: Vec<Port>,
+    outputs: Vec<Port>,
+    unsupported: Vec<usize>,
+    devices: devices::Devices,
+    permanent_ids: Vec<u64>,
+    tick: u64,
+    settled: bool,
+}
+
+impl Simulator {
+    pub fn new(circuit: &v9::CircuitData) -> Self {
+        Self::with_netlist(circuit, NetlistBuilder::new(circuit).build())
+    }
+
+    /// Simulates `circuit` using a netlist built from it, for instance one
+    /// with custom component pins resolved.
+    pub fn with_netlist(circuit: &v9::CircuitData, netlist: Netlist) -> Self {
+        let mut sim = Self {
+            nets: vec![0; netlist.nets.len()],
+            netlist,
+            word_sizes: Vec::new(),
+            models: Vec::new(),
+            external: vec![0; circuit.components.len()],
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            unsupported: Vec::new(),
+            devices: devices::Devices::new(circuit),
+            permanent_ids: circuit.components.iter().map(|c| c.permanent_id).collect(),
+            tick: 0,
+            settled: true,
+        };
+        for (i, component) in circuit.components.iter().enumerate() {
+            let too_wide = sim.netlist.pins[i]
+                .iter()
+                .any(|p| p.width.bits(component.word_size) > 64);
+            let model = if too_wide {
+                None
+            } else {
+                logic::model_for(component).or_else(|| sim.devices.model_for(i, component.kind))
+            };
+            if model.is_none() && (too_wide || !component.kind.pins().is_empty()) {
+                sim.unsupported.push(i);
+            }
+            sim.models.push(model);
+            sim.word_sizes.push(component.word_size);
+            let port = Port {
+                component: i,
+                kind: component.kind,
+                label: component.custom_string.to_string_lossy().into_owned(),
+                permanent_id: component.permanent_id,
+            };
+            if component.kind.is_input() {
+                sim.inputs.push(port);
+            } else if component.kind.is_output() {
+                sim.outputs.push(port);
+            }
+        }
+        sim
+    }
+
+    pub fn netlist(&self) -> &Netlist {
+        &self.netlist
+    }
+
+    pub fn inputs(&self) -> &[Port] {
+        &self.inputs
+    }
+
+    pub fn outputs(&self) -> &[Port] {
+        &self.outputs
+    }
+
+    /// Components with pins that have no model, and so drive nothing.
+    pub fn unsupported(&self) -> &[usize] {
+        &self.unsupported
+    }
+
+    /// Index of the component with `permanent_id`.
+    pub fn component_index(&self, permanent_id: u64) -> Option<usize> {
+        self.permanent_ids.iter().position(|&id| id == permanent_id)
+    }
+
+    /// Storage of a memory component, shared with the memories and displays
+    /// linked to it.
+    pub fn memory(&self, component: usize) -> Option<&Memory> {
+        self.devices.memories.get(&component)
+    }
+
+    fn memory_or_err(&self, component: usize) -> Result<&Memory, Error> {
+        self.memory(component).ok_or(Error::NoSuchDevice(component))
+    }
+
+    /// Loads little-endian `bytes` into a memory, from address 0.
+    pub fn load_memory(&mut self, component: usize, bytes: &[u8]) -> Result<(), Error> {
+        self.memory_or_err(component)?.load(bytes);
+        Ok(())
+    }
+
+    pub fn load_memory_file(
+        &mut self,
+        component: usize,
+        path: impl AsRef<Path>,
+    ) -> Result<(), Error> {
+        let bytes = std::fs::read(path)?;
+        self.load_memory(component, &bytes)
+    }
+
+    /// The text a console shows: its linked memory read as bytes, with NULs
+    /// dropped.
+    pub fn console_text(&self, component: usize) -> Option<String> {
+        let bytes = self.devices.displays.get(&component)?.bytes();
+        let text: Vec<u8> = bytes.into_iter().filter(|&b| b != 0).collect();
+        Some(String::from_utf8_lossy(&text).into_owned())
+    }
+
+    /// The words a pixel screen shows, from its linked memory.
+    pub fn screen(&self, component: usize) -> Option<Vec<u64>> {
+        Some(self.devices.displays.get(&component)?.words())
+    }
+
+    /// Queues key presses for a keyboard to read, one per enabled tick.
+    pub fn press_keys(&mut self, component: usize, keys: &[u8]) -> Result<(), Error> {
+        let queue = self
+            .devices
+            .keyboards
+            .get(&component)
+            .ok_or(Error::NoSuchDevice(component))?;
+        queue.borrow_mut().extend(keys);
+        Ok(())
+    }
+
+    /// Whether a `Halt` component has been enabled.
+    pub fn halted(&self) -> bool {
+        self.devices.halted.get()
+    }
+
+    /// Runs until halted or `max` ticks have run, returning the number run.
+    pub fn run_until_halt(&mut self, max: u64) -> u64 {
+        let mut ran = 0;
+        while ran < max && !self.halted() {
+            self.tick();
+            ran += 1;
+        }
+        ran
+    }
+
+    /// Ticks run so far.
+    pub fn ticks(&self) -> u64 {
+        self.tick
+    }
+
+    /// Whether the last tick's logic reached a stable state, rather than
+    /// oscillating until the iteration limit.
+    pub fn settled(&self) -> bool {
+        self.settled
+    }
+
+    /// The one port labelled `label`.
+    fn find(ports: &[Port], label: &str) -> Result<usize, Error> {
+        let mut matching = ports.iter().filter(|p| p.label == label);
+        match (matching.next(), matching.next()) {
+            (Some(port), None) => Ok(port.component),
+            (Some(_), Some(_)) => Err(Error::DuplicatePort(label.to_string())),
+            (None, _) => Err(Error::NoSuchPort(label.to_string())),
+        }
+    }
+
+    /// Sets the value an input component drives from the next tick on.
+    /// Multi-pin level inputs take one bit per pin, lowest first.
+    pub fn set_input(&mut self, label: &str, value: u64) -> Result<(), Error> {
+        let component = Self::find(&self.inputs, label)?;
+        self.set_input_at(component, value);
+        Ok(())
+    }
+
+    pub fn set_input_at(&mut self, component: usize, value: u64) {
+        self.external[component] = value;
+    }
+
+    /// The value an output component received in the last tick, from its
+    /// [port pins](Kind::port_pins). A switched output reads `None` while
+    /// disabled.
+    pub fn output(&self, label: &str) -> Result<Option<u64>, Error> {
+        Ok(self.output_at(Self::find(&self.outputs, label)?))
+    }
+
+    pub fn output_at(&self, component: usize) -> Option<u64> {
+        let pins = self.pins(component);
+        let kind = self.outputs.iter().find(|p| p.component == component)?.kind;
+        if kind.pin("enable").is_some() && !pins.get_bit("enable") {
+            return None;
+        }
+        let mut value = 0;
+        let mut low = 0;
+        for pin in kind.port_pins() {
+            value |= pins.get(pin.name).checked_shl(low).unwrap_or(0);
+            low += pins.width(pin.name);
+        }
+        Some(value)
+    }
+
+    /// The value on the net of a pin, or 0 if it isn't connected.
+    pub fn pin_value(&self, component: usize, name: &str) -> Option<u64> {
+        let pin = self.netlist.pin_ref(component, name)?;
+        Some(self.netlist.net_of(pin).map_or(0, |net| self.nets[net]))
+    }
+
+    /// The value a net settled to in the last tick.
+    pub fn net_value(&self, net: usize) -> u64 {
+        self.nets[net]
+    }
+
+    fn pins(&self, component: usize) -> Pins<'_> {
+        pins_of(
+            &self.netlist,
+            &self.nets,
+            component,
+            self.word_sizes[component],
+            self.external[component],
+        )
+    }
+
+    fn settle(&mut self) -> bool {
+        let limit = self.models.len() + 2;
+        for _ in 0..limit {
+            let mut next = vec![0; self.nets.len()];
+            for (component, model) in self.models.iter().enumerate() {
+                let Some(model) = model else {
+                    continue;
+                };
+                let mut pins = self.pins(component);
+                model.eval(&mut pins);
+                for (pin, drive) in pins.drives.iter().enumerate() {
+                    let pin = PinRef { component, pin };
+                    if let (Some(value), Some(net)) = (drive, self.netlist.net_of(pin)) {
+                        next[net] |= value;
+                    }
+                }
+            }
+            if next == self.nets {
+                return true;
+            }
+            self.nets = next;
+        }
+        false
+    }
+
+    /// Runs one tick: settles the logic, then latches sequential state.
+    pub fn tick(&mut self) {
+        self.settled = self.settle();
+        for component in 0..self.models.len() {
+            if self.models[component].is_none() {
+                continue;
+            }
+            let pins = pins_of(
+                &self.netlist,
+                &self.nets,
+                component,
+                self.word_sizes[component],
+                self.external[component],
+            );
+            if let Some(model) = &mut self.models[component] {
+                model.latch(&pins);
+            }
+        }
+        self.tick += 1;
+    }
+
+    /// Runs `count` ticks.
+    pub fn run(&mut self, count: u64) {
+        for _ in 0..count {
+            self.tick();
+        }
+    }
+
+    /// Sets several inputs, runs a tick, and reads every labelled output by
+    /// label. Fails without ticking if two outputs share a label.
+    pub fn step(&mut self, inputs: &[(&str, u64)]) -> Result<HashMap<String, Option<u64>>, Error> {
+        let mut labels = HashSet::new();
+        let outputs: Vec<_> = self
+            .outputs
+            .iter()
+            .filter(|p| !p.label.is_empty())
+            .collect();
+        if let Some(port) = outputs.iter().find(|p| !labels.insert(&p.label)) {
+            return Err(Error::DuplicatePort(port.label.clone()));
+        }
+        let outputs: Vec<_> = outputs
+            .into_iter()
+            .map(|p| (p.label.clone(), p.component))
+            .collect();
+        for &(label, value) in inputs {
+            self.set_input(label, value)?;
+        }
+        self.tick();
+        Ok(outputs
+            .into_iter()
+            .map(|(label, component)| (label, self.output_at(component)))
+            .collect())
+    }
+}
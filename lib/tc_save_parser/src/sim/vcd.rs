@@ -0,0 +1,252 @@
+//! Recording simulation runs as VCD waveforms, for viewers like GTKWave.
+//!
+//! Nets get their names from the circuit: the label (`custom_string`) of a
+//! component, qualified by pin name unless the component has a single pin;
+//! the comment of any wire in the net; and wire probes, named by their label
+//! or `probe<index>` without one. A net can have several names, which the
+//! VCD output lists as aliases of one signal.
+
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+};
+
+use super::Simulator;
+use crate::{
+    Error, Kind,
+    netlist::{Netlist, PinRef},
+    v9,
+};
+
+/// A named net that can be traced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signal {
+    pub name: String,
+    pub net: usize,
+    /// The widest pin on the net, in bits.
+    pub width: u32,
+}
+
+/// VCD identifiers can't contain whitespace.
+fn identifier(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join("_")
+}
+
+/// Every nameable net of `circuit`, in component order followed by wire
+/// comments. Repeated names on different nets get a `_<n>` suffix.
+pub fn signals(circuit: &v9::CircuitData, netlist: &Netlist) -> Vec<Signal> {
+    let mut widths = vec![0; netlist.nets.len()];
+    for (n, net) in netlist.nets.iter().enumerate() {
+        for &pin in &net.pins {
+            let word_size = circuit.components[pin.component].word_size;
+            widths[n] = widths[n].max(netlist.pin(pin).width.bits(word_size));
+        }
+    }
+
+    let mut named = Vec::new();
+    for (i, component) in circuit.components.iter().enumerate() {
+        let label = identifier(&component.custom_string.to_string_lossy());
+        let label = match component.kind {
+            Kind::ProbeWireBit | Kind::ProbeWireWord if label.is_empty() => {
+                format!("probe{}", i)
+            }
+            _ if label.is_empty() => continue,
+            _ => label,
+        };
+        let pins = &netlist.pins[i];
+        for (pin, placed) in pins.iter().enumerate() {
+            let pin = PinRef { component: i, pin };
+            let Some(net) = netlist.net_of(pin) else {
+                continue;
+            };
+            let name = if pins.len() == 1 {
+                label.clone()
+            } else {
+                format!("{}.{}", label, placed.name)
+            };
+            named.push((name, net));
+        }
+    }
+    for (n, net) in netlist.nets.iter().enumerate() {
+        for &wire in &net.wires {
+            let comment = identifier(&circuit.wires[wire].comment.to_string_lossy());
+            if !comment.is_empty() {
+                named.push((comment, n));
+            }
+        }
+    }
+
+    let mut seen: HashMap<String, Vec<usize>> = HashMap::new();
+    let mut signals = Vec::new();
+    for (name, net) in named {
+        let nets = seen.entry(name.clone()).or_default();
+        if nets.contains(&net) {
+            continue;
+        }
+        nets.push(net);
+        let name = match nets.len() {
+            1 => name,
+            n => format!("{}_{}", name, n - 1),
+        };
+        signals.push(Signal {
+            name,
+            net,
+            width: widths[net].max(1),
+        });
+    }
+    signals
+}
+
+/// Samples of chosen signals over a run, one per tick.
+#[derive(Debug, Clone, Default)]
+pub struct Recorder {
+    signals: Vec<Signal>,
+    /// Per tick, the value of every signal.
+    samples: Vec<Vec<u64>>,
+}
+
+impl Recorder {
+    pub fn new(signals: Vec<Signal>) -> Self {
+        Self {
+            signals,
+            samples: Vec::new(),
+        }
+    }
+
+    pub fn signals(&self) -> &[Signal] {
+        &self.signals
+    }
+
+    /// Recorded values of the signal at `index`, one per sampled tick.
+    pub fn values(&self, index: usize) -> impl Iterator<Item = u64> + '_ {
+        self.samples.iter().map(move |s| s[index])
+    }
+
+    /// Records the values the nets settled to in the last tick.
+    pub fn sample(&mut self, sim: &Simulator) {
+        let values = self
+            .signals
+            .iter()
+            .map(|s| sim.net_value(s.net) & super::mask(s.width))
+            .collect();
+        self.samples.push(values);
+    }
+
+    /// Runs `ticks` ticks, sampling after each.
+    pub fn run(&mut self, sim: &mut Simulator, ticks: u64) {
+        for _ in 0..ticks {
+            sim.tick();
+            self.sample(sim);
+        }
+    }
+
+    /// Writes the samples as VCD, one time unit per tick. Signals on the
+    /// same net share an identifier code.
+    pub fn write_vcd<W: Write>(&self, mut out: W) -> io::Result<()> {
+        let mut codes: HashMap<usize, String> = HashMap::new();
+        let mut vars = Vec::new();
+        for signal in &self.signals {
+            let next = codes.len();
+            let code = codes.entry(signal.net).or_insert_with(|| id_code(next));
+            vars.push(code.clone());
+        }
+
+        writeln!(out, "$version tc_save_parser $end")?;
+        writeln!(out, "$timescale 1ns $end")?;
+        writeln!(out, "$scope module circuit $end")?;
+        for (signal, code) in self.signals.iter().zip(&vars) {
+            let kind = if signal.width == 1 { "wire" } else { "reg" };
+            writeln!(
+                out,
+                "$var {} {} {} {} $end",
+                kind, signal.width, code, signal.name
+            )?;
+        }
+        writeln!(out, "$upscope $end")?;
+        writeln!(out, "$enddefinitions $end")?;
+
+        let mut last: HashMap<&str, u64> = HashMap::new();
+        for (tick, values) in self.samples.iter().enumerate() {
+            let mut changes = Vec::new();
+            for ((signal, code), &value) in self.signals.iter().zip(&vars).zip(values) {
+                if last.get(code.as_str()) != Some(&value) {
+                    last.insert(code.as_str(), value);
+                    changes.push(format_value(value, signal.width, code));
+                }
+            }
+            if tick == 0 {
+                writeln!(out, "#0")?;
+                writeln!(out, "$dumpvars")?;
+                for change in changes {
+                    writeln!(out, "{}", change)?;
+                }
+                writeln!(out, "$end")?;
+            } else if !changes.is_empty() {
+                writeln!(out, "#{}", tick)?;
+                for change in changes {
+                    writeln!(out, "{}", change)?;
+                }
+            }
+        }
+        writeln!(out, "#{}", self.samples.len())
+    }
+
+    pub fn to_vcd(&self) -> String {
+        let mut out = Vec::new();
+        self.write_vcd(&mut out)
+            .expect("writing to a Vec can't fail");
+        String::from_utf8(out).expect("VCD output is UTF-8")
+    }
+}
+
+/// Short identifier codes from the printable ASCII range.
+fn id_code(mut n: usize) -> String {
+    let mut code = String::new();
+    loop {
+        code.push(char::from(b'!' + (n % 94) as u8));
+        n /= 94;
+        if n == 0 {
+            return code;
+        }
+        n -= 1;
+    }
+}
+
+fn format_value(value: u64, width: u32, code: &str) -> String {
+    if width == 1 {
+        format!("{}{}", value & 1, code)
+    } else {
+        format!("b{:b} {}", value, code)
+    }
+}
+
+/// Runs `circuit` for `ticks` ticks with `inputs` held, tracing the signals
+/// named in `trace`, or every named signal if it is empty.
+pub fn record(
+    circuit: &v9::CircuitData,
+    inputs: &[(&str, u64)],
+    ticks: u64,
+    trace: &[&str],
+) -> Result<Recorder, Error> {
+    let mut sim = Simulator::new(circuit);
+    let all = signals(circuit, sim.netlist());
+    let chosen = if trace.is_empty() {
+        all
+    } else {
+        trace
+            .iter()
+            .map(|&name| {
+                all.iter()
+                    .find(|s| s.name == name)
+                    .cloned()
+                    .ok_or_else(|| Error::NoSuchSignal(name.to_string()))
+            })
+            .collect::<Result<_, _>>()?
+    };
+    for &(label, value) in inputs {
+        sim.set_input(label, value)?;
+    }
+    let mut recorder = Recorder::new(chosen);
+    recorder.run(&mut sim, ticks);
+    Ok(recorder)
+}
@@ -0,0 +1,261 @@
+//! Runs a circuit against a table of input vectors and expected outputs.
+//!
+//! Columns are keyed by the `custom_string` labels of the circuit's input
+//! and output components. Each vector sets its inputs, runs one tick and
+//! compares its outputs, so state carries over from one vector to the next.
+//! A missing or empty value is a don't-care. Values are decimal, or `0x`/`0b`
+//! prefixed hex and binary.
+//!
+//! As CSV, the header row holds the labels:
+//!
+//! ```text
+//! a,b,sum
+//! 1,2,3
+//! 0xff,1,0
+//! ```
+//!
+//! As TOML, each vector is a `[[vector]]` table:
+//!
+//! ```toml
+//! [[vector]]
+//! a = 1
+//! b = 2
+//! sum = 3
+//! ```
+
+use std::{collections::BTreeMap, fmt, path::Path};
+
+use crate::{CircuitDataFile, Error, sim::Simulator, v9};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Vector {
+    /// Line the vector starts on in its source, for reports.
+    pub line: usize,
+    pub values: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Testbench {
+    pub vectors: Vec<Vector>,
+}
+
+fn invalid(line: usize, message: impl Into<String>) -> Error {
+    Error::InvalidVectors {
+        line,
+        message: message.into(),
+    }
+}
+
+fn parse_value(text: &str, line: usize) -> Result<u64, Error> {
+    let text = text.trim().replace('_', "");
+    let parsed = if let Some(hex) = text.strip_prefix("0x") {
+        u64::from_str_radix(hex, 16)
+    } else if let Some(bin) = text.strip_prefix("0b") {
+        u64::from_str_radix(bin, 2)
+    } else {
+        text.parse()
+    };
+    parsed.map_err(|_| invalid(line, format!("invalid value {:?}", text)))
+}
+
+impl Testbench {
+    pub fn from_csv(text: &str) -> Result<Self, Error> {
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .comment(Some(b'#'))
+            .from_reader(text.as_bytes());
+        let labels = reader
+            .headers()
+            .map_err(|e| invalid(1, e.to_string()))?
+            .clone();
+        let mut vectors = Vec::new();
+        for record in reader.records() {
+            let record = record.map_err(|e| {
+                let line = e.position().map_or(0, |p| p.line() as usize);
+                invalid(line, e.to_string())
+            })?;
+            let line = record.position().map_or(0, |p| p.line() as usize);
+            let mut values = BTreeMap::new();
+            for (label, cell) in labels.iter().zip(record.iter()) {
+                if !cell.is_empty() {
+                    values.insert(label.to_string(), parse_value(cell, line)?);
+                }
+            }
+            vectors.push(Vector { line, values });
+        }
+        Ok(Self { vectors })
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self, Error> {
+        let table: toml::Table = text.parse().map_err(|e: toml::de::Error| {
+            let line = e
+                .span()
+                .map_or(0, |s| text[..s.start].lines().count().max(1));
+            invalid(line, e.message())
+        })?;
+        let Some(entries) = table.get("vector") else {
+            return Ok(Self::default());
+        };
+        let entries = entries
+            .as_array()
+            .ok_or_else(|| invalid(0, "`vector` must be an array of tables"))?;
+        let mut vectors = Vec::new();
+        for (i, entry) in entries.iter().enumerate() {
+            // TOML tables don't keep their position, so number them instead.
+            let line = i + 1;
+            let entry = entry
+                .as_table()
+                .ok_or_else(|| invalid(line, "each vector must be a table"))?;
+            let mut values = BTreeMap::new();
+            for (label, value) in entry {
+                let value = match value {
+                    // Negative values are taken as two's complement.
+                    toml::Value::Integer(n) => *n as u64,
+                    toml::Value::Boolean(b) => u64::from(*b),
+                    toml::Value::String(s) if s.trim().is_empty() => continue,
+                    toml::Value::String(s) => parse_value(s, line)?,
+                    other => return Err(invalid(line, format!("invalid value {}", other))),
+                };
+                values.insert(label.clone(), value);
+            }
+            vectors.push(Vector { line, values });
+        }
+        Ok(Self { vectors })
+    }
+
+    /// Reads vectors from a `.toml` file, or CSV otherwise.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Self::from_toml(&text),
+            _ => Self::from_csv(&text),
+        }
+    }
+
+    /// Simulates `circuit` through every vector.
+    pub fn run(&self, circuit: &v9::CircuitData) -> Result<Report, Error> {
+        let mut sim = Simulator::new(circuit);
+        let is_input = |label: &str| sim.inputs().iter().any(|p| p.label == label);
+        let is_output = |label: &str| sim.outputs().iter().any(|p| p.label == label);
+        for vector in &self.vectors {
+            if let Some(label) = vector.values.keys().find(|l| !is_input(l) && !is_output(l)) {
+                return Err(Error::NoSuchPort(label.clone()));
+            }
+        }
+
+        let mut results = Vec::new();
+        for (index, vector) in self.vectors.iter().enumerate() {
+            let (inputs, expected): (Vec<_>, Vec<_>) = vector
+                .values
+                .iter()
+                .partition(|(label, _)| sim.inputs().iter().any(|p| &p.label == *label));
+            for (label, &value) in inputs {
+                sim.set_input(label, value)?;
+            }
+            sim.tick();
+            let mut mismatches = Vec::new();
+            for (label, &expected) in expected {
+                let actual = sim.output(label)?;
+                if actual != Some(expected) {
+                    mismatches.push(Mismatch {
+                        label: label.clone(),
+                        expected,
+                        actual,
+                    });
+                }
+            }
+            results.push(VectorResult {
+                index,
+                line: vector.line,
+                mismatches,
+            });
+        }
+        Ok(Report { results })
+    }
+
+    /// Loads a `circuit.data` and runs it through every vector.
+    pub fn run_file(&self, path: impl AsRef<Path>) -> Result<Report, Error> {
+        let (circuit, _) = CircuitDataFile::load(path)?.into_latest()?;
+        self.run(&circuit)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    pub label: String,
+    pub expected: u64,
+    /// `None` when the output wasn't driven, such as a disabled switched
+    /// output.
+    pub actual: Option<u64>,
+}
+
+impl Mismatch {
+    /// Positions of the bits that differ, lowest first.
+    pub fn failing_bits(&self) -> Vec<u32> {
+        let diff = self.expected ^ self.actual.unwrap_or(0);
+        (0..64).filter(|bit| diff >> bit & 1 != 0).collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VectorResult {
+    pub index: usize,
+    pub line: usize,
+    pub mismatches: Vec<Mismatch>,
+}
+
+impl VectorResult {
+    pub fn passed(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub results: Vec<VectorResult>,
+}
+
+impl Report {
+    pub fn passed(&self) -> bool {
+        self.results.iter().all(VectorResult::passed)
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &VectorResult> {
+        self.results.iter().filter(|r| !r.passed())
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for result in &self.results {
+            let status = if result.passed() { "PASS" } else { "FAIL" };
+            writeln!(
+                f,
+                "vector {} (line {}): {}",
+                result.index, result.line, status
+            )?;
+            for m in &result.mismatches {
+                let actual = m
+                    .actual
+                    .map_or("undriven".to_string(), |a| format!("{:#x}", a));
+                let bits: Vec<_> = m.failing_bits().iter().map(u32::to_string).collect();
+                writeln!(
+                    f,
+                    "  {}: expected {:#x}, got {}, failing bits [{}]",
+                    m.label,
+                    m.expected,
+                    actual,
+                    bits.join(", ")
+                )?;
+            }
+        }
+        let failed = self.failures().count();
+        write!(
+            f,
+            "{} of {} vectors passed",
+            self.results.len() - failed,
+            self.results.len()
+        )
+    }
+}
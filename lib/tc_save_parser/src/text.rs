@@ -0,0 +1,140 @@
+//! Saves as pretty-printed JSON, for reading, diffing and editing by hand.
+//!
+//! [`data_to_text`] and [`text_to_data`] convert whole `circuit.data` files,
+//! and text that wasn't edited converts back to the identical file. Besides
+//! every field the binary format stores, the text keeps anything after the
+//! circuit data in the payload as `trailing`, and, when this crate would
+//! compress the payload differently from the game, the payload as stored as
+//! `compressed`, in hex. The stored payload is written back for as long as it
+//! holds what the text describes; once the circuit is edited, the payload is
+//! compressed again.
+//!
+//! [`CircuitDataFile::to_text`] and [`CircuitDataFile::from_text`] convert
+//! the parsed file alone, which gives the same circuit but not necessarily
+//! the same bytes. Strings are text when they are UTF-8 and lists of bytes
+//! otherwise, so they come back unchanged either way.
+//!
+//! Lengths are left out, as they follow from the lists, and wires keep the
+//! terminating segment. Saves of versions this crate can't read keep their
+//! decompressed payload as a list of bytes.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{CircuitDataFile, CircuitDataVersion, Error};
+
+/// A whole `circuit.data` file, as [`data_to_text`] writes it.
+#[derive(Serialize, Deserialize)]
+struct Text {
+    #[serde(flatten)]
+    file: CircuitDataFile,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    trailing: Vec<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    compressed: Option<String>,
+}
+
+fn invalid(e: impl ToString) -> Error {
+    Error::InvalidText(e.to_string())
+}
+
+impl CircuitDataFile {
+    /// Writes the file as pretty-printed JSON.
+    pub fn to_text(&self) -> Result<String, Error> {
+        serde_json::to_string_pretty(self).map_err(invalid)
+    }
+
+    /// Reads a file written by [`Self::to_text`].
+    pub fn from_text(text: &str) -> Result<Self, Error> {
+        serde_json::from_str::<Self>(text)
+            .map_err(invalid)?
+            .checked()
+    }
+
+    fn checked(self) -> Result<Self, Error> {
+        let expected = match self.circuit {
+            CircuitDataVersion::V7(_) => Some(7),
+            CircuitDataVersion::V8(_) => Some(8),
+            CircuitDataVersion::V9(_) => Some(9),
+            CircuitDataVersion::Unknown(_) => None,
+        };
+        let consistent = match expected {
+            Some(version) => self.version == version,
+            None => !(7..=9).contains(&self.version),
+        };
+        if !consistent {
+            return Err(Error::InvalidText(format!(
+                "version {} doesn't match the circuit data",
+                self.version
+            )));
+        }
+        Ok(self)
+    }
+}
+
+/// Converts the contents of a `circuit.data` file to text.
+pub fn data_to_text(data: &[u8]) -> Result<String, Error> {
+    let file = CircuitDataFile::from_bytes(data)?;
+    let stored = data.get(1..).unwrap_or_default();
+    let payload = CircuitDataFile::debug_dump_bytes(data)?;
+    let written = file.circuit.payload()?;
+    let trailing = payload.strip_prefix(&written[..]).unwrap_or_default();
+    let compressed = (compress(&payload)? != stored).then(|| to_hex(stored));
+    let text = Text {
+        file,
+        trailing: trailing.to_vec(),
+        compressed,
+    };
+    serde_json::to_string_pretty(&text).map_err(invalid)
+}
+
+/// Converts text from [`data_to_text`] back to the contents of a
+/// `circuit.data` file.
+pub fn text_to_data(text: &str) -> Result<Vec<u8>, Error> {
+    let text: Text = serde_json::from_str(text).map_err(invalid)?;
+    let file = text.file.checked()?;
+    let mut payload = file.circuit.payload()?;
+    payload.extend(text.trailing);
+    let stored = text.compressed.as_deref().map(from_hex).transpose()?;
+    let unchanged = |stored: &[u8]| {
+        snap::raw::Decoder::new()
+            .decompress_vec(stored)
+            .is_ok_and(|decompressed| decompressed == payload)
+    };
+    let compressed = match stored {
+        Some(stored) if unchanged(&stored) => stored,
+        _ => compress(&payload)?,
+    };
+    Ok([vec![file.version], compressed].concat())
+}
+
+fn compress(payload: &[u8]) -> Result<Vec<u8>, Error> {
+    let compressed = snap::raw::Encoder::new()
+        .compress_vec(payload)
+        .map_err(std::io::Error::from)?;
+    Ok(compressed)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(hex: &str) -> Result<Vec<u8>, Error> {
+    let digits = hex.as_bytes();
+    if !digits.len().is_multiple_of(2) {
+        return Err(invalid("compressed payload has an odd number of digits"));
+    }
+    digits
+        .chunks(2)
+        .map(|pair| {
+            std::str::from_utf8(pair)
+                .ok()
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(|| {
+                    invalid(format!(
+                        "{:?} isn't a hex byte",
+                        String::from_utf8_lossy(pair)
+                    ))
+                })
+        })
+        .collect()
+}
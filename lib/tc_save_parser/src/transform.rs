@@ -0,0 +1,226 @@
+//! Translating, rotating and mirroring points, components and wires.
+//!
+//! Coordinates follow the save format: `x` grows to the right and `y` grows
+//! downwards, so a positive [`Rotation`] turns clockwise on screen. Like the
+//! game's own `i16` coordinates, points wrap around the edges of the grid
+//! rather than overflowing.
+
+use std::ops::{Add, Neg};
+
+use crate::{Point, Wire, WireDirection, WireSegment, model, v7, v8, v9};
+
+/// A clockwise quarter-turn rotation, as stored in `Component::rotation`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rotation {
+    #[default]
+    R0,
+    R90,
+    R180,
+    R270,
+}
+
+impl Rotation {
+    /// Quarter turns, `0..4`.
+    pub fn quarter_turns(self) -> u8 {
+        self as u8
+    }
+
+    /// Rotates `point` around the origin.
+    pub fn apply(self, point: Point) -> Point {
+        let Point { x, y } = point;
+        match self {
+            Rotation::R0 => Point::new(x, y),
+            Rotation::R90 => Point::new(y.wrapping_neg(), x),
+            Rotation::R180 => Point::new(x.wrapping_neg(), y.wrapping_neg()),
+            Rotation::R270 => Point::new(y, x.wrapping_neg()),
+        }
+    }
+}
+
+/// Stored rotations wrap around, so `5` is a quarter turn.
+impl From<u8> for Rotation {
+    fn from(value: u8) -> Self {
+        match value % 4 {
+            0 => Rotation::R0,
+            1 => Rotation::R90,
+            2 => Rotation::R180,
+            _ => Rotation::R270,
+        }
+    }
+}
+
+impl From<Rotation> for u8 {
+    fn from(value: Rotation) -> u8 {
+        value.quarter_turns()
+    }
+}
+
+impl Add for Rotation {
+    type Output = Rotation;
+
+    fn add(self, rhs: Rotation) -> Rotation {
+        Rotation::from(self.quarter_turns() + rhs.quarter_turns())
+    }
+}
+
+impl Neg for Rotation {
+    type Output = Rotation;
+
+    fn neg(self) -> Rotation {
+        Rotation::from(4 - self.quarter_turns())
+    }
+}
+
+/// The axis a mirror flips across.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Mirror {
+    /// Flips left and right, negating `x`.
+    Horizontal,
+    /// Flips up and down, negating `y`.
+    Vertical,
+}
+
+impl Mirror {
+    /// Mirrors `point` across the axis through the origin.
+    pub fn apply(self, point: Point) -> Point {
+        match self {
+            Mirror::Horizontal => Point::new(point.x.wrapping_neg(), point.y),
+            Mirror::Vertical => Point::new(point.x, point.y.wrapping_neg()),
+        }
+    }
+}
+
+/// Geometric transforms. Rotations and mirrors pivot around `center`, so a
+/// selection rotates in place when given its own center.
+pub trait Transform {
+    fn translate(&mut self, by: Point);
+    fn rotate(&mut self, rotation: Rotation, center: Point);
+    fn mirror(&mut self, mirror: Mirror, center: Point);
+}
+
+fn around(point: Point, center: Point, f: impl FnOnce(Point) -> Point) -> Point {
+    let moved = f(Point::new(
+        point.x.wrapping_sub(center.x),
+        point.y.wrapping_sub(center.y),
+    ));
+    Point::new(
+        moved.x.wrapping_add(center.x),
+        moved.y.wrapping_add(center.y),
+    )
+}
+
+impl Transform for Point {
+    fn translate(&mut self, by: Point) {
+        self.x = self.x.wrapping_add(by.x);
+        self.y = self.y.wrapping_add(by.y);
+    }
+
+    fn rotate(&mut self, rotation: Rotation, center: Point) {
+        *self = around(*self, center, |p| rotation.apply(p));
+    }
+
+    fn mirror(&mut self, mirror: Mirror, center: Point) {
+        *self = around(*self, center, |p| mirror.apply(p));
+    }
+}
+
+impl WireDirection {
+    pub fn rotated(self, rotation: Rotation) -> WireDirection {
+        WireDirection::ALL[(self as usize + 2 * rotation.quarter_turns() as usize) % 8]
+    }
+
+    pub fn mirrored(self, mirror: Mirror) -> WireDirection {
+        let flipped = match mirror {
+            Mirror::Horizontal => 12 - self as usize,
+            Mirror::Vertical => 8 - self as usize,
+        };
+        WireDirection::ALL[flipped % 8]
+    }
+}
+
+impl WireSegment {
+    // Zero-length segments, including the list terminator, are left alone
+    // since they don't move anywhere.
+    fn map_direction(&mut self, f: impl FnOnce(WireDirection) -> WireDirection) {
+        if self.length() != 0 {
+            self.set_direction(f(self.direction()));
+        }
+    }
+}
+
+impl Transform for Wire {
+    fn translate(&mut self, by: Point) {
+        self.start.translate(by);
+    }
+
+    fn rotate(&mut self, rotation: Rotation, center: Point) {
+        self.start.rotate(rotation, center);
+        for segment in &mut self.segments {
+            segment.map_direction(|d| d.rotated(rotation));
+        }
+    }
+
+    fn mirror(&mut self, mirror: Mirror, center: Point) {
+        self.start.mirror(mirror, center);
+        for segment in &mut self.segments {
+            segment.map_direction(|d| d.mirrored(mirror));
+        }
+    }
+}
+
+impl<T: Transform> Transform for [T] {
+    fn translate(&mut self, by: Point) {
+        self.iter_mut().for_each(|t| t.translate(by));
+    }
+
+    fn rotate(&mut self, rotation: Rotation, center: Point) {
+        self.iter_mut().for_each(|t| t.rotate(rotation, center));
+    }
+
+    fn mirror(&mut self, mirror: Mirror, center: Point) {
+        self.iter_mut().for_each(|t| t.mirror(mirror, center));
+    }
+}
+
+// Components can't be mirrored in game, so mirroring one moves it and turns
+// it to face the mirrored direction, without flipping the order of its pins.
+macro_rules! impl_component_transform {
+    ($($component:ty),*) => {$(
+        impl $component {
+            pub fn rotation(&self) -> Rotation {
+                Rotation::from(self.rotation)
+            }
+
+            pub fn set_rotation(&mut self, rotation: Rotation) {
+                self.rotation = rotation.into();
+            }
+        }
+
+        impl Transform for $component {
+            fn translate(&mut self, by: Point) {
+                self.position.translate(by);
+            }
+
+            fn rotate(&mut self, rotation: Rotation, center: Point) {
+                self.position.rotate(rotation, center);
+                self.set_rotation(self.rotation() + rotation);
+            }
+
+            fn mirror(&mut self, mirror: Mirror, center: Point) {
+                self.position.mirror(mirror, center);
+                let turns = self.rotation().quarter_turns();
+                self.set_rotation(match mirror {
+                    Mirror::Horizontal => Rotation::from(6 - turns),
+                    Mirror::Vertical => Rotation::from(4 - turns),
+                });
+            }
+        }
+    )*};
+}
+
+impl_component_transform!(
+    v7::Component,
+    v8::Component,
+    v9::Component,
+    model::Component
+);
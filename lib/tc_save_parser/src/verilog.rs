@@ -0,0 +1,560 @@
+//! Exporting circuits as structural Verilog.
+//!
+//! Each component becomes a few continuous assignments, or an always block
+//! for the sequential ones, clocked by the `clk` input every module has: one
+//! clock edge per tick. Every output pin drives a wire of its own and each
+//! net is the OR of its drivers, matching how the game combines outputs on
+//! a net. Unconnected inputs read 0.
+//!
+//! Ports come from [input](Kind::is_input) and [output](Kind::is_output)
+//! components, named by their label. `Custom` components become instances
+//! of a module named by [`custom_module_name`], with ports named after the
+//! pins [`VerilogExporter::custom_pins`] resolves for them, plus `clk`.
+//! Components the exporter can't express, such as memories and devices, are
+//! listed in a comment and drive nothing.
+//!
+//! The exporter names its own signals `clk`, `n<net>` and `c<component>`,
+//! optionally followed by `_` and more. A label that looks like one of them
+//! gets a leading `_`, and one naming the same identifier as an earlier port
+//! gets a `_<n>` suffix.
+
+use std::{collections::HashSet, fmt::Write};
+
+use crate::{
+    ComponentSettings, Kind,
+    catalog::PinDirection,
+    netlist::{NetPin, Netlist, NetlistBuilder, PinDef, PinRef},
+    v9,
+};
+
+/// Name of the module a custom component with `id` instantiates.
+pub fn custom_module_name(id: u64) -> String {
+    format!("custom_{}", id)
+}
+
+/// Whether the exporter could declare `name` itself.
+fn is_reserved(name: &str) -> bool {
+    let numbered = |rest: &str| {
+        let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+        digits > 0 && rest[digits..].is_empty()
+    };
+    name == "clk"
+        || name.strip_prefix('n').is_some_and(numbered)
+        || name
+            .strip_prefix('c')
+            .is_some_and(|rest| rest.split('_').next().is_some_and(numbered))
+}
+
+/// The name an identifier from [`identifier`] declares. An escaped
+/// identifier names the same thing as the plain one with its characters.
+fn unescaped(identifier: &str) -> &str {
+    identifier
+        .strip_prefix('\\')
+        .map_or(identifier, |escaped| escaped.trim_end())
+}
+
+/// Makes a Verilog identifier from a label, escaping it if needed. Escaping
+/// keeps only printable ASCII, so a label without any becomes `_`.
+fn identifier(name: &str) -> String {
+    let plain = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if plain {
+        name.to_string()
+    } else {
+        let escaped: String = name.chars().filter(|c| c.is_ascii_graphic()).collect();
+        if escaped.is_empty() {
+            return "_".to_string();
+        }
+        format!("\\{} ", escaped)
+    }
+}
+
+fn range(bits: u32) -> String {
+    if bits > 1 {
+        format!("[{}:0] ", bits - 1)
+    } else {
+        String::new()
+    }
+}
+
+pub struct VerilogExporter<'a> {
+    circuit: &'a v9::CircuitData,
+    name: String,
+    netlist: NetlistBuilder<'a>,
+}
+
+impl<'a> VerilogExporter<'a> {
+    pub fn new(circuit: &'a v9::CircuitData, name: &str) -> Self {
+        Self {
+            circuit,
+            name: name.to_string(),
+            netlist: NetlistBuilder::new(circuit),
+        }
+    }
+
+    /// Supplies the unrotated pins of `Custom` components, which also name
+    /// the ports of their instances.
+    pub fn custom_pins(
+        mut self,
+        resolve: impl Fn(&v9::Component) -> Option<Vec<PinDef>> + 'a,
+    ) -> Self {
+        self.netlist = self.netlist.custom_pins(resolve);
+        self
+    }
+
+    pub fn export(self) -> String {
+        let netlist = self.netlist.build();
+        let mut writer = Writer {
+            circuit: self.circuit,
+            net_widths: net_widths(self.circuit, &netlist),
+            netlist: &netlist,
+            decls: Vec::new(),
+            body: Vec::new(),
+            ports: Vec::new(),
+            port_names: HashSet::new(),
+            unsupported: Vec::new(),
+        };
+        for component in 0..self.circuit.components.len() {
+            writer.component(component);
+        }
+        writer.finish(&self.name)
+    }
+}
+
+/// Exports `circuit` as a module called `name`, leaving custom components
+/// unconnected.
+pub fn to_verilog(circuit: &v9::CircuitData, name: &str) -> String {
+    VerilogExporter::new(circuit, name).export()
+}
+
+fn net_widths(circuit: &v9::CircuitData, netlist: &Netlist) -> Vec<u32> {
+    netlist
+        .nets
+        .iter()
+        .map(|net| {
+            net.pins
+                .iter()
+                .map(|&p| {
+                    let word_size = circuit.components[p.component].word_size;
+                    netlist.pin(p).width.bits(word_size)
+                })
+                .max()
+                .unwrap_or(1)
+        })
+        .collect()
+}
+
+struct Writer<'a> {
+    circuit: &'a v9::CircuitData,
+    netlist: &'a Netlist,
+    net_widths: Vec<u32>,
+    decls: Vec<String>,
+    body: Vec<String>,
+    ports: Vec<String>,
+    port_names: HashSet<String>,
+    unsupported: Vec<String>,
+}
+
+impl Writer<'_> {
+    fn pins(&self, component: usize) -> &[NetPin] {
+        &self.netlist.pins[component]
+    }
+
+    fn width(&self, component: usize, pin: &str) -> u32 {
+        let word_size = self.circuit.components[component].word_size;
+        self.pins(component)
+            .iter()
+            .find(|p| p.name == pin)
+            .map_or(1, |p| p.width.bits(word_size))
+    }
+
+    /// The value an input pin reads: its net, cut to the pin's width.
+    fn read(&self, component: usize, pin: &str) -> String {
+        let width = self.width(component, pin);
+        let net = self
+            .netlist
+            .pin_ref(component, pin)
+            .and_then(|p| self.netlist.net_of(p));
+        match net {
+            Some(net) if self.net_widths[net] == width => format!("n{}", net),
+            Some(net) if width == 1 => format!("n{}[0]", net),
+            Some(net) => format!("n{}[{}:0]", net, width - 1),
+            None => format!("{}'d0", width),
+        }
+    }
+
+    fn out(&self, component: usize, pin: &str) -> String {
+        format!("c{}_{}", component, pin)
+    }
+
+    fn assign(&mut self, component: usize, pin: &str, expr: String) {
+        let out = self.out(component, pin);
+        self.body.push(format!("assign {} = {};", out, expr));
+    }
+
+    fn port_name(&mut self, component: usize, prefix: &str) -> String {
+        let label = self.circuit.components[component]
+            .custom_string
+            .to_string_lossy();
+        let mut base = if label.chars().any(|c| c.is_ascii_graphic()) {
+            label.into_owned()
+        } else {
+            format!("{}{}", prefix, component)
+        };
+        if is_reserved(unescaped(&identifier(&base))) {
+            base.insert(0, '_');
+        }
+        let mut name = base.clone();
+        let mut n = 1;
+        // Compare what the identifiers declare, since `x y` and `xy` both
+        // escape to `xy`.
+        while !self
+            .port_names
+            .insert(unescaped(&identifier(&name)).to_string())
+        {
+            name = format!("{}_{}", base, n);
+            n += 1;
+        }
+        identifier(&name)
+    }
+
+    fn component(&mut self, i: usize) {
+        let component = &self.circuit.components[i];
+        let kind = component.kind;
+        for pin in self.pins(i).to_vec() {
+            if pin.direction == PinDirection::Output {
+                let bits = pin.width.bits(component.word_size);
+                let decl = format!("wire {}{};", range(bits), self.out(i, &pin.name));
+                self.decls.push(decl);
+            }
+        }
+        let r = |pin: &str| self.read(i, pin);
+        let w = |pin: &str| self.width(i, pin);
+
+        let binary = |op: &str| format!("{} {} {}", r("a"), op, r("b"));
+        let expr = match kind {
+            Kind::Off => Some("1'b0".to_string()),
+            Kind::On => Some("1'b1".to_string()),
+            Kind::Constant | Kind::StaticValue => {
+                let value = match component.typed_settings() {
+                    ComponentSettings::Constant { value }
+                    | ComponentSettings::StaticValue { value } => value,
+                    _ => component.settings.first().copied().unwrap_or(0),
+                };
+                Some(format!(
+                    "{}'d{}",
+                    w("out"),
+                    value & crate::sim::mask(w("out"))
+                ))
+            }
+            Kind::NotBit | Kind::NotWord => Some(format!("~{}", r("a"))),
+            Kind::AndBit | Kind::AndWord => Some(binary("&")),
+            Kind::OrBit | Kind::OrWord => Some(binary("|")),
+            Kind::XorBit | Kind::XorWord => Some(binary("^")),
+            Kind::NandBit | Kind::NandWord => Some(format!("~({})", binary("&"))),
+            Kind::NorBit | Kind::NorWord => Some(format!("~({})", binary("|"))),
+            Kind::XnorBit | Kind::XnorWord => Some(format!("~({})", binary("^"))),
+            Kind::And3Bit => Some(format!("{} & {} & {}", r("a"), r("b"), r("c"))),
+            Kind::Or3Bit => Some(format!("{} | {} | {}", r("a"), r("b"), r("c"))),
+            Kind::SwitchBit | Kind::SwitchWord => {
+                Some(format!("{} ? {} : {}'d0", r("enable"), r("a"), w("out")))
+            }
+            Kind::Equal => Some(binary("==")),
+            Kind::LessU => Some(binary("<")),
+            Kind::LessS => Some(format!("$signed({}) < $signed({})", r("a"), r("b"))),
+            Kind::Neg => Some(format!("-{}", r("a"))),
+            Kind::Inc => Some(format!("{} + 1'b1", r("a"))),
+            Kind::Lsl => Some(binary("<<")),
+            Kind::Lsr => Some(binary(">>")),
+            Kind::Asr => Some(format!("$signed({}) >>> {}", r("a"), r("b"))),
+            Kind::Rol | Kind::Ror => {
+                let bits = w("a");
+                let by = format!("({} % {})", r("b"), bits);
+                let (first, second) = if kind == Kind::Rol {
+                    ("<<", ">>")
+                } else {
+                    (">>", "<<")
+                };
+                Some(format!(
+                    "({a} {first} {by}) | ({a} {second} ({bits} - {by}))",
+                    a = r("a")
+                ))
+            }
+            Kind::Mod => Some(format!(
+                "{b} == 0 ? {w}'d0 : {a} % {b}",
+                a = r("a"),
+                b = r("b"),
+                w = w("out")
+            )),
+            Kind::Mux => Some(format!("{} ? {} : {}", r("select"), r("b"), r("a"))),
+            _ => None,
+        };
+        if let Some(expr) = expr {
+            self.assign(i, "out", expr);
+            return;
+        }
+
+        match kind {
+            Kind::FullAdder | Kind::Add => {
+                let (sum, carry) = if kind == Kind::FullAdder {
+                    ("sum", "carry_out")
+                } else {
+                    ("out", "carry_out")
+                };
+                let line = format!(
+                    "assign {{{}, {}}} = {} + {} + {};",
+                    self.out(i, carry),
+                    self.out(i, sum),
+                    self.read(i, "a"),
+                    self.read(i, "b"),
+                    self.read(i, "carry_in")
+                );
+                self.body.push(line);
+            }
+            Kind::Mul => {
+                let line = format!(
+                    "assign {{{}, {}}} = {} * {};",
+                    self.out(i, "high"),
+                    self.out(i, "out"),
+                    self.read(i, "a"),
+                    self.read(i, "b")
+                );
+                self.body.push(line);
+            }
+            Kind::Div => {
+                let (a, b, bits) = (self.read(i, "a"), self.read(i, "b"), self.width(i, "a"));
+                let quotient = format!("{b} == 0 ? {bits}'d0 : {a} / {b}");
+                let remainder = format!("{b} == 0 ? {bits}'d0 : {a} % {b}");
+                self.assign(i, "quotient", quotient);
+                self.assign(i, "remainder", remainder);
+            }
+            Kind::Decoder1 | Kind::Decoder2 | Kind::Decoder3 => {
+                let inputs = ["a", "b", "c"];
+                let count = match kind {
+                    Kind::Decoder1 => 1,
+                    Kind::Decoder2 => 2,
+                    _ => 3,
+                };
+                let select: Vec<_> = inputs[..count]
+                    .iter()
+                    .rev()
+                    .map(|pin| self.read(i, pin))
+                    .collect();
+                let select = format!("{{{}}}", select.join(", "));
+                for n in 0..1 << count {
+                    let expr = format!("{} == {}'d{}", select, count, n);
+                    self.assign(i, &format!("out{}", n), expr);
+                }
+            }
+            Kind::MakerBit2
+            | Kind::MakerBit4
+            | Kind::MakerBit8
+            | Kind::MakerWord2
+            | Kind::MakerWord4
+            | Kind::MakerWord8
+            | Kind::Concatenator2
+            | Kind::Concatenator4
+            | Kind::Concatenator8 => {
+                // `in0` is the lowest part.
+                let parts = self
+                    .pins(i)
+                    .iter()
+                    .filter(|p| p.name.starts_with("in"))
+                    .count();
+                let inputs: Vec<_> = (0..parts)
+                    .rev()
+                    .map(|n| self.read(i, &format!("in{}", n)))
+                    .collect();
+                self.assign(i, "out", format!("{{{}}}", inputs.join(", ")));
+            }
+            Kind::SplitterBit2
+            | Kind::SplitterBit4
+            | Kind::SplitterBit8
+            | Kind::SplitterWord2
+            | Kind::SplitterWord4
+            | Kind::SplitterWord8 => {
+                // `out0` is the lowest part.
+                let input = self.read(i, "in");
+                let mut low = 0;
+                let parts = self
+                    .pins(i)
+                    .iter()
+                    .filter(|p| p.name.starts_with("out"))
+                    .count();
+                for n in 0..parts {
+                    let pin = format!("out{}", n);
+                    let bits = self.width(i, &pin);
+                    let expr = format!("{} >> {}", input, low);
+                    self.assign(i, &pin, expr);
+                    low += bits;
+                }
+            }
+            Kind::RegisterBit | Kind::RegisterWord => {
+                let state = self.state(i, self.width(i, "out"), None);
+                let (save, value) = (self.read(i, "save"), self.read(i, "value"));
+                self.clock(format!("if ({}) {} <= {};", save, state, value));
+                let expr = format!(
+                    "{} ? {} : {}'d0",
+                    self.read(i, "load"),
+                    state,
+                    self.width(i, "out")
+                );
+                self.assign(i, "out", expr);
+            }
+            Kind::DelayLineBit | Kind::DelayLineWord | Kind::ConfigDelay => {
+                let delay = match component.typed_settings() {
+                    ComponentSettings::ConfigDelay { delay } => delay.clamp(1, 1 << 16),
+                    _ => 1,
+                };
+                let bits = self.width(i, "out");
+                let a = self.read(i, "a");
+                if delay == 1 {
+                    let state = self.state(i, bits, None);
+                    self.clock(format!("{} <= {};", state, a));
+                    self.assign(i, "out", state);
+                } else {
+                    let line = self.state(i, bits, Some(delay));
+                    self.clock(format!(
+                        "for (c{i}_i = {last}; c{i}_i > 0; c{i}_i = c{i}_i - 1) {line}[c{i}_i] <= {line}[c{i}_i - 1];",
+                        last = delay - 1
+                    ));
+                    self.clock(format!("{}[0] <= {};", line, a));
+                    self.decls.push(format!("integer c{}_i;", i));
+                    self.assign(i, "out", format!("{}[{}]", line, delay - 1));
+                }
+            }
+            Kind::Counter => {
+                let increment = match component.typed_settings() {
+                    ComponentSettings::Counter { increment } => increment,
+                    _ => 1,
+                };
+                let bits = self.width(i, "out");
+                let state = self.state(i, bits, None);
+                let (overwrite, value) = (self.read(i, "overwrite"), self.read(i, "value"));
+                let increment = increment & crate::sim::mask(bits);
+                self.clock(format!(
+                    "{state} <= {overwrite} ? {value} : {state} + {bits}'d{increment};"
+                ));
+                self.assign(i, "out", state);
+            }
+            Kind::Custom => {
+                let mut connections = vec![".clk(clk)".to_string()];
+                for pin in self.pins(i) {
+                    let value = match pin.direction {
+                        PinDirection::Input => self.read(i, &pin.name),
+                        PinDirection::Output => self.out(i, &pin.name),
+                    };
+                    connections.push(format!(".{}({})", identifier(&pin.name), value));
+                }
+                self.body.push(format!(
+                    "{} c{} ({});",
+                    custom_module_name(component.custom.id),
+                    i,
+                    connections.join(", ")
+                ));
+            }
+            kind if kind.is_input() => {
+                let widths: Vec<_> = kind
+                    .port_pins()
+                    .map(|pin| (pin.name, self.width(i, pin.name)))
+                    .collect();
+                let bits = widths.iter().map(|&(_, bits)| bits).sum();
+                let name = self.port_name(i, "in");
+                self.ports.push(format!("input {}{}", range(bits), name));
+                let mut low = 0;
+                for (pin, width) in widths {
+                    let expr = match width {
+                        _ if width == bits => name.clone(),
+                        1 => format!("{}[{}]", name, low),
+                        _ => format!("{}[{}:{}]", name, low + width - 1, low),
+                    };
+                    self.assign(i, pin, expr);
+                    low += width;
+                }
+            }
+            kind if kind.is_output() => {
+                let bits = kind.port_pins().map(|pin| self.width(i, pin.name)).sum();
+                let name = self.port_name(i, "out");
+                self.ports.push(format!("output {}{}", range(bits), name));
+                // The highest part comes first in a concatenation.
+                let mut parts: Vec<_> =
+                    kind.port_pins().map(|pin| self.read(i, pin.name)).collect();
+                parts.reverse();
+                let mut value = if parts.len() == 1 {
+                    parts.swap_remove(0)
+                } else {
+                    format!("{{{}}}", parts.join(", "))
+                };
+                if kind.pin("enable").is_some() {
+                    value = format!("{} ? {} : {}'d0", self.read(i, "enable"), value, bits);
+                }
+                self.body.push(format!("assign {} = {};", name, value));
+            }
+            _ if self.pins(i).is_empty() => {}
+            kind => self.unsupported.push(format!("c{}: {}", i, kind.name())),
+        }
+    }
+
+    /// Declares a register, or an array of `depth` registers, starting at 0.
+    fn state(&mut self, component: usize, bits: u32, depth: Option<u64>) -> String {
+        let name = format!("c{}_state", component);
+        match depth {
+            None => {
+                self.decls.push(format!("reg {}{} = 0;", range(bits), name));
+            }
+            Some(depth) => {
+                self.decls
+                    .push(format!("reg {}{} [0:{}];", range(bits), name, depth - 1));
+            }
+        }
+        name
+    }
+
+    fn clock(&mut self, statement: String) {
+        self.body
+            .push(format!("always @(posedge clk) {}", statement));
+    }
+
+    fn finish(mut self, name: &str) -> String {
+        for (n, net) in self.netlist.nets.iter().enumerate() {
+            let drivers: Vec<_> = net
+                .pins
+                .iter()
+                .map(|&p: &PinRef| (p, self.netlist.pin(p)))
+                .filter(|(_, pin)| pin.direction == PinDirection::Output)
+                .map(|(p, pin)| self.out(p.component, &pin.name))
+                .collect();
+            let width = self.net_widths[n];
+            self.decls.push(format!("wire {}n{};", range(width), n));
+            let value = if drivers.is_empty() {
+                format!("{}'d0", width)
+            } else {
+                drivers.join(" | ")
+            };
+            self.body.push(format!("assign n{} = {};", n, value));
+        }
+
+        let mut ports = self.ports;
+        ports.insert(0, "input clk".to_string());
+        let mut out = String::new();
+        writeln!(out, "module {} (", identifier(name)).unwrap();
+        for (i, port) in ports.iter().enumerate() {
+            let comma = if i + 1 < ports.len() { "," } else { "" };
+            writeln!(out, "    {}{}", port, comma).unwrap();
+        }
+        writeln!(out, ");").unwrap();
+        if !self.unsupported.is_empty() {
+            writeln!(out, "    // Not exported: {}", self.unsupported.join(", ")).unwrap();
+        }
+        for line in self.decls.iter().chain(&self.body) {
+            writeln!(out, "    {}", line).unwrap();
+        }
+        writeln!(out, "endmodule").unwrap();
+        out
+    }
+}
@@ -1,13 +1,17 @@
 use std::io::Cursor;
 
-use binrw::{BinWrite, binrw, helpers::until};
+use binrw::{BinResult, BinWrite, binrw};
+use tc_save_parser_macros::located;
 
-use crate::{CDString, Point, kind::Kind};
+pub use crate::wire::{Wire, WireBuilder, WireDirection, WireSegment};
+use crate::{CDString, Point, kind::Kind, located::located_vec};
 
+#[located]
 #[binrw]
 #[br(little)]
 #[bw(little)]
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CircuitData {
     pub custom_id: i64,
     pub hub_id: u32,
@@ -19,41 +23,42 @@ pub struct CircuitData {
     pub clock_speed: u64,
     #[bw(try_calc(u16::try_from(dependencies.len())))]
     dependencies_len: u16,
-    #[br(count = dependencies_len)]
+    #[br(parse_with = located_vec, args(u64::from(dependencies_len)))]
     pub dependencies: Vec<i64>,
     pub description: CDString,
     pub camera_position: Point,
     pub synced: u8, // SyncState
-    dummy0: u16,
-    // dummy0: u32,
+    /// Unknown, preserved as read. Versions 8 and 9 store a `u32` here.
+    pub dummy0: u16,
     #[bw(try_calc(u16::try_from(player_data.len())))]
     player_data_len: u16,
-    #[br(count = player_data_len)]
+    #[br(parse_with = located_vec, args(u64::from(player_data_len)))]
     pub player_data: Vec<u8>,
     pub hub_description: CDString,
-    #[bw(try_calc(u64::try_from(components.len() as u64)))]
+    #[bw(calc(components.len() as u64))]
     components_len: u64,
-    #[br(count = components_len)]
+    #[br(parse_with = located_vec, args(components_len))]
     pub components: Vec<Component>,
-    #[bw(try_calc(u64::try_from(wires.len() as u64)))]
+    #[bw(calc(wires.len() as u64))]
     wires_len: u64,
-    #[br(count = wires_len)]
+    #[br(parse_with = located_vec, args(wires_len))]
     pub wires: Vec<Wire>,
 }
 
 impl CircuitData {
-    pub fn get_bytes(&self) -> Vec<u8> {
-        let mut buf = Cursor::new(vec![0u8; 8192]);
-        self.write(&mut buf).unwrap();
-        let len = buf.position();
-        buf.into_inner()[..len as usize].to_vec()
+    pub fn get_bytes(&self) -> BinResult<Vec<u8>> {
+        let mut buf = Cursor::new(Vec::new());
+        self.write(&mut buf)?;
+        Ok(buf.into_inner())
     }
 }
 
+#[located]
 #[binrw]
 #[br(little)]
 #[bw(little)]
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Component {
     // #[bw(try_calc(u16::from(kind.clone())))]
     // #[br(map = |k:u16| Kind::from(k))]
@@ -64,13 +69,14 @@ pub struct Component {
     pub custom_string: CDString,
     #[bw(try_calc(u16::try_from(settings.len())))]
     settings_len: u16,
-    #[br(count = settings_len)]
+    #[br(parse_with = located_vec, args(u64::from(settings_len)))]
     pub settings: Vec<u64>,
     pub buffer_size: i64,
     pub ui_order: i16,
     pub word_size: i64,
-    dummy0: i64,
-    // dummy0: u16,
+    /// Unknown, present on every component and preserved as read. Later
+    /// versions have no equivalent.
+    pub dummy0: i64,
     #[bw(if(kind.is_custom()))]
     #[br(if(kind.is_custom()))]
     pub custom: CustomInfo,
@@ -79,70 +85,69 @@ pub struct Component {
     pub memory: MemoryInfo,
 }
 
+#[located]
 #[binrw]
 #[br(little)]
 #[bw(little)]
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CustomInfo {
     pub id: i64,
     #[bw(try_calc(u16::try_from(explicit_word_sizes.len())))]
     pub explicit_word_sizes_len: u16,
-    #[br(count = explicit_word_sizes_len)]
+    #[br(parse_with = located_vec, args(u64::from(explicit_word_sizes_len)))]
     pub explicit_word_sizes: Vec<ExplicitWordSize>,
-    dummy0: u16,
+    /// Unknown, preserved as read. Later versions have no equivalent.
+    pub dummy0: u16,
 }
 
+#[located]
 #[binrw]
 #[br(little)]
 #[bw(little)]
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SelectedProgram {
-    level: CDString,
-    program: CDString,
+    pub level: CDString,
+    pub program: CDString,
 }
 
+#[located]
 #[binrw]
 #[br(little)]
 #[bw(little)]
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WatchedComponent {
-    permanent_id: i64,
-    inner_id: i64,
-    name: CDString,
+    pub permanent_id: i64,
+    pub inner_id: i64,
+    pub name: CDString,
 }
 
+#[located]
 #[binrw]
 #[br(little)]
 #[bw(little)]
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MemoryInfo {
     #[bw(try_calc(u16::try_from(selected_programs.len())))]
     selected_programs_len: u16,
-    #[br(count = selected_programs_len)]
+    #[br(parse_with = located_vec, args(u64::from(selected_programs_len)))]
     pub selected_programs: Vec<SelectedProgram>,
     #[bw(try_calc(u16::try_from(watched_components.len())))]
     watched_components_len: u16,
-    #[br(count = watched_components_len)]
+    #[br(parse_with = located_vec, args(u64::from(watched_components_len)))]
     pub watched_components: Vec<WatchedComponent>,
 }
 
+#[located]
 #[binrw]
 #[br(little)]
 #[bw(little)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ExplicitWordSize {
     pub a: i64,
     pub b: i64,
 }
-
-#[binrw]
-#[br(little)]
-#[bw(little)]
-#[derive(Debug, Clone)]
-pub struct Wire {
-    pub color: u8,
-    pub comment: CDString,
-    pub start: Point,
-    #[br(parse_with = until(|v| *v == 0))]
-    pub segments: Vec<u8>,
-}
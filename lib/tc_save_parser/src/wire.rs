@@ -0,0 +1,308 @@
+use binrw::{BinRead, BinWrite, binrw, helpers::until};
+use modular_bitfield::BitfieldSpecifier;
+use tc_save_parser_macros::located;
+
+use crate::{CDString, Error, Point};
+
+/// Longest run a single segment can store.
+pub const MAX_SEGMENT_LENGTH: u8 = 31;
+
+#[located]
+#[binrw]
+#[br(little)]
+#[bw(little)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Wire {
+    pub color: u8,
+    pub comment: CDString,
+    pub start: Point,
+    #[br(parse_with = until(WireSegment::is_terminator))]
+    pub segments: Vec<WireSegment>,
+}
+
+impl Wire {
+    /// Builds a wire through `points`, which must be joined by horizontal,
+    /// vertical or diagonal runs. Runs longer than [`MAX_SEGMENT_LENGTH`] are
+    /// split across several segments.
+    pub fn from_polyline(points: &[Point]) -> Result<Wire, Error> {
+        let start = points.first().copied().unwrap_or_default();
+        let mut builder = WireBuilder::new(start);
+        for pair in points.windows(2) {
+            let (from, to) = (pair[0], pair[1]);
+            let (dx, dy) = (
+                i32::from(to.x) - i32::from(from.x),
+                i32::from(to.y) - i32::from(from.y),
+            );
+            if dx == 0 && dy == 0 {
+                continue;
+            }
+            let step = Point::new(dx.signum() as i16, dy.signum() as i16);
+            let direction = match WireDirection::from_offset(step) {
+                Some(direction) if dx == 0 || dy == 0 || dx.abs() == dy.abs() => direction,
+                _ => return Err(Error::NotOctilinear { from, to }),
+            };
+            builder = builder.run(direction, dx.abs().max(dy.abs()) as u32);
+        }
+        Ok(builder.build())
+    }
+
+    /// Every grid cell the wire passes through, in order, starting with
+    /// `start`.
+    pub fn cells(&self) -> Vec<Point> {
+        let mut cells = vec![self.start];
+        let mut at = self.start;
+        for segment in &self.segments {
+            let step = segment.direction().offset();
+            for _ in 0..segment.length() {
+                at = step_by(at, step, 1);
+                cells.push(at);
+            }
+        }
+        cells
+    }
+
+    /// The start, every point where the wire changes direction, and the end.
+    /// A wire that doesn't move has only its start.
+    pub fn corners(&self) -> Vec<Point> {
+        let mut corners = vec![self.start];
+        let mut at = self.start;
+        let mut heading = None;
+        for segment in self.segments.iter().filter(|s| s.length() != 0) {
+            let direction = segment.direction();
+            if heading.is_some_and(|h| h != direction) {
+                corners.push(at);
+            }
+            at = step_by(at, direction.offset(), segment.length());
+            heading = Some(direction);
+        }
+        if heading.is_some() {
+            corners.push(at);
+        }
+        corners
+    }
+
+    pub fn end(&self) -> Point {
+        self.segments.iter().fold(self.start, |at, segment| {
+            step_by(at, segment.direction().offset(), segment.length())
+        })
+    }
+
+    /// The two cells a wire connects, `start` then `end`.
+    pub fn endpoints(&self) -> (Point, Point) {
+        (self.start, self.end())
+    }
+}
+
+// Wraps rather than overflowing, like the game's own `i16` coordinates.
+fn step_by(at: Point, step: Point, count: u8) -> Point {
+    let count = i16::from(count);
+    Point::new(
+        at.x.wrapping_add(step.x.wrapping_mul(count)),
+        at.y.wrapping_add(step.y.wrapping_mul(count)),
+    )
+}
+
+pub use segment::WireSegment;
+
+// The code the bitfield macro generates wraps field types in parentheses.
+#[allow(unused_parens)]
+mod segment {
+    use modular_bitfield::{BitfieldSpecifier, bitfield, prelude::B5};
+
+    use super::WireDirection;
+
+    #[bitfield(bits = 8)]
+    #[derive(BitfieldSpecifier, Debug, Clone, Copy, Default, PartialEq, Eq)]
+    pub struct WireSegment {
+        pub length: B5,
+        #[bits = 3]
+        pub direction: WireDirection,
+    }
+}
+
+impl WireSegment {
+    /// The zero-length step right that ends every segment list.
+    pub fn is_terminator(&self) -> bool {
+        self.direction() == WireDirection::Right && self.length() == 0
+    }
+}
+
+impl BinRead for WireSegment {
+    type Args<'a> = ();
+
+    fn read_options<R: std::io::Read + std::io::Seek>(
+        reader: &mut R,
+        _endian: binrw::Endian,
+        _args: Self::Args<'_>,
+    ) -> binrw::BinResult<Self> {
+        let mut buf = [0u8; 1];
+        reader.read_exact(&mut buf)?;
+        Ok(WireSegment::from_bytes(buf))
+    }
+}
+
+impl BinWrite for WireSegment {
+    type Args<'a> = ();
+
+    fn write_options<W: std::io::Write + std::io::Seek>(
+        &self,
+        writer: &mut W,
+        _endian: binrw::Endian,
+        _args: Self::Args<'_>,
+    ) -> binrw::BinResult<()> {
+        let buf = WireSegment::into_bytes(*self);
+        writer.write_all(&buf)?;
+        Ok(())
+    }
+}
+
+/// The fields of a [`WireSegment`], which serde can't derive through the
+/// bitfield.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename = "WireSegment")]
+struct SegmentFields {
+    direction: WireDirection,
+    length: u8,
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for WireSegment {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SegmentFields {
+            direction: self.direction(),
+            length: self.length(),
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for WireSegment {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let fields = SegmentFields::deserialize(deserializer)?;
+        if fields.length > MAX_SEGMENT_LENGTH {
+            return Err(serde::de::Error::custom(format!(
+                "segment length {} is over {}",
+                fields.length, MAX_SEGMENT_LENGTH
+            )));
+        }
+        Ok(WireSegment::new()
+            .with_direction(fields.direction)
+            .with_length(fields.length))
+    }
+}
+
+#[derive(BitfieldSpecifier, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[bits = 3]
+#[repr(u8)]
+pub enum WireDirection {
+    Right = 0,
+    DownRight,
+    Down,
+    DownLeft,
+    Left,
+    UpLeft,
+    Up,
+    UpRight,
+}
+
+impl WireDirection {
+    /// Every direction, clockwise from `Right`, indexed by discriminant.
+    pub const ALL: [WireDirection; 8] = [
+        WireDirection::Right,
+        WireDirection::DownRight,
+        WireDirection::Down,
+        WireDirection::DownLeft,
+        WireDirection::Left,
+        WireDirection::UpLeft,
+        WireDirection::Up,
+        WireDirection::UpRight,
+    ];
+
+    /// The step one cell in this direction takes, with `y` growing downwards.
+    pub fn offset(self) -> Point {
+        match self {
+            WireDirection::Right => Point::new(1, 0),
+            WireDirection::DownRight => Point::new(1, 1),
+            WireDirection::Down => Point::new(0, 1),
+            WireDirection::DownLeft => Point::new(-1, 1),
+            WireDirection::Left => Point::new(-1, 0),
+            WireDirection::UpLeft => Point::new(-1, -1),
+            WireDirection::Up => Point::new(0, -1),
+            WireDirection::UpRight => Point::new(1, -1),
+        }
+    }
+
+    /// The direction of a single step, the inverse of [`Self::offset`].
+    pub fn from_offset(step: Point) -> Option<WireDirection> {
+        WireDirection::ALL.into_iter().find(|d| d.offset() == step)
+    }
+}
+
+pub struct WireBuilder(Wire);
+
+impl WireBuilder {
+    pub fn new(start: Point) -> Self {
+        Self(Wire {
+            color: 0,
+            comment: CDString::default(),
+            start,
+            segments: vec![],
+        })
+    }
+    pub fn comment(mut self, comment: &str) -> Self {
+        self.0.comment = CDString::from(comment);
+        self
+    }
+    pub fn color(mut self, color: u8) -> Self {
+        self.0.color = color;
+        self
+    }
+    /// Adds a run of `length` cells, split into as many segments as it
+    /// needs. Zero-length runs are skipped, since one would end the wire.
+    pub fn go(self, dir: WireDirection, length: u8) -> Self {
+        self.run(dir, u32::from(length))
+    }
+    pub(crate) fn run(mut self, dir: WireDirection, mut length: u32) -> Self {
+        while length > 0 {
+            let step = length.min(u32::from(MAX_SEGMENT_LENGTH)) as u8;
+            self.0
+                .segments
+                .push(WireSegment::new().with_direction(dir).with_length(step));
+            length -= u32::from(step);
+        }
+        self
+    }
+    pub fn right(self, length: u8) -> Self {
+        self.go(WireDirection::Right, length)
+    }
+    pub fn down_right(self, length: u8) -> Self {
+        self.go(WireDirection::DownRight, length)
+    }
+    pub fn down(self, length: u8) -> Self {
+        self.go(WireDirection::Down, length)
+    }
+    pub fn down_left(self, length: u8) -> Self {
+        self.go(WireDirection::DownLeft, length)
+    }
+    pub fn left(self, length: u8) -> Self {
+        self.go(WireDirection::Left, length)
+    }
+    pub fn up_left(self, length: u8) -> Self {
+        self.go(WireDirection::UpLeft, length)
+    }
+    pub fn up(self, length: u8) -> Self {
+        self.go(WireDirection::Up, length)
+    }
+    pub fn up_right(self, length: u8) -> Self {
+        self.go(WireDirection::UpRight, length)
+    }
+    /// Finishes the wire, ending its segments with the terminator.
+    pub fn build(mut self) -> Wire {
+        self.0.segments.push(WireSegment::new());
+        self.0
+    }
+}
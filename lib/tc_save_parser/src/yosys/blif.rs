@@ -0,0 +1,230 @@
+//! Writing gate-level yosys modules as BLIF, for ABC and other tools that
+//! read it.
+//!
+//! Gates become `.names` truth tables and flip-flops `.latch`es, with
+//! enables turned into a multiplexer in front. Cell types that aren't
+//! yosys-internal, such as the custom components [`export`] produces, become
+//! `.subckt`s of the model of that name. Bits are named after the port they
+//! belong to, `name` or `name[i]`, or `n<number>` otherwise, and constants
+//! come from `$false` and `$true`.
+//!
+//! [`export`]: super::export
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::Write,
+};
+
+use super::{Bit, Cell, Module, Netlist, PortDirection};
+use crate::Error;
+
+impl Netlist {
+    /// Writes every module as a BLIF model, the top one first.
+    pub fn to_blif(&self) -> Result<String, Error> {
+        let top = self.top().map(|(name, _)| name);
+        let mut out = String::new();
+        if let Some((name, module)) = self.top() {
+            out.push_str(&module.to_blif(name)?);
+        }
+        for (name, module) in &self.modules {
+            if Some(name.as_str()) != top {
+                out.push_str(&module.to_blif(name)?);
+            }
+        }
+        Ok(out)
+    }
+}
+
+impl Module {
+    /// Writes the module as a BLIF model called `name`.
+    pub fn to_blif(&self, name: &str) -> Result<String, Error> {
+        if let Some((port, _)) = self
+            .ports
+            .iter()
+            .find(|(_, p)| p.direction == PortDirection::Inout)
+        {
+            return Err(Error::InvalidNetlist(format!(
+                "inout port {} can't be written as BLIF",
+                port
+            )));
+        }
+        let mut writer = BlifWriter::default();
+        let mut inputs = Vec::new();
+        let mut outputs = Vec::new();
+        for direction in [PortDirection::Input, PortDirection::Output] {
+            for (port, def) in &self.ports {
+                if def.direction != direction {
+                    continue;
+                }
+                for (i, bit) in def.bits.iter().enumerate() {
+                    let label = if def.bits.len() == 1 {
+                        port.clone()
+                    } else {
+                        format!("{}[{}]", port, i)
+                    };
+                    if direction == PortDirection::Input {
+                        writer.claim(bit, &label);
+                        inputs.push(label);
+                    } else {
+                        writer.output(bit, &label);
+                        outputs.push(label);
+                    }
+                }
+            }
+        }
+        for (cell_name, cell) in &self.cells {
+            writer.cell(cell_name, cell)?;
+        }
+
+        let mut out = String::new();
+        writeln!(out, ".model {}", name).unwrap();
+        writeln!(out, ".inputs {}", inputs.join(" ")).unwrap();
+        writeln!(out, ".outputs {}", outputs.join(" ")).unwrap();
+        if writer.constants.contains("$false") {
+            writeln!(out, ".names $false").unwrap();
+        }
+        if writer.constants.contains("$true") {
+            writeln!(out, ".names $true\n1").unwrap();
+        }
+        for line in &writer.body {
+            writeln!(out, "{}", line).unwrap();
+        }
+        writeln!(out, ".end").unwrap();
+        Ok(out)
+    }
+}
+
+#[derive(Default)]
+struct BlifWriter {
+    names: HashMap<u64, String>,
+    /// Constant nets in use.
+    constants: BTreeSet<&'static str>,
+    body: Vec<String>,
+}
+
+impl BlifWriter {
+    /// Names a net after a port, unless it already has a name.
+    fn claim(&mut self, bit: &Bit, label: &str) -> bool {
+        match *bit {
+            Bit::Net(n) if !self.names.contains_key(&n) => {
+                self.names.insert(n, label.to_string());
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Names a net after an output port, or buffers it into one when the
+    /// net already has a name or is a constant.
+    fn output(&mut self, bit: &Bit, label: &str) {
+        if !self.claim(bit, label) {
+            let source = self.name(bit);
+            self.body.push(format!(".names {} {}\n1 1", source, label));
+        }
+    }
+
+    fn name(&mut self, bit: &Bit) -> String {
+        match bit {
+            Bit::Net(n) => self
+                .names
+                .entry(*n)
+                .or_insert_with(|| format!("n{}", n))
+                .clone(),
+            // `x` and `z` read as 0, like in the importer.
+            Bit::Constant(c) => {
+                let name = if c == "1" { "$true" } else { "$false" };
+                self.constants.insert(name);
+                name.to_string()
+            }
+        }
+    }
+
+    fn pin(&mut self, cell_name: &str, cell: &Cell, pin: &str) -> Result<String, Error> {
+        match cell.connections.get(pin).map(Vec::as_slice) {
+            Some([bit]) => Ok(self.name(bit)),
+            _ => Err(Error::InvalidNetlist(format!(
+                "cell {} needs a single bit on {}",
+                cell_name, pin
+            ))),
+        }
+    }
+
+    fn names(
+        &mut self,
+        cell_name: &str,
+        cell: &Cell,
+        inputs: &[&str],
+        rows: &[&str],
+    ) -> Result<(), Error> {
+        let mut nets = Vec::new();
+        for pin in inputs.iter().chain(&["Y"]) {
+            nets.push(self.pin(cell_name, cell, pin)?);
+        }
+        self.body
+            .push(format!(".names {}\n{}", nets.join(" "), rows.join("\n")));
+        Ok(())
+    }
+
+    fn cell(&mut self, cell_name: &str, cell: &Cell) -> Result<(), Error> {
+        let ab = &["A", "B"][..];
+        match cell.cell_type.as_str() {
+            "$_BUF_" => self.names(cell_name, cell, &["A"], &["1 1"]),
+            "$_NOT_" => self.names(cell_name, cell, &["A"], &["0 1"]),
+            "$_AND_" => self.names(cell_name, cell, ab, &["11 1"]),
+            "$_NAND_" => self.names(cell_name, cell, ab, &["0- 1", "-0 1"]),
+            "$_OR_" => self.names(cell_name, cell, ab, &["1- 1", "-1 1"]),
+            "$_NOR_" => self.names(cell_name, cell, ab, &["00 1"]),
+            "$_XOR_" => self.names(cell_name, cell, ab, &["01 1", "10 1"]),
+            "$_XNOR_" => self.names(cell_name, cell, ab, &["00 1", "11 1"]),
+            "$_ANDNOT_" => self.names(cell_name, cell, ab, &["10 1"]),
+            "$_ORNOT_" => self.names(cell_name, cell, ab, &["1- 1", "-0 1"]),
+            "$_MUX_" => self.names(cell_name, cell, &["A", "B", "S"], &["1-0 1", "-11 1"]),
+            "$_NMUX_" => self.names(cell_name, cell, &["A", "B", "S"], &["0-0 1", "-01 1"]),
+            "$_DFF_P_" | "$_DFF_N_" | "$_DFFE_PP_" | "$_DFFE_PN_" | "$_DFFE_NP_" | "$_DFFE_NN_" => {
+                let clock = self.pin(cell_name, cell, "C")?;
+                let mut d = self.pin(cell_name, cell, "D")?;
+                let q = self.pin(cell_name, cell, "Q")?;
+                // The letters after the type name are the clock polarity,
+                // then the enable polarity.
+                let (clock_polarity, enable_polarity) = match cell.cell_type.strip_prefix("$_DFFE_")
+                {
+                    Some(rest) => (rest.as_bytes()[0], Some(rest.as_bytes()[1])),
+                    None => (cell.cell_type.as_bytes()[6], None),
+                };
+                let edge = if clock_polarity == b'P' { "re" } else { "fe" };
+                if let Some(polarity) = enable_polarity {
+                    // Keep the old value while the enable is inactive.
+                    let enable = self.pin(cell_name, cell, "E")?;
+                    let (active, idle) = if polarity == b'P' { (1, 0) } else { (0, 1) };
+                    let next = format!("{}$next", q);
+                    self.body.push(format!(
+                        ".names {q} {d} {enable} {next}\n1-{idle} 1\n-1{active} 1"
+                    ));
+                    d = next;
+                }
+                self.body
+                    .push(format!(".latch {} {} {} {} 0", d, q, edge, clock));
+                Ok(())
+            }
+            cell_type if !cell_type.starts_with('$') => {
+                let mut line = format!(".subckt {}", cell_type);
+                for (port, bits) in &cell.connections {
+                    for (i, bit) in bits.iter().enumerate() {
+                        let net = self.name(bit);
+                        if bits.len() == 1 {
+                            write!(line, " {}={}", port, net).unwrap();
+                        } else {
+                            write!(line, " {}[{}]={}", port, i, net).unwrap();
+                        }
+                    }
+                }
+                self.body.push(line);
+                Ok(())
+            }
+            cell_type => Err(Error::UnsupportedCell {
+                cell: cell_name.to_string(),
+                cell_type: cell_type.to_string(),
+            }),
+        }
+    }
+}
@@ -0,0 +1,627 @@
+//! Exporting circuits as gate-level yosys netlists.
+//!
+//! Every pin is split into bits and every component into yosys' internal
+//! single-bit cells (`$_AND_`, `$_MUX_`, `$_DFF_P_`, ...), so word gates
+//! become one gate per bit and adders become ripple-carry chains. Makers,
+//! splitters and constants only rearrange bits and produce no cells. Nets
+//! with several drivers are the OR of them, like in the game.
+//!
+//! Sequential components are clocked by a `clk` input every module has.
+//! Ports come from [input](Kind::is_input) and [output](Kind::is_output)
+//! components, named by their label. `Custom` components become cells of the type
+//! [`custom_module_name`] gives, with their pins as ports plus `clk`.
+//!
+//! [`custom_module_name`]: crate::verilog::custom_module_name
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use super::{Bit, Cell, Module, NetName, Netlist, Port, PortDirection};
+use crate::{
+    ComponentSettings, Error, Kind,
+    catalog::PinDirection,
+    netlist::{self, NetlistBuilder, PinDef},
+    v9,
+    verilog::custom_module_name,
+};
+
+const ZERO: &str = "0";
+const ONE: &str = "1";
+
+fn constant(value: &str) -> Bit {
+    Bit::Constant(value.to_string())
+}
+
+pub struct Exporter<'a> {
+    circuit: &'a v9::CircuitData,
+    netlist: NetlistBuilder<'a>,
+}
+
+impl<'a> Exporter<'a> {
+    pub fn new(circuit: &'a v9::CircuitData) -> Self {
+        Self {
+            circuit,
+            netlist: NetlistBuilder::new(circuit),
+        }
+    }
+
+    /// Supplies the unrotated pins of `Custom` components, which also name
+    /// the ports of their cells.
+    pub fn custom_pins(
+        mut self,
+        resolve: impl Fn(&v9::Component) -> Option<Vec<PinDef>> + 'a,
+    ) -> Self {
+        self.netlist = self.netlist.custom_pins(resolve);
+        self
+    }
+
+    /// Builds the module. Components without a gate-level equivalent, such
+    /// as memories and devices, are [`Error::UnsupportedCell`]s.
+    pub fn module(self) -> Result<Module, Error> {
+        let netlist = self.netlist.build();
+        let mut builder = Builder {
+            circuit: self.circuit,
+            netlist: &netlist,
+            module: Module::default(),
+            next: 2,
+            aliases: HashMap::new(),
+            outputs: HashMap::new(),
+            nets: Vec::new(),
+            port_names: HashSet::new(),
+            clock: Bit::Net(0),
+        };
+        builder.clock = builder.port("clk".to_string(), PortDirection::Input, 1)[0].clone();
+        builder.allocate();
+        for component in 0..self.circuit.components.len() {
+            builder.component(component)?;
+        }
+        builder.name_wires();
+        Ok(builder.finish())
+    }
+
+    /// Builds a netlist holding just the module, as its top.
+    pub fn netlist(self, name: &str) -> Result<Netlist, Error> {
+        let mut module = self.module()?;
+        module.attributes.insert(
+            "top".to_string(),
+            serde_json::Value::String(format!("{:032b}", 1)),
+        );
+        Ok(Netlist {
+            modules: BTreeMap::from([(name.to_string(), module)]),
+        })
+    }
+}
+
+/// Exports `circuit` as a module called `name`, which must not contain
+/// custom components.
+pub fn export(circuit: &v9::CircuitData, name: &str) -> Result<Netlist, Error> {
+    Exporter::new(circuit).netlist(name)
+}
+
+struct Builder<'a> {
+    circuit: &'a v9::CircuitData,
+    netlist: &'a netlist::Netlist,
+    module: Module,
+    /// Next unused net number; yosys reserves 0 and 1.
+    next: u64,
+    /// Bits that turned out to be other bits, resolved when finishing.
+    aliases: HashMap<u64, Bit>,
+    /// Fresh bits for every output pin, by component and pin name.
+    outputs: HashMap<(usize, String), Vec<Bit>>,
+    /// The bits of every net.
+    nets: Vec<Vec<Bit>>,
+    port_names: HashSet<String>,
+    clock: Bit,
+}
+
+impl Builder<'_> {
+    fn fresh(&mut self) -> Bit {
+        self.next += 1;
+        Bit::Net(self.next - 1)
+    }
+
+    fn fresh_bits(&mut self, width: u32) -> Vec<Bit> {
+        (0..width).map(|_| self.fresh()).collect()
+    }
+
+    /// Makes `bit`, a fresh bit, stand for `value`.
+    fn alias(&mut self, bit: &Bit, value: Bit) {
+        if let Bit::Net(n) = *bit {
+            self.aliases.insert(n, value);
+        }
+    }
+
+    fn alias_all(&mut self, bits: &[Bit], values: Vec<Bit>) {
+        for (bit, value) in bits.iter().zip(values) {
+            self.alias(bit, value);
+        }
+    }
+
+    fn width(&self, component: usize, pin: &str) -> u32 {
+        let word_size = self.circuit.components[component].word_size;
+        self.netlist.pins[component]
+            .iter()
+            .find(|p| p.name == pin)
+            .map_or(1, |p| p.width.bits(word_size))
+    }
+
+    /// Gives every output pin fresh bits, and every net the OR of the
+    /// output pins driving it.
+    fn allocate(&mut self) {
+        for (i, pins) in self.netlist.pins.iter().enumerate() {
+            let word_size = self.circuit.components[i].word_size;
+            for pin in pins {
+                if pin.direction == PinDirection::Output {
+                    let bits = self.fresh_bits(pin.width.bits(word_size));
+                    self.outputs.insert((i, pin.name.clone()), bits);
+                }
+            }
+        }
+        for (n, net) in self.netlist.nets.iter().enumerate() {
+            let mut width = 0;
+            let mut drivers = Vec::new();
+            for &p in &net.pins {
+                let pin = self.netlist.pin(p);
+                let word_size = self.circuit.components[p.component].word_size;
+                width = width.max(pin.width.bits(word_size));
+                if pin.direction == PinDirection::Output {
+                    drivers.push(self.outputs[&(p.component, pin.name.clone())].clone());
+                }
+            }
+            let bits = (0..width as usize)
+                .map(|b| {
+                    let mut sources = drivers.iter().filter_map(|d| d.get(b).cloned());
+                    let first = sources.next().unwrap_or_else(|| constant(ZERO));
+                    sources.fold(first, |acc, bit| {
+                        self.gate(&format!("n{}", n), "$_OR_", &[acc, bit])
+                    })
+                })
+                .collect();
+            self.nets.push(bits);
+        }
+    }
+
+    /// The bits an input pin reads: its net, cut or padded to the pin's
+    /// width.
+    fn read(&self, component: usize, pin: &str) -> Vec<Bit> {
+        let width = self.width(component, pin) as usize;
+        let net = self
+            .netlist
+            .pin_ref(component, pin)
+            .and_then(|p| self.netlist.net_of(p));
+        let bits = net.map_or(&[][..], |n| &self.nets[n][..]);
+        (0..width)
+            .map(|b| bits.get(b).cloned().unwrap_or_else(|| constant(ZERO)))
+            .collect()
+    }
+
+    fn read_bit(&self, component: usize, pin: &str) -> Bit {
+        self.read(component, pin).swap_remove(0)
+    }
+
+    fn out(&self, component: usize, pin: &str) -> Vec<Bit> {
+        self.outputs
+            .get(&(component, pin.to_string()))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn cell(&mut self, owner: &str, cell_type: &str, connections: Vec<(&str, Vec<Bit>)>) {
+        let name = format!("{}_{}", owner, self.module.cells.len());
+        self.module.cells.insert(
+            name,
+            Cell {
+                cell_type: cell_type.to_string(),
+                port_directions: BTreeMap::new(),
+                connections: connections
+                    .into_iter()
+                    .map(|(pin, bits)| (pin.to_string(), bits))
+                    .collect(),
+            },
+        );
+    }
+
+    /// Adds a gate on `inputs` (`A`, `B`, then `S`) and returns its output.
+    fn gate(&mut self, owner: &str, cell_type: &str, inputs: &[Bit]) -> Bit {
+        let y = self.fresh();
+        let mut connections: Vec<_> = ["A", "B", "S"]
+            .into_iter()
+            .zip(inputs.iter().map(|bit| vec![bit.clone()]))
+            .collect();
+        connections.push(("Y", vec![y.clone()]));
+        self.cell(owner, cell_type, connections);
+        y
+    }
+
+    fn dff(&mut self, owner: &str, d: Bit, enable: Option<Bit>) -> Bit {
+        let q = self.fresh();
+        let mut connections = vec![
+            ("C", vec![self.clock.clone()]),
+            ("D", vec![d]),
+            ("Q", vec![q.clone()]),
+        ];
+        let cell_type = match enable {
+            Some(e) => {
+                connections.push(("E", vec![e]));
+                "$_DFFE_PP_"
+            }
+            None => "$_DFF_P_",
+        };
+        self.cell(owner, cell_type, connections);
+        q
+    }
+
+    /// Ripple-carry `a + b + carry`, as wide as `a`, and the carry out.
+    fn add(&mut self, owner: &str, a: &[Bit], b: &[Bit], mut carry: Bit) -> (Vec<Bit>, Bit) {
+        let mut sum = Vec::new();
+        for (n, a) in a.iter().enumerate() {
+            let b = b.get(n).cloned().unwrap_or_else(|| constant(ZERO));
+            let half = self.gate(owner, "$_XOR_", &[a.clone(), b.clone()]);
+            sum.push(self.gate(owner, "$_XOR_", &[half.clone(), carry.clone()]));
+            let both = self.gate(owner, "$_AND_", &[a.clone(), b]);
+            let carried = self.gate(owner, "$_AND_", &[half, carry]);
+            carry = self.gate(owner, "$_OR_", &[both, carried]);
+        }
+        (sum, carry)
+    }
+
+    fn port(&mut self, base: String, direction: PortDirection, width: u32) -> Vec<Bit> {
+        let mut name = base.clone();
+        let mut n = 1;
+        while !self.port_names.insert(name.clone()) {
+            name = format!("{}_{}", base, n);
+            n += 1;
+        }
+        let bits = self.fresh_bits(width);
+        self.module.ports.insert(
+            name.clone(),
+            Port {
+                direction,
+                bits: bits.clone(),
+            },
+        );
+        self.module.netnames.insert(
+            name,
+            NetName {
+                bits: bits.clone(),
+                hide_name: 0,
+            },
+        );
+        bits
+    }
+
+    fn port_base(&self, component: usize, prefix: &str) -> String {
+        let label = self.circuit.components[component]
+            .custom_string
+            .to_string_lossy();
+        if label.is_empty() {
+            format!("{}{}", prefix, component)
+        } else {
+            label.into_owned()
+        }
+    }
+
+    fn component(&mut self, i: usize) -> Result<(), Error> {
+        let circuit = self.circuit;
+        let component = &circuit.components[i];
+        let kind = component.kind;
+        let owner = format!("c{}", i);
+        let owner = owner.as_str();
+        let unsupported = |cell_type: String| Error::UnsupportedCell {
+            cell: owner.to_string(),
+            cell_type,
+        };
+
+        let bitwise = match kind {
+            Kind::AndBit | Kind::AndWord | Kind::And3Bit => Some("$_AND_"),
+            Kind::OrBit | Kind::OrWord | Kind::Or3Bit => Some("$_OR_"),
+            Kind::XorBit | Kind::XorWord => Some("$_XOR_"),
+            Kind::NandBit | Kind::NandWord => Some("$_NAND_"),
+            Kind::NorBit | Kind::NorWord => Some("$_NOR_"),
+            Kind::XnorBit | Kind::XnorWord => Some("$_XNOR_"),
+            _ => None,
+        };
+        if let Some(cell_type) = bitwise {
+            let (a, b, out) = (self.read(i, "a"), self.read(i, "b"), self.out(i, "out"));
+            let c = matches!(kind, Kind::And3Bit | Kind::Or3Bit).then(|| self.read(i, "c"));
+            for (n, bit) in out.iter().enumerate() {
+                let mut y = self.gate(owner, cell_type, &[a[n].clone(), b[n].clone()]);
+                if let Some(c) = &c {
+                    y = self.gate(owner, cell_type, &[y, c[n].clone()]);
+                }
+                self.alias(bit, y);
+            }
+            return Ok(());
+        }
+
+        match kind {
+            Kind::Off => self.alias_all(&self.out(i, "out"), vec![constant(ZERO)]),
+            Kind::On => self.alias_all(&self.out(i, "out"), vec![constant(ONE)]),
+            Kind::Constant | Kind::StaticValue => {
+                let value = match component.typed_settings() {
+                    ComponentSettings::Constant { value }
+                    | ComponentSettings::StaticValue { value } => value,
+                    _ => component.settings.first().copied().unwrap_or(0),
+                };
+                let out = self.out(i, "out");
+                let bits = (0..out.len())
+                    .map(|b| constant(if (value >> b) & 1 == 1 { ONE } else { ZERO }))
+                    .collect();
+                self.alias_all(&out, bits);
+            }
+            Kind::NotBit | Kind::NotWord => {
+                let (a, out) = (self.read(i, "a"), self.out(i, "out"));
+                for (a, bit) in a.into_iter().zip(&out) {
+                    let y = self.gate(owner, "$_NOT_", &[a]);
+                    self.alias(bit, y);
+                }
+            }
+            Kind::SwitchBit | Kind::SwitchWord => {
+                let (a, enable, out) = (
+                    self.read(i, "a"),
+                    self.read_bit(i, "enable"),
+                    self.out(i, "out"),
+                );
+                for (a, bit) in a.into_iter().zip(&out) {
+                    let y = self.gate(owner, "$_AND_", &[a, enable.clone()]);
+                    self.alias(bit, y);
+                }
+            }
+            Kind::Mux => {
+                let (a, b, select, out) = (
+                    self.read(i, "a"),
+                    self.read(i, "b"),
+                    self.read_bit(i, "select"),
+                    self.out(i, "out"),
+                );
+                for ((a, b), bit) in a.into_iter().zip(b).zip(&out) {
+                    let y = self.gate(owner, "$_MUX_", &[a, b, select.clone()]);
+                    self.alias(bit, y);
+                }
+            }
+            Kind::FullAdder | Kind::Add => {
+                let sum = if kind == Kind::FullAdder {
+                    "sum"
+                } else {
+                    "out"
+                };
+                let (a, b) = (self.read(i, "a"), self.read(i, "b"));
+                let carry_in = self.read_bit(i, "carry_in");
+                let (bits, carry) = self.add(owner, &a, &b, carry_in);
+                self.alias_all(&self.out(i, sum), bits);
+                self.alias_all(&self.out(i, "carry_out"), vec![carry]);
+            }
+            Kind::Inc | Kind::Neg => {
+                let a = self.read(i, "a");
+                let a = if kind == Kind::Neg {
+                    a.into_iter()
+                        .map(|bit| self.gate(owner, "$_NOT_", &[bit]))
+                        .collect()
+                } else {
+                    a
+                };
+                let (bits, _) = self.add(owner, &a, &[], constant(ONE));
+                self.alias_all(&self.out(i, "out"), bits);
+            }
+            Kind::Equal => {
+                let (a, b) = (self.read(i, "a"), self.read(i, "b"));
+                let mut equal = constant(ONE);
+                for (n, (a, b)) in a.into_iter().zip(b).enumerate() {
+                    let same = self.gate(owner, "$_XNOR_", &[a, b]);
+                    equal = if n == 0 {
+                        same
+                    } else {
+                        self.gate(owner, "$_AND_", &[equal, same])
+                    };
+                }
+                self.alias_all(&self.out(i, "out"), vec![equal]);
+            }
+            Kind::Decoder1 | Kind::Decoder2 | Kind::Decoder3 => {
+                let inputs = match kind {
+                    Kind::Decoder1 => &["a"][..],
+                    Kind::Decoder2 => &["a", "b"],
+                    _ => &["a", "b", "c"],
+                };
+                let select: Vec<_> = inputs.iter().map(|pin| self.read_bit(i, pin)).collect();
+                let inverted: Vec<_> = select
+                    .iter()
+                    .map(|bit| self.gate(owner, "$_NOT_", std::slice::from_ref(bit)))
+                    .collect();
+                for n in 0..1usize << inputs.len() {
+                    let mut terms = (0..inputs.len()).map(|k| {
+                        if (n >> k) & 1 == 1 {
+                            select[k].clone()
+                        } else {
+                            inverted[k].clone()
+                        }
+                    });
+                    let first = terms.next().expect("decoders have inputs");
+                    let terms: Vec<_> = terms.collect();
+                    let y = terms
+                        .into_iter()
+                        .fold(first, |acc, term| self.gate(owner, "$_AND_", &[acc, term]));
+                    self.alias_all(&self.out(i, &format!("out{}", n)), vec![y]);
+                }
+            }
+            Kind::MakerBit2
+            | Kind::MakerBit4
+            | Kind::MakerBit8
+            | Kind::MakerWord2
+            | Kind::MakerWord4
+            | Kind::MakerWord8
+            | Kind::Concatenator2
+            | Kind::Concatenator4
+            | Kind::Concatenator8 => {
+                // `in0` is the lowest part.
+                let parts = self.netlist.pins[i]
+                    .iter()
+                    .filter(|p| p.name.starts_with("in"))
+                    .count();
+                let bits = (0..parts)
+                    .flat_map(|n| self.read(i, &format!("in{}", n)))
+                    .collect();
+                self.alias_all(&self.out(i, "out"), bits);
+            }
+            Kind::SplitterBit2
+            | Kind::SplitterBit4
+            | Kind::SplitterBit8
+            | Kind::SplitterWord2
+            | Kind::SplitterWord4
+            | Kind::SplitterWord8 => {
+                // `out0` is the lowest part.
+                let mut input = self.read(i, "in").into_iter();
+                let parts = self.netlist.pins[i]
+                    .iter()
+                    .filter(|p| p.name.starts_with("out"))
+                    .count();
+                for n in 0..parts {
+                    let out = self.out(i, &format!("out{}", n));
+                    let bits = input.by_ref().take(out.len()).collect();
+                    self.alias_all(&out, bits);
+                }
+            }
+            Kind::RegisterBit | Kind::RegisterWord => {
+                let (value, save, load) = (
+                    self.read(i, "value"),
+                    self.read_bit(i, "save"),
+                    self.read_bit(i, "load"),
+                );
+                for (d, bit) in value.into_iter().zip(self.out(i, "out")) {
+                    let q = self.dff(owner, d, Some(save.clone()));
+                    let y = self.gate(owner, "$_AND_", &[q, load.clone()]);
+                    self.alias(&bit, y);
+                }
+            }
+            Kind::DelayLineBit | Kind::DelayLineWord | Kind::ConfigDelay => {
+                let delay = match component.typed_settings() {
+                    ComponentSettings::ConfigDelay { delay } => delay.clamp(1, 1 << 16),
+                    _ => 1,
+                };
+                for (mut d, bit) in self.read(i, "a").into_iter().zip(self.out(i, "out")) {
+                    for _ in 0..delay {
+                        d = self.dff(owner, d, None);
+                    }
+                    self.alias(&bit, d);
+                }
+            }
+            Kind::Counter => {
+                let increment = match component.typed_settings() {
+                    ComponentSettings::Counter { increment } => increment,
+                    _ => 1,
+                };
+                let out = self.out(i, "out");
+                let increment: Vec<_> = (0..out.len())
+                    .map(|b| constant(if (increment >> b) & 1 == 1 { ONE } else { ZERO }))
+                    .collect();
+                let (value, overwrite) = (self.read(i, "value"), self.read_bit(i, "overwrite"));
+                let (next, _) = self.add(owner, &out, &increment, constant(ZERO));
+                for ((next, value), bit) in next.into_iter().zip(value).zip(&out) {
+                    let d = self.gate(owner, "$_MUX_", &[next, value, overwrite.clone()]);
+                    let q = self.dff(owner, d, None);
+                    self.alias(bit, q);
+                }
+            }
+            Kind::Custom => {
+                let cell_type = custom_module_name(component.custom.id);
+                if self.netlist.unresolved_components.contains(&i) {
+                    return Err(unsupported(cell_type));
+                }
+                let mut cell = Cell {
+                    cell_type,
+                    port_directions: BTreeMap::from([("clk".to_string(), PortDirection::Input)]),
+                    connections: BTreeMap::from([("clk".to_string(), vec![self.clock.clone()])]),
+                };
+                for pin in &self.netlist.pins[i] {
+                    let (direction, bits) = match pin.direction {
+                        PinDirection::Input => (PortDirection::Input, self.read(i, &pin.name)),
+                        PinDirection::Output => (PortDirection::Output, self.out(i, &pin.name)),
+                    };
+                    cell.port_directions.insert(pin.name.clone(), direction);
+                    cell.connections.insert(pin.name.clone(), bits);
+                }
+                self.module.cells.insert(owner.to_string(), cell);
+            }
+            kind if kind.is_input() => {
+                let base = self.port_base(i, "in");
+                let out: Vec<_> = kind
+                    .port_pins()
+                    .flat_map(|pin| self.out(i, pin.name))
+                    .collect();
+                let bits = self.port(base, PortDirection::Input, out.len() as u32);
+                self.alias_all(&out, bits);
+            }
+            kind if kind.is_output() => {
+                let base = self.port_base(i, "out");
+                let mut value: Vec<_> = kind
+                    .port_pins()
+                    .flat_map(|pin| self.read(i, pin.name))
+                    .collect();
+                if kind.pin("enable").is_some() {
+                    let enable = self.read_bit(i, "enable");
+                    value = value
+                        .into_iter()
+                        .map(|bit| self.gate(owner, "$_AND_", &[bit, enable.clone()]))
+                        .collect();
+                }
+                // The port's bits end up being the bits of the net it reads.
+                let bits = self.port(base, PortDirection::Output, value.len() as u32);
+                self.alias_all(&bits, value);
+            }
+            _ if self.netlist.pins[i].is_empty() => {}
+            kind => return Err(unsupported(kind.name().to_string())),
+        }
+        Ok(())
+    }
+
+    /// Names nets by the comments of their wires.
+    fn name_wires(&mut self) {
+        for (n, net) in self.netlist.nets.iter().enumerate() {
+            for &wire in &net.wires {
+                let comment = self.circuit.wires[wire].comment.to_string_lossy();
+                if !comment.is_empty() && !self.module.netnames.contains_key(comment.as_ref()) {
+                    self.module.netnames.insert(
+                        comment.into_owned(),
+                        NetName {
+                            bits: self.nets[n].clone(),
+                            hide_name: 0,
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    fn resolve(&self, bit: &Bit) -> Bit {
+        let mut bit = bit.clone();
+        // Chains through makers and splitters can loop back on themselves;
+        // such bits stay undriven.
+        for _ in 0..=self.aliases.len() {
+            match bit {
+                Bit::Net(n) => match self.aliases.get(&n) {
+                    Some(next) => bit = next.clone(),
+                    None => return bit,
+                },
+                Bit::Constant(_) => return bit,
+            }
+        }
+        bit
+    }
+
+    fn finish(mut self) -> Module {
+        let mut module = std::mem::take(&mut self.module);
+        let resolve = |bits: &mut Vec<Bit>| {
+            for bit in bits {
+                *bit = self.resolve(bit);
+            }
+        };
+        for cell in module.cells.values_mut() {
+            cell.connections.values_mut().for_each(resolve);
+        }
+        for port in module.ports.values_mut() {
+            resolve(&mut port.bits);
+        }
+        for name in module.netnames.values_mut() {
+            resolve(&mut name.bits);
+        }
+        module
+    }
+}
@@ -0,0 +1,856 @@
+//! Importing yosys JSON netlists (`write_json`) as placed, wired circuits,
+//! and exporting circuits the other way, as yosys JSON ([`export`]) or BLIF
+//! ([`Netlist::to_blif`]).
+//!
+//! Only the gate-level primitive cells are supported, so synthesize with
+//! something like `synth -flatten; abc -g AND,NAND,OR,NOR,XOR,XNOR,MUX`
+//! first. Cells without a direct equivalent are built from several gates:
+//! `$_MUX_` from NOT, AND and OR, and `$_DFFE_*_` from a multiplexer in front
+//! of a delay line. Flip-flops become bit delay lines, clocked by the tick
+//! rather than their `C` input.
+//!
+//! Every port bit becomes a 1-bit `CcInput` or `CcOutput`, labelled with the
+//! port name, or `name[i]` for wider ports. Gates are labelled with the name
+//! of the cell they came from, and named nets set the comment of their
+//! wires.
+//!
+//! Components are placed in columns by logic depth, with inputs, constants
+//! and delay lines first and outputs last. Every connection is its own wire,
+//! running from the driving pin to a lane left of the sink's column, then
+//! across to the sink.
+//!
+//! With the `yosys-netlist-json` feature, netlists read by that crate
+//! convert into a [`Netlist`] with `From`.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+mod blif;
+pub mod export;
+
+use crate::{
+    CircuitDataFile, CircuitDataVersion, Error, Kind, Point, Wire, new_permament_id,
+    transform::Rotation, v9,
+};
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Netlist {
+    #[serde(default)]
+    pub modules: BTreeMap<String, Module>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Module {
+    #[serde(default)]
+    pub attributes: BTreeMap<String, serde_json::Value>,
+    #[serde(default)]
+    pub ports: BTreeMap<String, Port>,
+    #[serde(default)]
+    pub cells: BTreeMap<String, Cell>,
+    #[serde(default)]
+    pub netnames: BTreeMap<String, NetName>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PortDirection {
+    Input,
+    Output,
+    Inout,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Port {
+    pub direction: PortDirection,
+    pub bits: Vec<Bit>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Cell {
+    #[serde(rename = "type")]
+    pub cell_type: String,
+    /// Written for cell types yosys doesn't know, such as instances of
+    /// other modules.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub port_directions: BTreeMap<String, PortDirection>,
+    #[serde(default)]
+    pub connections: BTreeMap<String, Vec<Bit>>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct NetName {
+    pub bits: Vec<Bit>,
+    #[serde(default)]
+    pub hide_name: u8,
+}
+
+/// A signal bit: a net number, or a constant `"0"`, `"1"`, `"x"` or `"z"`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum Bit {
+    Net(u64),
+    Constant(String),
+}
+
+impl Netlist {
+    pub fn from_json(text: &str) -> Result<Self, Error> {
+        serde_json::from_str(text).map_err(|e| Error::InvalidNetlist(e.to_string()))
+    }
+
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, Error> {
+        serde_json::from_reader(reader).map_err(|e| Error::InvalidNetlist(e.to_string()))
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("netlists serialize as JSON")
+    }
+
+    pub fn module(&self, name: &str) -> Result<&Module, Error> {
+        self.modules
+            .get(name)
+            .ok_or_else(|| Error::NoSuchModule(name.to_string()))
+    }
+
+    /// The module yosys marked as top, or the only module.
+    pub fn top(&self) -> Option<(&str, &Module)> {
+        let is_top = |m: &Module| match m.attributes.get("top") {
+            Some(serde_json::Value::String(s)) => s.contains('1'),
+            Some(serde_json::Value::Number(n)) => n.as_u64() != Some(0),
+            _ => false,
+        };
+        self.modules
+            .iter()
+            .find(|(_, m)| is_top(m))
+            .or_else(|| {
+                self.modules
+                    .iter()
+                    .next()
+                    .filter(|_| self.modules.len() == 1)
+            })
+            .map(|(name, m)| (name.as_str(), m))
+    }
+}
+
+/// Netlists read with the `yosys-netlist-json` crate, which the editor
+/// uses to load `write_json` output.
+#[cfg(feature = "yosys-netlist-json")]
+mod netlist_json {
+    use yosys_netlist_json as json;
+
+    use super::{Bit, Cell, Module, NetName, Netlist, Port, PortDirection};
+
+    impl From<json::Netlist> for Netlist {
+        fn from(netlist: json::Netlist) -> Self {
+            Netlist {
+                modules: netlist
+                    .modules
+                    .into_iter()
+                    .map(|(name, module)| (name, module.into()))
+                    .collect(),
+            }
+        }
+    }
+
+    impl From<json::Module> for Module {
+        fn from(module: json::Module) -> Self {
+            Module {
+                attributes: module
+                    .attributes
+                    .into_iter()
+                    .map(|(name, value)| {
+                        let value = match value {
+                            json::AttributeVal::N(n) => serde_json::Value::from(n),
+                            json::AttributeVal::S(s) => serde_json::Value::String(s),
+                        };
+                        (name, value)
+                    })
+                    .collect(),
+                ports: module
+                    .ports
+                    .into_iter()
+                    .map(|(name, port)| {
+                        let port = Port {
+                            direction: port.direction.into(),
+                            bits: bits(port.bits),
+                        };
+                        (name, port)
+                    })
+                    .collect(),
+                cells: module
+                    .cells
+                    .into_iter()
+                    .map(|(name, cell)| {
+                        let cell = Cell {
+                            cell_type: cell.cell_type,
+                            port_directions: cell
+                                .port_directions
+                                .into_iter()
+                                .map(|(port, direction)| (port, direction.into()))
+                                .collect(),
+                            connections: cell
+                                .connections
+                                .into_iter()
+                                .map(|(port, connected)| (port, bits(connected)))
+                                .collect(),
+                        };
+                        (name, cell)
+                    })
+                    .collect(),
+                netnames: module
+                    .netnames
+                    .into_iter()
+                    .map(|(name, net)| {
+                        let net = NetName {
+                            bits: bits(net.bits),
+                            hide_name: u8::from(net.hide_name != 0),
+                        };
+                        (name, net)
+                    })
+                    .collect(),
+            }
+        }
+    }
+
+    impl From<json::PortDirection> for PortDirection {
+        fn from(direction: json::PortDirection) -> Self {
+            match direction {
+                json::PortDirection::Input => PortDirection::Input,
+                json::PortDirection::Output => PortDirection::Output,
+                json::PortDirection::InOut => PortDirection::Inout,
+            }
+        }
+    }
+
+    impl From<json::BitVal> for Bit {
+        fn from(bit: json::BitVal) -> Self {
+            let constant = match bit {
+                json::BitVal::N(net) => return Bit::Net(net as u64),
+                json::BitVal::S(json::SpecialBit::_0) => "0",
+                json::BitVal::S(json::SpecialBit::_1) => "1",
+                json::BitVal::S(json::SpecialBit::X) => "x",
+                json::BitVal::S(json::SpecialBit::Z) => "z",
+            };
+            Bit::Constant(constant.to_string())
+        }
+    }
+
+    fn bits(bits: Vec<json::BitVal>) -> Vec<Bit> {
+        bits.into_iter().map(Bit::from).collect()
+    }
+}
+
+/// A component to create, with the nodes its pins connect to.
+#[derive(Debug, Clone)]
+pub(crate) struct Prim {
+    pub(crate) kind: Kind,
+    pub(crate) label: String,
+    pub(crate) pins: Vec<(&'static str, usize)>,
+}
+
+/// The components of a module and the nets joining them, before placement.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Design {
+    pub(crate) prims: Vec<Prim>,
+    /// Net names to set as wire comments, by node.
+    pub(crate) names: HashMap<usize, String>,
+    nodes: usize,
+    bits: HashMap<Bit, usize>,
+    /// `$_BUF_` outputs, mapped to the node driving them.
+    aliases: HashMap<usize, usize>,
+}
+
+impl Design {
+    fn node(&mut self) -> usize {
+        self.nodes += 1;
+        self.nodes - 1
+    }
+
+    fn bit(&mut self, bit: &Bit) -> usize {
+        let bit = match bit {
+            Bit::Constant(c) if c != "1" => Bit::Constant("0".into()),
+            bit => bit.clone(),
+        };
+        if let Some(&node) = self.bits.get(&bit) {
+            return node;
+        }
+        let node = self.node();
+        self.bits.insert(bit.clone(), node);
+        if let Bit::Constant(c) = bit {
+            let kind = if c == "1" { Kind::On } else { Kind::Off };
+            self.add(kind, "", &[], Some(node));
+        }
+        node
+    }
+
+    fn add(&mut self, kind: Kind, label: &str, inputs: &[usize], output: Option<usize>) {
+        let mut pins: Vec<_> = kind
+            .pins()
+            .iter()
+            .filter(|p| p.direction == crate::catalog::PinDirection::Input)
+            .map(|p| p.name)
+            .zip(inputs.iter().copied())
+            .collect();
+        if let Some(output) = output {
+            pins.push(("out", output));
+        }
+        self.prims.push(Prim {
+            kind,
+            label: label.to_string(),
+            pins,
+        });
+    }
+
+    fn gate(&mut self, kind: Kind, label: &str, inputs: &[usize]) -> usize {
+        let out = self.node();
+        self.add(kind, label, inputs, Some(out));
+        out
+    }
+
+    /// `select ? b : a`, from NOT, AND and OR gates, or NOR for an inverted
+    /// output.
+    fn mux(&mut self, label: &str, a: usize, b: usize, select: usize, out: usize, invert: bool) {
+        let not_select = self.gate(Kind::NotBit, label, &[select]);
+        let pick_a = self.gate(Kind::AndBit, label, &[a, not_select]);
+        let pick_b = self.gate(Kind::AndBit, label, &[b, select]);
+        let or = if invert { Kind::NorBit } else { Kind::OrBit };
+        self.add(or, label, &[pick_a, pick_b], Some(out));
+    }
+
+    fn cell(&mut self, name: &str, cell: &Cell) -> Result<(), Error> {
+        let port = |design: &mut Self, pin: &str| match cell.connections.get(pin) {
+            Some(bits) if bits.len() == 1 => Ok(design.bit(&bits[0])),
+            _ => Err(Error::InvalidNetlist(format!(
+                "cell {} has no single-bit {} connection",
+                name, pin
+            ))),
+        };
+        let simple = match cell.cell_type.as_str() {
+            "$_NOT_" => Some((Kind::NotBit, 1)),
+            "$_AND_" => Some((Kind::AndBit, 2)),
+            "$_NAND_" => Some((Kind::NandBit, 2)),
+            "$_OR_" => Some((Kind::OrBit, 2)),
+            "$_NOR_" => Some((Kind::NorBit, 2)),
+            "$_XOR_" => Some((Kind::XorBit, 2)),
+            "$_XNOR_" => Some((Kind::XnorBit, 2)),
+            _ => None,
+        };
+        if let Some((kind, arity)) = simple {
+            let inputs = ["A", "B"][..arity]
+                .iter()
+                .map(|pin| port(self, pin))
+                .collect::<Result<Vec<_>, _>>()?;
+            let y = port(self, "Y")?;
+            self.add(kind, name, &inputs, Some(y));
+            return Ok(());
+        }
+        match cell.cell_type.as_str() {
+            "$_BUF_" => {
+                let (a, y) = (port(self, "A")?, port(self, "Y")?);
+                self.aliases.insert(y, a);
+            }
+            "$_ANDNOT_" | "$_ORNOT_" => {
+                let (a, b, y) = (port(self, "A")?, port(self, "B")?, port(self, "Y")?);
+                let not_b = self.gate(Kind::NotBit, name, &[b]);
+                let kind = if cell.cell_type == "$_ANDNOT_" {
+                    Kind::AndBit
+                } else {
+                    Kind::OrBit
+                };
+                self.add(kind, name, &[a, not_b], Some(y));
+            }
+            "$_AOI3_" | "$_OAI3_" => {
+                let (a, b, c) = (port(self, "A")?, port(self, "B")?, port(self, "C")?);
+                let y = port(self, "Y")?;
+                let (inner, outer) = if cell.cell_type == "$_AOI3_" {
+                    (Kind::AndBit, Kind::NorBit)
+                } else {
+                    (Kind::OrBit, Kind::NandBit)
+                };
+                let ab = self.gate(inner, name, &[a, b]);
+                self.add(outer, name, &[ab, c], Some(y));
+            }
+            "$_MUX_" | "$_NMUX_" => {
+                let (a, b, s) = (port(self, "A")?, port(self, "B")?, port(self, "S")?);
+                let y = port(self, "Y")?;
+                self.mux(name, a, b, s, y, cell.cell_type == "$_NMUX_");
+            }
+            "$_DFF_P_" | "$_DFF_N_" => {
+                let (d, q) = (port(self, "D")?, port(self, "Q")?);
+                self.add(Kind::DelayLineBit, name, &[d], Some(q));
+            }
+            "$_DFFE_PP_" | "$_DFFE_NP_" | "$_DFFE_PN_" | "$_DFFE_NN_" => {
+                let (d, e, q) = (port(self, "D")?, port(self, "E")?, port(self, "Q")?);
+                let next = self.node();
+                if cell.cell_type.ends_with("P_") {
+                    self.mux(name, q, d, e, next, false);
+                } else {
+                    self.mux(name, d, q, e, next, false);
+                }
+                self.add(Kind::DelayLineBit, name, &[next], Some(q));
+            }
+            other => {
+                return Err(Error::UnsupportedCell {
+                    cell: name.to_string(),
+                    cell_type: other.to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn resolve(&self, mut node: usize) -> usize {
+        while let Some(&next) = self.aliases.get(&node) {
+            node = next;
+        }
+        node
+    }
+
+    pub(crate) fn new(module: &Module) -> Result<Self, Error> {
+        let mut design = Design::default();
+        let mut outputs = Vec::new();
+        for (name, port) in &module.ports {
+            for (i, bit) in port.bits.iter().enumerate() {
+                let label = if port.bits.len() == 1 {
+                    name.clone()
+                } else {
+                    format!("{}[{}]", name, i)
+                };
+                match port.direction {
+                    PortDirection::Input => {
+                        let node = design.node();
+                        design.bits.insert(bit.clone(), node);
+                        design.add(Kind::CcInput, &label, &[], Some(node));
+                    }
+                    PortDirection::Output => outputs.push((label, bit)),
+                    PortDirection::Inout => {
+                        return Err(Error::InvalidNetlist(format!(
+                            "inout port {} is not supported",
+                            name
+                        )));
+                    }
+                }
+            }
+        }
+        for (name, cell) in &module.cells {
+            design.cell(name, cell)?;
+        }
+        for (label, bit) in outputs {
+            let node = design.bit(bit);
+            design.add(Kind::CcOutput, &label, &[node], None);
+        }
+
+        for (name, net) in module.netnames.iter().filter(|(_, n)| n.hide_name == 0) {
+            for (i, bit) in net.bits.iter().enumerate() {
+                let Some(&node) = design.bits.get(bit) else {
+                    continue;
+                };
+                let name = if net.bits.len() == 1 {
+                    name.clone()
+                } else {
+                    format!("{}[{}]", name, i)
+                };
+                design.names.entry(design.resolve(node)).or_insert(name);
+            }
+        }
+        let resolved: Vec<_> = (0..design.nodes).map(|n| design.resolve(n)).collect();
+        for prim in &mut design.prims {
+            for (_, node) in &mut prim.pins {
+                *node = resolved[*node];
+            }
+        }
+        Ok(design)
+    }
+
+    fn is_output(kind: Kind, pin: &str) -> bool {
+        kind.pin(pin)
+            .is_some_and(|p| p.direction == crate::catalog::PinDirection::Output)
+    }
+
+    /// The prim driving each node.
+    fn drivers(&self) -> HashMap<usize, usize> {
+        let mut drivers = HashMap::new();
+        for (i, prim) in self.prims.iter().enumerate() {
+            for &(pin, node) in &prim.pins {
+                if Self::is_output(prim.kind, pin) {
+                    drivers.insert(node, i);
+                }
+            }
+        }
+        drivers
+    }
+
+    /// Column of every prim: sources and delay lines first, then logic by
+    /// depth, then outputs.
+    fn columns(&self) -> Vec<usize> {
+        fn depth(
+            design: &Design,
+            drivers: &HashMap<usize, usize>,
+            memo: &mut Vec<Option<usize>>,
+            visiting: &mut HashSet<usize>,
+            i: usize,
+        ) -> usize {
+            if let Some(depth) = memo[i] {
+                return depth;
+            }
+            let prim = &design.prims[i];
+            if prim.kind == Kind::DelayLineBit || !visiting.insert(i) {
+                return 0;
+            }
+            let inputs = prim
+                .pins
+                .iter()
+                .filter(|(pin, _)| !Design::is_output(prim.kind, pin));
+            let mut deepest = None;
+            for &(_, node) in inputs {
+                if let Some(&driver) = drivers.get(&node) {
+                    let d = depth(design, drivers, memo, visiting, driver);
+                    deepest = Some(deepest.map_or(d, |m: usize| m.max(d)));
+                }
+            }
+            visiting.remove(&i);
+            let depth = deepest.map_or(0, |d| d + 1);
+            memo[i] = Some(depth);
+            depth
+        }
+
+        let drivers = self.drivers();
+        let mut memo = vec![None; self.prims.len()];
+        let mut columns: Vec<_> = (0..self.prims.len())
+            .map(|i| {
+                if self.prims[i].kind == Kind::CcOutput {
+                    0
+                } else {
+                    depth(self, &drivers, &mut memo, &mut HashSet::new(), i)
+                }
+            })
+            .collect();
+        let last = columns.iter().max().map_or(0, |m| m + 1);
+        for (column, prim) in columns.iter_mut().zip(&self.prims) {
+            if prim.kind == Kind::CcOutput {
+                *column = last;
+            }
+        }
+        columns
+    }
+
+    /// Positions for every prim, in columns wide enough for the wire lanes
+    /// in front of them.
+    pub(crate) fn place(&self) -> Vec<Point> {
+        let columns = self.columns();
+        let count = columns.iter().max().map_or(0, |m| m + 1);
+        let mut feeding = vec![HashSet::new(); count];
+        for (prim, &column) in self.prims.iter().zip(&columns) {
+            for &(pin, node) in &prim.pins {
+                if !Self::is_output(prim.kind, pin) {
+                    feeding[column].insert(node);
+                }
+            }
+        }
+        let mut x = vec![0i16; count];
+        for c in 1..count {
+            x[c] = x[c - 1] + 3 + feeding[c].len().max(1) as i16;
+        }
+        let mut y = vec![0i16; count];
+        self.prims
+            .iter()
+            .zip(&columns)
+            .map(|(prim, &column)| {
+                let footprint = prim.kind.footprint();
+                let at = Point::new(x[column], y[column] - footprint.origin.y);
+                y[column] += footprint.height as i16 + 1;
+                at
+            })
+            .collect()
+    }
+
+    /// Wires for the connections between `components`, which correspond to
+    /// the prims, skipping those `connected(driver, sink, pin)` reports as
+    /// already wired.
+    pub(crate) fn route(
+        &self,
+        components: &[v9::Component],
+        connected: impl Fn(usize, usize, &str) -> bool,
+    ) -> Result<Vec<Wire>, Error> {
+        let pin_at = |i: usize, name: &str| {
+            let component = &components[i];
+            let offset = component.kind.pin(name).map_or_else(Point::default, |p| {
+                Rotation::from(component.rotation).apply(p.offset)
+            });
+            Point::new(
+                component.position.x.wrapping_add(offset.x),
+                component.position.y.wrapping_add(offset.y),
+            )
+        };
+        let drivers = self.drivers();
+
+        let mut lanes: HashMap<(i16, usize), i16> = HashMap::new();
+        let mut wires = Vec::new();
+        for (i, prim) in self.prims.iter().enumerate() {
+            for &(pin, node) in &prim.pins {
+                if Self::is_output(prim.kind, pin) {
+                    continue;
+                }
+                let Some(&driver) = drivers.get(&node) else {
+                    continue;
+                };
+                if connected(driver, i, pin) {
+                    continue;
+                }
+                let from = pin_at(driver, "out");
+                let to = pin_at(i, pin);
+                let next = lanes.keys().filter(|(x, _)| *x == to.x).count() as i16;
+                let lane = to.x - 1 - *lanes.entry((to.x, node)).or_insert(next);
+                let points = [from, Point::new(lane, from.y), Point::new(lane, to.y), to];
+                let mut wire = Wire::from_polyline(&points)?;
+                if let Some(name) = self.names.get(&node) {
+                    wire.comment = name.as_str().into();
+                }
+                wires.push(wire);
+            }
+        }
+        Ok(wires)
+    }
+}
+
+/// Builds a placed and wired circuit from `module`, with a fresh
+/// `custom_id` so it can be saved as a new custom component.
+pub fn import(module: &Module) -> Result<v9::CircuitData, Error> {
+    let design = Design::new(module)?;
+    let components: Vec<_> = design
+        .prims
+        .iter()
+        .zip(design.place())
+        .map(|(prim, position)| component(prim, position))
+        .collect();
+    let wires = design.route(&components, |_, _, _| false)?;
+    Ok(v9::CircuitData {
+        custom_id: new_permament_id(),
+        components,
+        wires,
+        ..Default::default()
+    })
+}
+
+fn component(prim: &Prim, position: Point) -> v9::Component {
+    v9::Component {
+        kind: prim.kind,
+        position,
+        permanent_id: new_permament_id(),
+        custom_string: prim.label.as_str().into(),
+        word_size: match prim.kind {
+            Kind::CcInput | Kind::CcOutput => 1,
+            _ => 0,
+        },
+        ..Default::default()
+    }
+}
+
+/// Whether the importer creates components of this kind. Components of
+/// other kinds are left alone when re-importing.
+fn is_generated(kind: Kind) -> bool {
+    matches!(
+        kind,
+        Kind::CcInput
+            | Kind::CcOutput
+            | Kind::On
+            | Kind::Off
+            | Kind::NotBit
+            | Kind::AndBit
+            | Kind::NandBit
+            | Kind::OrBit
+            | Kind::NorBit
+            | Kind::XorBit
+            | Kind::XnorBit
+            | Kind::DelayLineBit
+    )
+}
+
+/// Identifies a generated component across imports: its kind and label, and
+/// how many components before it share both.
+fn match_keys<'a>(items: impl Iterator<Item = (Kind, &'a str)>) -> Vec<(Kind, &'a str, usize)> {
+    let mut seen: HashMap<(Kind, &str), usize> = HashMap::new();
+    items
+        .map(|(kind, label)| {
+            let count = seen.entry((kind, label)).or_default();
+            *count += 1;
+            (kind, label, *count - 1)
+        })
+        .collect()
+}
+
+/// What [`reimport`] changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReimportReport {
+    /// Permanent ids of the components placed for new cells and ports.
+    pub added: Vec<u64>,
+    /// Permanent ids of the components whose cells and ports are gone.
+    pub removed: Vec<u64>,
+    /// Wires kept because they only join pins that should be joined.
+    pub kept_wires: usize,
+    /// Wires dropped because they join pins that no longer belong together.
+    pub removed_wires: usize,
+    /// Wires routed for connections the kept wires don't make.
+    pub added_wires: usize,
+}
+
+/// Updates a circuit made by [`import`] from a new version of its module.
+///
+/// Generated components are matched to cells and ports by kind and label.
+/// Matched components keep their position, rotation, `permanent_id` and
+/// other settings; new ones are placed below the existing layout, and
+/// unmatched generated ones are deleted. Components of kinds the importer
+/// never creates, such as probes, are kept as they are.
+///
+/// Wires are kept when everything they join should still be joined. The
+/// rest are removed, and the connections left unmade are routed anew.
+pub fn reimport(
+    existing: &v9::CircuitData,
+    module: &Module,
+) -> Result<(v9::CircuitData, ReimportReport), Error> {
+    let design = Design::new(module)?;
+    let mut report = ReimportReport::default();
+
+    let generated: Vec<_> = existing
+        .components
+        .iter()
+        .filter(|c| is_generated(c.kind))
+        .collect();
+    let labels: Vec<_> = generated
+        .iter()
+        .map(|c| c.custom_string.to_string_lossy())
+        .collect();
+    let mut old: HashMap<_, _> = match_keys(
+        generated
+            .iter()
+            .zip(&labels)
+            .map(|(c, label)| (c.kind, label.as_ref())),
+    )
+    .into_iter()
+    .zip(generated.iter().copied())
+    .collect();
+    let keys = match_keys(design.prims.iter().map(|p| (p.kind, p.label.as_str())));
+    let matched: Vec<_> = keys.iter().map(|key| old.remove(key)).collect();
+    let mut removed: Vec<_> = old.into_values().map(|c| c.permanent_id).collect();
+    removed.sort();
+    report.removed = removed;
+
+    // New components keep their fresh layout, moved below everything
+    // already there.
+    let fresh = design.place();
+    let bottom = existing
+        .components
+        .iter()
+        .map(|c| {
+            c.position
+                .y
+                .saturating_add(c.kind.footprint().height as i16)
+        })
+        .chain(existing.wires.iter().flat_map(|w| w.cells()).map(|p| p.y))
+        .max();
+    let top = fresh
+        .iter()
+        .zip(&design.prims)
+        .zip(&matched)
+        .filter(|(_, m)| m.is_none())
+        .map(|((at, prim), _)| at.y + prim.kind.footprint().origin.y)
+        .min();
+    let shift = match (bottom, top) {
+        (Some(bottom), Some(top)) if matched.iter().any(Option::is_some) => bottom + 2 - top,
+        _ => 0,
+    };
+
+    let mut components = Vec::new();
+    for ((prim, at), matched) in design.prims.iter().zip(&fresh).zip(&matched) {
+        match *matched {
+            Some(old) => components.push(old.clone()),
+            None => {
+                let component = component(prim, Point::new(at.x, at.y + shift));
+                report.added.push(component.permanent_id);
+                components.push(component);
+            }
+        }
+    }
+    components.extend(
+        existing
+            .components
+            .iter()
+            .filter(|c| !is_generated(c.kind))
+            .cloned(),
+    );
+
+    // Keep the wire groups that only reach pins of one node, and that haven't
+    // been left dangling by deleted components.
+    let mut circuit = v9::CircuitData {
+        components,
+        wires: existing.wires.clone(),
+        ..existing.clone()
+    };
+    let netlist = crate::netlist::NetlistBuilder::new(&circuit).build();
+    let node_of = |pin: crate::netlist::PinRef| {
+        let prim = design.prims.get(pin.component)?;
+        let name = &netlist.pin(pin).name;
+        prim.pins
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|&(_, node)| node)
+    };
+    let was_floating: HashSet<_> = crate::netlist::NetlistBuilder::new(existing)
+        .build()
+        .floating_wire_ends
+        .into_iter()
+        .collect();
+    let dangling: HashSet<_> = netlist
+        .floating_wire_ends
+        .iter()
+        .filter(|end| !was_floating.contains(end))
+        .map(|end| end.wire)
+        .collect();
+    let mut keep = vec![true; circuit.wires.len()];
+    let mut net_node = vec![None; netlist.nets.len()];
+    for (n, net) in netlist.nets.iter().enumerate() {
+        let mut nodes: Vec<_> = net.pins.iter().filter_map(|&p| node_of(p)).collect();
+        nodes.sort();
+        nodes.dedup();
+        if nodes.len() > 1 || net.wires.iter().any(|w| dangling.contains(w)) {
+            for &wire in &net.wires {
+                keep[wire] = false;
+            }
+        } else {
+            net_node[n] = nodes.first().copied();
+        }
+    }
+    let mut kept = keep.iter();
+    circuit.wires.retain(|_| *kept.next().unwrap());
+    report.kept_wires = circuit.wires.len();
+    report.removed_wires = keep.len() - circuit.wires.len();
+
+    let connected = |driver: usize, sink: usize, pin: &str| {
+        let net = |component: usize, name: &str| {
+            let net = netlist.net_of(netlist.pin_ref(component, name)?)?;
+            net_node[net].is_some().then_some(net)
+        };
+        matches!((net(driver, "out"), net(sink, pin)), (Some(a), Some(b)) if a == b)
+    };
+    let wires = design.route(&circuit.components, connected)?;
+    report.added_wires = wires.len();
+    circuit.wires.extend(wires);
+    Ok((circuit, report))
+}
+
+/// Saves `circuit` as `circuit.data` in `dir`, such as
+/// `schematics/component_factory/<name>`, creating the directory if needed.
+pub fn save_component(circuit: &v9::CircuitData, dir: impl AsRef<Path>) -> Result<PathBuf, Error> {
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.as_ref().join("circuit.data");
+    let file = CircuitDataFile {
+        version: 9,
+        circuit: CircuitDataVersion::V9(circuit.clone()),
+    };
+    file.save(&path)?;
+    Ok(path)
+}
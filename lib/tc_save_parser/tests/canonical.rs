@@ -0,0 +1,105 @@
+use tc_save_parser::{
+    CDString, CircuitDataFile, CircuitDataVersion, Kind, Point, Wire, WireBuilder, WireDirection,
+    WireSegment, v9,
+};
+
+fn segment(direction: WireDirection, length: u8) -> WireSegment {
+    WireSegment::new()
+        .with_direction(direction)
+        .with_length(length)
+}
+
+fn directions(wire: &Wire) -> Vec<(WireDirection, u8)> {
+    wire.segments
+        .iter()
+        .map(|s| (s.direction(), s.length()))
+        .collect()
+}
+
+fn component(kind: Kind, id: u64, x: i16) -> v9::Component {
+    v9::Component {
+        kind,
+        permanent_id: id,
+        position: Point::new(x, 0),
+        ..Default::default()
+    }
+}
+
+fn bytes(circuit: &v9::CircuitData) -> Vec<u8> {
+    CircuitDataFile {
+        version: 9,
+        circuit: CircuitDataVersion::V9(circuit.clone()),
+    }
+    .to_bytes()
+    .unwrap()
+}
+
+#[test]
+fn wires_start_at_their_lesser_end_with_merged_segments() {
+    let mut wire = Wire {
+        color: 2,
+        comment: CDString::from("kept"),
+        start: Point::new(40, 3),
+        segments: vec![
+            segment(WireDirection::Left, 20),
+            segment(WireDirection::Up, 0),
+            segment(WireDirection::Left, 20),
+            segment(WireDirection::UpLeft, 2),
+            WireSegment::new(),
+        ],
+    };
+    let cells = wire.cells();
+    wire.canonicalize();
+    assert_eq!(wire.start, Point::new(-2, 1));
+    assert_eq!(
+        directions(&wire),
+        [
+            (WireDirection::DownRight, 2),
+            (WireDirection::Right, 31),
+            (WireDirection::Right, 9),
+            (WireDirection::Right, 0),
+        ]
+    );
+    let mut reversed = wire.cells();
+    reversed.reverse();
+    assert_eq!(reversed, cells);
+    assert_eq!((wire.color, wire.comment.as_str()), (2, Some("kept")));
+}
+
+#[test]
+fn shuffled_circuits_save_identically() {
+    let wires = [
+        WireBuilder::new(Point::new(0, 0)).right(3).down(2).build(),
+        WireBuilder::new(Point::new(5, 5)).up(4).build(),
+        WireBuilder::new(Point::new(9, 1)).left(2).left(2).build(),
+    ];
+    let mut a = v9::CircuitData {
+        components: vec![
+            component(Kind::AndBit, 7, 0),
+            component(Kind::OrBit, 3, 10),
+            component(Kind::NotBit, 3, -4),
+        ],
+        wires: wires.to_vec(),
+        ..Default::default()
+    };
+    let mut b = a.clone();
+    b.components.reverse();
+    b.wires.reverse();
+    b.wires[2] = WireBuilder::new(Point::new(3, 2)).up(2).left(3).build();
+    b.wires[0] = WireBuilder::new(Point::new(5, 1)).right(4).build();
+
+    a.canonicalize();
+    b.canonicalize();
+    assert_eq!(bytes(&a), bytes(&b));
+    let kinds: Vec<_> = a.components.iter().map(|c| c.kind).collect();
+    assert_eq!(kinds, [Kind::NotBit, Kind::OrBit, Kind::AndBit]);
+    let starts: Vec<_> = a.wires.iter().map(|w| w.start).collect();
+    assert_eq!(
+        starts,
+        [Point::new(0, 0), Point::new(5, 1), Point::new(5, 1)]
+    );
+
+    let once = bytes(&a);
+    a.canonicalize();
+    assert_eq!(bytes(&a), once);
+}
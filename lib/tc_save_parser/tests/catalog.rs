@@ -0,0 +1,104 @@
+use std::collections::HashSet;
+
+use tc_save_parser::{
+    Kind, Point,
+    catalog::{Category, PinDirection, Width},
+};
+
+fn known_kinds() -> impl Iterator<Item = Kind> {
+    (0..=u16::MAX)
+        .map(Kind::from)
+        .filter(|k| !matches!(k, Kind::Unmapped(_)))
+}
+
+#[test]
+fn pins_lie_inside_the_footprint_and_have_unique_names() {
+    for kind in known_kinds() {
+        let footprint = kind.footprint();
+        let mut names = HashSet::new();
+        for pin in kind.pins() {
+            assert!(footprint.contains(pin.offset), "{:?}.{}", kind, pin.name);
+            assert!(names.insert(pin.name), "{:?}.{}", kind, pin.name);
+        }
+    }
+}
+
+#[test]
+fn gates_have_the_expected_pins() {
+    let and = Kind::AndBit;
+    assert_eq!(and.name(), "AND");
+    assert_eq!(and.category(), Category::Logic);
+    let names: Vec<_> = and.pins().iter().map(|p| p.name).collect();
+    assert_eq!(names, ["a", "b", "out"]);
+    let out = and.pin("out").unwrap();
+    assert_eq!(out.direction, PinDirection::Output);
+    assert_eq!(out.offset, Point::new(1, 0));
+}
+
+#[test]
+fn widths_follow_word_size() {
+    let add = Kind::Add;
+    assert_eq!(add.pin("a").unwrap().width.bits(16), 16);
+    assert_eq!(add.pin("carry_out").unwrap().width.bits(16), 1);
+    assert_eq!(Width::WordDiv(4).bits(32), 8);
+    assert_eq!(Width::WordMul(2).bits(8), 16);
+}
+
+#[test]
+fn unset_word_sizes_resolve_to_the_widest_word() {
+    // A default component has a word size of 0. Treating it as 64 bits keeps
+    // every bit a word could hold, where any narrower guess would truncate.
+    assert_eq!(tc_save_parser::v9::Component::default().word_size, 0);
+    assert_eq!(Width::Word.bits(0), 64);
+    assert_eq!(Width::Word.bits(-8), 64);
+    assert_eq!(Width::Word.bits(65), 64);
+    assert_eq!(Width::WordDiv(8).bits(0), 8);
+    assert_eq!(Width::Bits(1).bits(0), 1);
+}
+
+#[test]
+fn kinds_without_mapped_pins_are_listed() {
+    let pinless: Vec<_> = known_kinds()
+        .filter(|k| k.pins().is_empty())
+        .map(|k| k.name())
+        .collect();
+    assert_eq!(
+        pinless,
+        [
+            "None",
+            "Front panel",
+            "Assembler",
+            "Level gate",
+            "Custom component",
+            "Bit memory probe",
+            "Word memory probe",
+            "Console",
+            "Pixel screen",
+            "Immediate bit memory probe",
+            "Immediate word memory probe",
+        ]
+    );
+}
+
+#[test]
+fn unmapped_kinds_have_no_pins() {
+    let kind = Kind::from(4000);
+    assert_eq!(kind.name(), "Unknown");
+    assert!(kind.pins().is_empty());
+}
+
+#[test]
+fn memory_kinds_carry_version_7_memory_info() {
+    let memories: Vec<_> = known_kinds().filter(Kind::is_memory).collect();
+    assert_eq!(
+        memories,
+        [
+            Kind::Assembler,
+            Kind::Ram,
+            Kind::RamLatency,
+            Kind::RamFast,
+            Kind::RamDualLoad,
+            Kind::Rom,
+        ]
+    );
+}
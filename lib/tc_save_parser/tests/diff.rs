@@ -0,0 +1,197 @@
+use tc_save_parser::{
+    CircuitDataFile, CircuitDataVersion, Kind, Point, Wire,
+    diff::{self, Change, PinId},
+    v9,
+};
+
+fn component(kind: Kind, id: u64, x: i16, y: i16) -> v9::Component {
+    v9::Component {
+        kind,
+        permanent_id: id,
+        position: Point::new(x, y),
+        ..Default::default()
+    }
+}
+
+fn wire(points: &[(i16, i16)]) -> Wire {
+    let points: Vec<_> = points.iter().map(|&(x, y)| Point::new(x, y)).collect();
+    Wire::from_polyline(&points).unwrap()
+}
+
+#[test]
+fn reports_component_changes_by_permanent_id() {
+    let old = v9::CircuitData {
+        components: vec![
+            component(Kind::AndBit, 1, 0, 0),
+            component(Kind::NotBit, 2, 0, 3),
+            component(Kind::OrBit, 3, 0, 6),
+            component(Kind::AndBit, 5, 0, 9),
+        ],
+        ..Default::default()
+    };
+    let mut new = v9::CircuitData {
+        components: vec![
+            component(Kind::OrBit, 3, 0, 6),
+            component(Kind::AndBit, 1, 2, 0),
+            component(Kind::NotBit, 4, 4, 4),
+            component(Kind::NotBit, 5, 0, 9),
+        ],
+        ..Default::default()
+    };
+    new.components[0].settings = vec![1];
+    new.components[0].word_size = 8;
+    new.components[1].rotation = 1;
+    new.components[1].custom_string = "x".into();
+
+    let diff = diff::diff(&old, &new);
+    assert_eq!(
+        diff.to_string(),
+        "- #2 NOT at (0, 3)\n\
+         - #5 AND at (0, 9)\n\
+         + #4 NOT at (4, 4)\n\
+         + #5 NOT at (0, 9)\n\
+         ~ #3 OR settings [] -> [1]\n\
+         ~ #3 OR word_size 0 -> 8\n\
+         ~ #1 AND moved from (0, 0) to (2, 0)\n\
+         ~ #1 AND rotated from 0° to 90°\n\
+         ~ #1 AND custom_string \"\" -> \"x\"\n"
+    );
+    assert!(diff::diff(&new, &new).is_empty());
+}
+
+#[test]
+fn rewiring_comes_from_the_netlist() {
+    let components = vec![
+        component(Kind::NotBit, 1, 0, 0),
+        component(Kind::NotBit, 2, 0, 5),
+        component(Kind::NotBit, 3, 10, 0),
+    ];
+    let old = v9::CircuitData {
+        components: components.clone(),
+        wires: vec![wire(&[(1, 0), (9, 0)])],
+        ..Default::default()
+    };
+    let rerouted = v9::CircuitData {
+        components: components.clone(),
+        wires: vec![wire(&[(1, 0), (1, -3), (9, -3), (9, 0)])],
+        ..Default::default()
+    };
+    assert!(diff::diff(&old, &rerouted).is_empty());
+
+    let rewired = v9::CircuitData {
+        components,
+        wires: vec![wire(&[(1, 5), (5, 5), (5, 0), (9, 0)])],
+        ..Default::default()
+    };
+    let pin = |component, pin: &str| PinId {
+        component,
+        pin: pin.to_string(),
+    };
+    let diff = diff::diff(&old, &rewired);
+    assert_eq!(
+        diff.changes,
+        [
+            Change::Left {
+                pin: pin(1, "out"),
+                net: pin(3, "a"),
+            },
+            Change::Joined {
+                pin: pin(2, "out"),
+                net: pin(3, "a"),
+            },
+        ]
+    );
+    assert_eq!(
+        diff.to_string(),
+        "- #1.out left the net of #3.a\n+ #2.out joined the net of #3.a\n"
+    );
+}
+
+#[test]
+fn a_pin_leaving_a_net_is_one_change() {
+    let components = vec![
+        component(Kind::NotBit, 1, 0, 0),
+        component(Kind::NotBit, 3, 10, 0),
+        component(Kind::NotBit, 4, 10, 5),
+        component(Kind::NotBit, 5, 10, 10),
+    ];
+    let fan_out = vec![wire(&[(9, 0), (9, 5)]), wire(&[(9, 5), (9, 10)])];
+    let old = v9::CircuitData {
+        components: components.clone(),
+        wires: [vec![wire(&[(1, 0), (9, 0)])], fan_out.clone()].concat(),
+        ..Default::default()
+    };
+    let new = v9::CircuitData {
+        components,
+        wires: fan_out,
+        ..Default::default()
+    };
+    assert_eq!(
+        diff::diff(&old, &new).to_string(),
+        "- #1.out left the net of #3.a\n"
+    );
+    assert_eq!(
+        diff::diff(&new, &old).to_string(),
+        "+ #1.out joined the net of #3.a\n"
+    );
+}
+
+#[test]
+fn saves_of_any_version_compare_as_the_latest() {
+    let old = v9::CircuitData {
+        components: vec![component(Kind::NotBit, 1, 0, 0)],
+        ..Default::default()
+    };
+    let new = v9::CircuitData {
+        components: vec![component(Kind::NotBit, 1, 2, 0)],
+        ..Default::default()
+    };
+    let file = |circuit: &v9::CircuitData| CircuitDataFile {
+        version: 9,
+        circuit: CircuitDataVersion::V9(circuit.clone()),
+    };
+    let (old_v7, _) = file(&old).convert_to(7).unwrap();
+    let expected = diff::diff(&old, &new);
+    assert!(!expected.is_empty());
+    assert_eq!(
+        diff::diff_saves(old_v7.clone(), file(&new)).unwrap(),
+        expected
+    );
+
+    let dir = std::env::temp_dir();
+    let old_path = dir.join(format!("tc_diff_old_{}.data", std::process::id()));
+    let new_path = dir.join(format!("tc_diff_new_{}.data", std::process::id()));
+    old_v7.save(&old_path).unwrap();
+    file(&new).save(&new_path).unwrap();
+    let from_files = diff::diff_files(&old_path, &new_path);
+    std::fs::remove_file(old_path).unwrap();
+    std::fs::remove_file(new_path).unwrap();
+    assert_eq!(from_files.unwrap(), expected);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serializes_as_json() {
+    let old = v9::CircuitData::default();
+    let new = v9::CircuitData {
+        components: vec![component(Kind::NotBit, 7, 1, -2)],
+        ..Default::default()
+    };
+    let json = diff::diff(&old, &new).to_json();
+    assert_eq!(
+        json,
+        r#"{
+  "changes": [
+    {
+      "change": "added",
+      "component": 7,
+      "kind": "NotBit",
+      "position": {
+        "x": 1,
+        "y": -2
+      }
+    }
+  ]
+}"#
+    );
+}
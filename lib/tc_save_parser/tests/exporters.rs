@@ -0,0 +1,174 @@
+use std::collections::{BTreeSet, HashMap};
+
+use tc_save_parser::{
+    Error, Kind, Point, Wire,
+    catalog::PinDirection,
+    sim::Simulator,
+    v9, verilog,
+    yosys::{self, Module},
+};
+
+fn known_kinds() -> impl Iterator<Item = Kind> {
+    (0..=u16::MAX)
+        .map(Kind::from)
+        .filter(|k| !matches!(k, Kind::Unmapped(_) | Kind::Custom))
+}
+
+fn component(kind: Kind, x: i16, y: i16, label: &str) -> v9::Component {
+    v9::Component {
+        kind,
+        position: Point::new(x, y),
+        custom_string: label.into(),
+        word_size: 8,
+        ..Default::default()
+    }
+}
+
+fn pin(c: &v9::Component, name: &str) -> Point {
+    let offset = c.kind.pin(name).unwrap().offset;
+    Point::new(c.position.x + offset.x, c.position.y + offset.y)
+}
+
+/// One `kind` component, labelled `dut` if it is a port, with a `CcInput`
+/// called `in_<pin>` on each input pin and a `CcOutput` called `out_<pin>`
+/// on each output pin.
+fn harness(kind: Kind) -> v9::CircuitData {
+    let label = if kind.is_input() || kind.is_output() {
+        "dut"
+    } else {
+        ""
+    };
+    let mut circuit = v9::CircuitData {
+        components: vec![component(kind, 0, 0, label)],
+        ..Default::default()
+    };
+    for (n, def) in kind.pins().iter().enumerate() {
+        let y = 4 * n as i16;
+        let (other, other_pin) = match def.direction {
+            PinDirection::Input => (
+                component(Kind::CcInput, -20, y, &format!("in_{}", def.name)),
+                "out",
+            ),
+            PinDirection::Output => (
+                component(Kind::CcOutput, 20, y, &format!("out_{}", def.name)),
+                "in",
+            ),
+        };
+        let (a, b) = (
+            pin(&other, other_pin),
+            pin(&circuit.components[0], def.name),
+        );
+        let points = [a, Point::new(a.x, b.y), b];
+        circuit.wires.push(Wire::from_polyline(&points).unwrap());
+        circuit.components.push(other);
+    }
+    circuit
+}
+
+/// Outputs of `sim` by label, with wide ports imported from yosys as one
+/// output per bit put back together. Outputs without a value read 0, as
+/// the exporters write them.
+fn outputs(sim: &mut Simulator, inputs: &HashMap<String, u64>) -> HashMap<String, u64> {
+    for port in sim.inputs().to_vec() {
+        let (name, bit) = split(&port.label);
+        let value = inputs.get(name).copied().unwrap_or(0);
+        let value = bit.map_or(value, |bit| (value >> bit) & 1);
+        sim.set_input_at(port.component, value);
+    }
+    sim.tick();
+    let mut outputs = HashMap::new();
+    for port in sim.outputs().to_vec() {
+        let (name, bit) = split(&port.label);
+        let value = sim.output_at(port.component).unwrap_or(0);
+        *outputs.entry(name.to_string()).or_insert(0) |= value << bit.unwrap_or(0);
+    }
+    outputs
+}
+
+/// `name[3]` as `("name", Some(3))`.
+fn split(label: &str) -> (&str, Option<u32>) {
+    match label.strip_suffix(']').and_then(|l| l.split_once('[')) {
+        Some((name, bit)) => (name, bit.parse().ok()),
+        None => (label, None),
+    }
+}
+
+fn port_names(module: &Module) -> BTreeSet<String> {
+    module.ports.keys().cloned().collect()
+}
+
+fn sim_port_names(sim: &Simulator) -> BTreeSet<String> {
+    let ports = sim.inputs().iter().chain(sim.outputs());
+    ports
+        .map(|p| p.label.clone())
+        .chain(["clk".to_string()])
+        .collect()
+}
+
+#[test]
+fn exporters_support_only_what_the_simulator_does() {
+    for kind in known_kinds().filter(|k| !k.pins().is_empty()) {
+        let circuit = harness(kind);
+        let sim = Simulator::new(&circuit);
+        let simulated = !sim.unsupported().contains(&0);
+
+        let text = verilog::to_verilog(&circuit, "top");
+        if !text.contains("// Not exported: c0: ") {
+            assert!(simulated, "{:?} exports to Verilog only", kind);
+            let declared: BTreeSet<_> = text
+                .lines()
+                .skip(1)
+                .take_while(|l| !l.starts_with(')'))
+                .map(|l| {
+                    let l = l.trim().trim_end_matches(',');
+                    l.rsplit(' ').next().unwrap().to_string()
+                })
+                .collect();
+            assert_eq!(declared, sim_port_names(&sim), "{:?}", kind);
+        }
+
+        match yosys::export::export(&circuit, "top") {
+            Ok(netlist) => {
+                assert!(simulated, "{:?} exports to yosys only", kind);
+                let module = netlist.module("top").unwrap();
+                assert_eq!(port_names(module), sim_port_names(&sim), "{:?}", kind);
+            }
+            Err(Error::UnsupportedCell { .. }) => {}
+            Err(err) => panic!("{:?}: {}", kind, err),
+        }
+    }
+}
+
+#[test]
+fn yosys_exports_simulate_like_the_circuit() {
+    for kind in known_kinds().filter(|k| !k.pins().is_empty()) {
+        let circuit = harness(kind);
+        let Ok(netlist) = yosys::export::export(&circuit, "top") else {
+            continue;
+        };
+        let imported = yosys::import(netlist.module("top").unwrap()).unwrap();
+        let mut original = Simulator::new(&circuit);
+        let mut exported = Simulator::new(&imported);
+
+        let mut seed = 0x2545_f491_4f6c_dd1d_u64;
+        for tick in 0..16 {
+            let inputs: HashMap<_, _> = original
+                .inputs()
+                .iter()
+                .map(|p| {
+                    seed ^= seed << 13;
+                    seed ^= seed >> 7;
+                    seed ^= seed << 17;
+                    (p.label.clone(), seed)
+                })
+                .collect();
+            assert_eq!(
+                outputs(&mut exported, &inputs),
+                outputs(&mut original, &inputs),
+                "{:?} at tick {}",
+                kind,
+                tick
+            );
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use tc_save_parser::{
+    Kind,
+    migrate::{FieldChange, MigrationReport},
+    v7, v8, v9,
+};
+
+fn dropped(path: &str) -> FieldChange {
+    FieldChange::Dropped(path.to_string())
+}
+
+fn defaulted(path: &str) -> FieldChange {
+    FieldChange::Defaulted(path.to_string())
+}
+
+fn v7_program() -> v7::SelectedProgram {
+    v7::SelectedProgram {
+        level: "level".into(),
+        program: "prog.asm".into(),
+    }
+}
+
+fn v9_program() -> v9::SelectedProgram {
+    v9::SelectedProgram {
+        level: "level".into(),
+        program: "prog.asm".into(),
+    }
+}
+
+#[test]
+fn upgrading_version_7_reports_what_version_8_lacks() {
+    let mut rom = v7::Component {
+        kind: Kind::Rom,
+        ..Default::default()
+    };
+    rom.memory.selected_programs = vec![v7_program()];
+    let mut assembler = v7::Component {
+        kind: Kind::Assembler,
+        ..Default::default()
+    };
+    assembler.memory.selected_programs = vec![v7_program()];
+    let mut custom = v7::Component {
+        kind: Kind::Custom,
+        dummy0: 5,
+        ..Default::default()
+    };
+    custom.custom.dummy0 = 1;
+    let circuit = v7::CircuitData {
+        hub_description: "hub".into(),
+        components: vec![rom, assembler, custom],
+        ..Default::default()
+    };
+
+    let (upgraded, report) = circuit.upgrade();
+    assert_eq!(
+        report.changes,
+        [
+            dropped("hub_description"),
+            dropped("components[0].selected_programs"),
+            dropped("components[2].dummy0"),
+            dropped("components[2].custom.dummy0"),
+            defaulted("components[0].linked_components"),
+        ]
+    );
+    assert!(!report.is_lossless());
+    assert_eq!(
+        upgraded.components[1]
+            .assembler_info
+            .selected_programs
+            .len(),
+        1
+    );
+}
+
+#[test]
+fn downgrading_version_8_reports_what_version_7_lacks() {
+    let mut ram = v8::Component {
+        kind: Kind::Ram,
+        ..Default::default()
+    };
+    ram.linked_components.linked_components = vec![3];
+    let circuit = v8::CircuitData {
+        components: vec![
+            ram,
+            v8::Component {
+                kind: Kind::Assembler,
+                ..Default::default()
+            },
+            v8::Component {
+                kind: Kind::AndBit,
+                dummy0: 2,
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    };
+
+    let (_, report) = circuit.downgrade();
+    assert_eq!(
+        report.changes,
+        [
+            dropped("components[0].linked_components"),
+            dropped("components[2].dummy0"),
+            defaulted("components[0].memory"),
+        ]
+    );
+}
+
+#[test]
+fn versions_8_and_9_convert_both_ways() {
+    let circuit = v9::CircuitData {
+        components: vec![
+            v9::Component {
+                kind: Kind::Ram,
+                selected_programs: vec![v9_program()],
+                ..Default::default()
+            },
+            v9::Component {
+                kind: Kind::Assembler,
+                selected_programs: vec![v9_program()],
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    };
+
+    let (v8, report) = circuit.clone().downgrade();
+    assert_eq!(report.changes, [dropped("components[0].selected_programs")]);
+    let (v9, report) = v8.upgrade();
+    assert_eq!(report, MigrationReport::default());
+    assert!(v9.components[0].selected_programs.is_empty());
+    assert_eq!(v9.components[1], circuit.components[1]);
+}
+
+#[test]
+fn lossless_conversions_report_nothing() {
+    let circuit = v9::CircuitData {
+        description: "unchanged".into(),
+        components: vec![v9::Component {
+            kind: Kind::AndBit,
+            permanent_id: 4,
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let (v8, down) = circuit.clone().downgrade();
+    let (v7, down_again) = v8.downgrade();
+    let (v8, up) = v7.upgrade();
+    let (v9, up_again) = v8.upgrade();
+    for report in [down, down_again, up, up_again] {
+        assert!(report.changes.is_empty(), "{:?}", report);
+    }
+    assert_eq!(v9, circuit);
+}
@@ -0,0 +1,108 @@
+use tc_save_parser::{
+    CircuitDataFile, CircuitDataVersion, Error, Kind,
+    migrate::FieldChange,
+    model::{Circuit, SelectedProgram},
+    v7, v9,
+};
+
+fn program() -> SelectedProgram {
+    SelectedProgram {
+        level: "level".into(),
+        program: "prog.asm".into(),
+    }
+}
+
+#[test]
+fn unknown_versions_keep_their_version_number() {
+    let file = CircuitDataFile {
+        version: 12,
+        circuit: CircuitDataVersion::Unknown(vec![1, 2, 3]),
+    };
+    match Circuit::try_from(file) {
+        Err(Error::UnsupportedVersion(12, data)) => assert_eq!(data, [1, 2, 3]),
+        other => panic!("expected version 12 to be unsupported, got {:?}", other),
+    }
+}
+
+#[test]
+fn files_convert_through_the_model() {
+    let circuit = v9::CircuitData {
+        components: vec![v9::Component {
+            kind: Kind::AndBit,
+            permanent_id: 3,
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let file = CircuitDataFile {
+        version: 9,
+        circuit: CircuitDataVersion::V9(circuit.clone()),
+    };
+    let model = Circuit::try_from(file).unwrap();
+    assert_eq!(model.component(3).unwrap().kind, Kind::AndBit);
+    assert_eq!(model.into_v9(), (circuit, Default::default()));
+}
+
+#[test]
+fn skipping_a_version_keeps_what_both_ends_hold() {
+    // Version 8 only stores programs for assemblers, but versions 7 and 9
+    // store them for every memory.
+    let mut rom = v7::Component {
+        kind: Kind::Rom,
+        ..Default::default()
+    };
+    rom.memory.selected_programs = vec![v7::SelectedProgram {
+        level: "level".into(),
+        program: "prog.asm".into(),
+    }];
+    let file = CircuitDataFile {
+        version: 7,
+        circuit: CircuitDataVersion::V7(v7::CircuitData {
+            components: vec![rom],
+            ..Default::default()
+        }),
+    };
+    let (file, report) = file.clone().convert_to(9).unwrap();
+    assert!(report.is_lossless(), "{:?}", report);
+    let CircuitDataVersion::V9(data) = &file.circuit else {
+        panic!("expected version 9, got {:?}", file.circuit);
+    };
+    assert_eq!(data.components[0].selected_programs.len(), 1);
+
+    let (file, report) = file.convert_to(7).unwrap();
+    assert!(report.changes.is_empty(), "{:?}", report);
+    let CircuitDataVersion::V7(data) = file.circuit else {
+        panic!("expected version 7, got {:?}", file.circuit);
+    };
+    assert_eq!(data.components[0].memory.selected_programs.len(), 1);
+}
+
+#[test]
+fn reports_what_the_target_version_lacks() {
+    let mut circuit = Circuit {
+        hub_description: "hub".into(),
+        components: vec![Default::default(), Default::default()],
+        ..Default::default()
+    };
+    circuit.components[0].kind = Kind::Ram;
+    circuit.components[0].selected_programs = vec![program()];
+    circuit.components[1].kind = Kind::Assembler;
+    circuit.components[1].selected_programs = vec![program()];
+
+    let (_, report) = circuit.clone().into_v8();
+    assert_eq!(
+        report.changes,
+        [
+            FieldChange::Dropped("hub_description".to_string()),
+            FieldChange::Dropped("components[0].selected_programs".to_string()),
+        ]
+    );
+    let (_, report) = circuit.clone().into_v9();
+    assert_eq!(
+        report.changes,
+        [FieldChange::Dropped("hub_description".to_string())]
+    );
+    let (data, report) = circuit.into_v7();
+    assert!(report.changes.is_empty(), "{:?}", report);
+    assert_eq!(data.hub_description, "hub");
+}
@@ -0,0 +1,124 @@
+use tc_save_parser::{
+    Kind, Point, Wire,
+    catalog::{PinDirection, Width},
+    netlist::{NetlistBuilder, PinDef, WireEnd},
+    v9,
+};
+
+fn component(kind: Kind, x: i16, y: i16, rotation: u8) -> v9::Component {
+    v9::Component {
+        kind,
+        position: Point::new(x, y),
+        rotation,
+        ..Default::default()
+    }
+}
+
+fn wire(points: &[(i16, i16)]) -> Wire {
+    let points: Vec<_> = points.iter().map(|&(x, y)| Point::new(x, y)).collect();
+    Wire::from_polyline(&points).unwrap()
+}
+
+/// Two level inputs feeding an AND gate at (10, 0), whose output runs to a
+/// level output at (20, 0) through two joined wires.
+fn and_circuit() -> v9::CircuitData {
+    v9::CircuitData {
+        components: vec![
+            component(Kind::LevelInput1, 0, 0, 0),
+            component(Kind::LevelInput1, 0, 4, 0),
+            component(Kind::AndBit, 10, 0, 0),
+            component(Kind::LevelOutput1, 20, 0, 0),
+        ],
+        wires: vec![
+            wire(&[(1, 0), (9, 0)]),
+            wire(&[(1, 4), (5, 4), (5, 1), (9, 1)]),
+            wire(&[(11, 0), (15, 0)]),
+            wire(&[(15, 0), (19, 0)]),
+        ],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn wires_join_pins_into_nets() {
+    let circuit = and_circuit();
+    let netlist = NetlistBuilder::new(&circuit).build();
+    assert_eq!(netlist.nets.len(), 3);
+
+    let a = netlist.pin_ref(2, "a").unwrap();
+    let b = netlist.pin_ref(2, "b").unwrap();
+    let out = netlist.pin_ref(2, "out").unwrap();
+    let input = netlist.pin_ref(0, "out").unwrap();
+    let output = netlist.pin_ref(3, "in").unwrap();
+
+    assert_eq!(netlist.net_of(input), netlist.net_of(a));
+    assert_ne!(netlist.net_of(a), netlist.net_of(b));
+    let net = &netlist.nets[netlist.net_of(out).unwrap()];
+    assert_eq!(net.wires, [2, 3]);
+    assert_eq!(net.pins, [out, output]);
+
+    assert!(netlist.floating_wire_ends.is_empty());
+    assert!(netlist.unconnected_pins.is_empty());
+}
+
+#[test]
+fn reports_floating_ends_and_unconnected_pins() {
+    let mut circuit = and_circuit();
+    circuit.wires.remove(3);
+    circuit.components[2].position = Point::new(10, 10);
+    let netlist = NetlistBuilder::new(&circuit).build();
+
+    // Moved away, the gate's pins no longer meet any wire.
+    let gate_pins: Vec<_> = netlist
+        .unconnected_pins
+        .iter()
+        .filter(|p| p.component == 2)
+        .collect();
+    assert_eq!(gate_pins.len(), 3);
+    assert!(
+        netlist
+            .unconnected_pins
+            .contains(&netlist.pin_ref(3, "in").unwrap())
+    );
+    assert!(netlist.floating_wire_ends.contains(&WireEnd {
+        wire: 2,
+        at: Point::new(15, 0),
+    }));
+    assert!(netlist.floating_wire_ends.contains(&WireEnd {
+        wire: 0,
+        at: Point::new(9, 0),
+    }));
+}
+
+#[test]
+fn custom_pins_come_from_the_resolver() {
+    let circuit = v9::CircuitData {
+        components: vec![
+            component(Kind::Custom, 0, 0, 1),
+            component(Kind::LevelOutputWord, 0, 6, 0),
+        ],
+        wires: vec![wire(&[(0, 2), (0, 4), (-1, 5), (-1, 6)])],
+        ..Default::default()
+    };
+
+    let unresolved = NetlistBuilder::new(&circuit).build();
+    assert_eq!(unresolved.unresolved_components, [0]);
+
+    let netlist = NetlistBuilder::new(&circuit)
+        .custom_pins(|_| {
+            Some(vec![PinDef {
+                name: "result".into(),
+                offset: Point::new(2, 0),
+                direction: PinDirection::Output,
+                width: Width::Word,
+            }])
+        })
+        .build();
+    assert!(netlist.unresolved_components.is_empty());
+    let result = netlist.pin_ref(0, "result").unwrap();
+    assert_eq!(netlist.pin(result).position, Point::new(0, 2));
+    assert_eq!(
+        netlist.net_of(result),
+        netlist.net_of(netlist.pin_ref(1, "in").unwrap())
+    );
+}
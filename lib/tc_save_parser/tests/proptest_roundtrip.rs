@@ -0,0 +1,38 @@
+use std::io::Cursor;
+
+use binrw::BinRead;
+use proptest::prelude::*;
+use tc_save_parser::{CircuitDataFile, CircuitDataVersion, generate::strategy, v9};
+
+proptest! {
+    #[test]
+    fn v9_write_then_read_is_identity(data in strategy::circuit_data()) {
+        let bytes = data.get_bytes().unwrap();
+        let back = v9::CircuitData::read_le(&mut Cursor::new(&bytes)).unwrap();
+        prop_assert_eq!(&back, &data);
+        prop_assert_eq!(back.get_bytes().unwrap(), bytes);
+    }
+
+    #[test]
+    fn files_write_then_read_is_identity(data in strategy::circuit_data()) {
+        let cdf = CircuitDataFile {
+            version: 9,
+            circuit: CircuitDataVersion::V9(data.clone()),
+        };
+        let bytes = cdf.to_bytes().unwrap();
+        let back = CircuitDataFile::from_bytes(&bytes).unwrap();
+        match back.circuit {
+            CircuitDataVersion::V9(back) => prop_assert_eq!(back, data),
+            other => prop_assert!(false, "read back as {:?}", other),
+        }
+    }
+
+    #[test]
+    fn truncated_payloads_are_errors(data in strategy::circuit_data(), cut in any::<prop::sample::Index>()) {
+        let bytes = data.get_bytes().unwrap();
+        let cut = cut.index(bytes.len());
+        let mut file = vec![9];
+        file.extend(snap::raw::Encoder::new().compress_vec(&bytes[..cut]).unwrap());
+        prop_assert!(CircuitDataFile::from_bytes(&file).is_err());
+    }
+}
@@ -0,0 +1,116 @@
+use tc_save_parser::{
+    Kind, Point, WireBuilder,
+    render::{self, RenderOptions, Viewport},
+    v9,
+};
+
+/// An AND gate labelled with markup, and a red commented wire from its
+/// output.
+fn circuit() -> v9::CircuitData {
+    v9::CircuitData {
+        components: vec![v9::Component {
+            kind: Kind::AndBit,
+            position: Point::new(2, 3),
+            custom_string: "a<b".into(),
+            ..Default::default()
+        }],
+        wires: vec![
+            WireBuilder::new(Point::new(3, 3))
+                .right(4)
+                .down(2)
+                .color(1)
+                .comment("carry & co")
+                .build(),
+        ],
+        camera_position: Point::new(100, 50),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn draws_components_wires_and_labels() {
+    let svg = render::to_svg(&circuit(), &RenderOptions::default());
+    // The gate covers x 1..=3, y 3..=4 and the wire reaches (7, 5), plus a
+    // cell of margin.
+    assert!(svg.starts_with(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="144" height="80" viewBox="0 2 9 5""#
+    ));
+    assert!(svg.contains(r#"<rect x="1.05" y="3.05" width="2.9" height="1.9" rx="0.2""#));
+    assert!(svg.contains(r##"<polyline points="3.5,3.5 7.5,3.5 7.5,5.5" stroke="#e23d3d"/>"##));
+    assert!(svg.contains(">a&lt;b</text>"));
+    assert!(svg.contains(">carry &amp; co</text>"));
+    assert!(svg.ends_with("</svg>\n"));
+}
+
+#[test]
+fn viewports_crop_and_follow_the_camera() {
+    let svg = |viewport, scale| render::to_svg(&circuit(), &RenderOptions { viewport, scale });
+    let cropped = svg(
+        Viewport::Crop {
+            min: Point::new(5, 5),
+            max: Point::new(-4, 0),
+        },
+        2.0,
+    );
+    assert!(cropped.contains(r#"width="20" height="12" viewBox="-4 0 10 6""#));
+    let camera = svg(
+        Viewport::Camera {
+            width: 40,
+            height: 30,
+        },
+        1.5,
+    );
+    assert!(camera.contains(r#"width="60" height="45" viewBox="80 35 40 30""#));
+}
+
+#[test]
+fn the_edges_of_the_grid_do_not_overflow() {
+    let gate = |x, y| v9::Component {
+        kind: Kind::AndBit,
+        position: Point::new(x, y),
+        ..Default::default()
+    };
+    let mut circuit = v9::CircuitData {
+        components: vec![gate(i16::MAX, i16::MAX)],
+        ..Default::default()
+    };
+    let options = RenderOptions {
+        viewport: Viewport::Fit,
+        scale: 1.0,
+    };
+    // The gate reaches a cell past `i16::MAX`, and the margin one more.
+    let svg = render::to_svg(&circuit, &options);
+    assert!(svg.contains(r#"viewBox="32765 32766 5 4""#));
+    assert!(svg.contains(r#"<circle cx="32768.5" cy="32767.5""#));
+    circuit.components.push(gate(i16::MIN, i16::MIN));
+    let svg = render::to_svg(&circuit, &options);
+    assert!(svg.contains(r#"viewBox="-32770 -32769 65540 65539""#));
+
+    let svg = |camera, width, height| {
+        let circuit = v9::CircuitData {
+            camera_position: camera,
+            ..Default::default()
+        };
+        let viewport = Viewport::Camera { width, height };
+        render::to_svg(
+            &circuit,
+            &RenderOptions {
+                viewport,
+                scale: 1.0,
+            },
+        )
+    };
+    assert!(svg(Point::new(-30000, 0), 20000, 10).contains(r#"viewBox="-40000 -5 20000 10""#));
+    assert!(
+        svg(Point::new(0, 0), 40000, u16::MAX).contains(r#"viewBox="-20000 -32767 40000 65535""#)
+    );
+}
+
+#[cfg(feature = "png")]
+#[test]
+fn rasterises_to_png() {
+    let png = render::to_png(&circuit(), &RenderOptions::default()).unwrap();
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    let size = |at: usize| u32::from_be_bytes(png[at..at + 4].try_into().unwrap());
+    assert_eq!((size(16), size(20)), (144, 80));
+}
@@ -0,0 +1,338 @@
+use std::io::Cursor;
+
+use binrw::BinRead;
+use tc_save_parser::{
+    CDString, CircuitDataFile, CircuitDataVersion, Error, Kind, model::Circuit, v7, v8, v9,
+};
+
+/// Little-endian payload builder, so every byte of the fixtures is spelled out
+/// rather than produced by the writer under test.
+#[derive(Default)]
+struct Payload(Vec<u8>);
+
+impl Payload {
+    fn u8(mut self, v: u8) -> Self {
+        self.0.push(v);
+        self
+    }
+    fn u16(mut self, v: u16) -> Self {
+        self.0.extend(v.to_le_bytes());
+        self
+    }
+    fn u32(mut self, v: u32) -> Self {
+        self.0.extend(v.to_le_bytes());
+        self
+    }
+    fn u64(mut self, v: u64) -> Self {
+        self.0.extend(v.to_le_bytes());
+        self
+    }
+    fn kind(self, kind: Kind) -> Self {
+        self.u16(u16::from(kind))
+    }
+    fn str(self, s: &str) -> Self {
+        let mut out = self.u16(s.len() as u16);
+        out.0.extend(s.as_bytes());
+        out
+    }
+    fn point(self, x: i16, y: i16) -> Self {
+        self.u16(x as u16).u16(y as u16)
+    }
+    fn header(self) -> Self {
+        self.u64(0x1234_5678_9abc_def0) // custom_id
+            .u32(7) // hub_id
+            .u64(12) // gate
+            .u64(34) // delay
+            .u8(1) // menu_visible
+            .u64(100) // clock_speed
+            .u16(1)
+            .u64(0xdead_beef) // dependencies
+            .str("a description")
+            .point(-3, 4)
+            .u8(2) // synced
+    }
+    fn component_head(self, kind: Kind, id: u64) -> Self {
+        self.kind(kind)
+            .point(5, -6)
+            .u8(1) // rotation
+            .u64(id)
+            .str("label")
+            .u16(2)
+            .u64(1)
+            .u64(u64::MAX) // settings
+            .u64(0) // buffer_size
+            .u16(3) // ui_order
+            .u64(8) // word_size
+    }
+    fn wire(self) -> Self {
+        self.u8(3).str("a wire").point(1, 2).u8(0x05).u8(0x43).u8(0)
+    }
+}
+
+fn assert_round_trip<T>(payload: &[u8], to_bytes: impl Fn(&T) -> Vec<u8>)
+where
+    T: for<'a> BinRead<Args<'a> = ()>,
+{
+    let data = T::read_le(&mut Cursor::new(payload)).unwrap();
+    assert_eq!(to_bytes(&data), payload);
+}
+
+fn compress(version: u8, payload: &[u8]) -> Vec<u8> {
+    let mut file = vec![version];
+    file.extend(snap::raw::Encoder::new().compress_vec(payload).unwrap());
+    file
+}
+
+fn v9_payload() -> Vec<u8> {
+    Payload::default()
+        .header()
+        .u32(0xa5a5_5a5a) // dummy0
+        .u16(3)
+        .u8(9)
+        .u8(8)
+        .u8(7) // player_data
+        .u64(3) // components
+        .component_head(Kind::AndBit, 1)
+        .u16(0)
+        .u16(0)
+        .component_head(Kind::Ram, 2)
+        .u16(1)
+        .u64(1)
+        .u64(0x77) // linked_components + dummy0
+        .u16(1)
+        .u64(1)
+        .u64(2)
+        .str("watched")
+        .u16(1)
+        .str("level")
+        .str("prog.asm")
+        .component_head(Kind::Custom, 3)
+        .u16(0)
+        .u16(0)
+        .u64(99)
+        .u16(1)
+        .u64(4)
+        .u64(5) // custom
+        .u64(1) // wires
+        .wire()
+        .0
+}
+
+fn v8_payload() -> Vec<u8> {
+    Payload::default()
+        .header()
+        .u32(0xa5a5_5a5a) // dummy0
+        .u16(0) // player_data
+        .u64(4) // components
+        .component_head(Kind::AndBit, 1)
+        .u16(0x1234) // dummy0
+        .component_head(Kind::Ram, 2)
+        .u16(1)
+        .u64(1)
+        .u64(0x77) // linked_components + dummy0
+        .u16(0x4321) // dummy0
+        .component_head(Kind::Custom, 3)
+        .u16(0) // dummy0
+        .u64(99)
+        .u16(1)
+        .u64(4)
+        .u64(5) // custom
+        .component_head(Kind::Assembler, 4)
+        .u16(1)
+        .u64(1)
+        .u64(2)
+        .str("watched")
+        .u16(1)
+        .str("level")
+        .str("prog.asm")
+        .u64(2) // wires
+        .wire()
+        .wire()
+        .0
+}
+
+fn v7_payload() -> Vec<u8> {
+    Payload::default()
+        .header()
+        .u16(0xa55a) // dummy0
+        .u16(1)
+        .u8(1) // player_data
+        .str("hub")
+        .u64(3) // components
+        .component_head(Kind::AndBit, 1)
+        .u64(0x0102_0304) // dummy0
+        .component_head(Kind::Custom, 3)
+        .u64(0)
+        .u64(99)
+        .u16(1)
+        .u64(4)
+        .u64(5)
+        .u16(0xbeef) // custom
+        .component_head(Kind::Rom, 4)
+        .u64(0)
+        .u16(1)
+        .str("level")
+        .str("prog.asm")
+        .u16(1)
+        .u64(1)
+        .u64(2)
+        .str("watched") // memory
+        .u64(1) // wires
+        .wire()
+        .0
+}
+
+#[test]
+fn v9_payload_round_trips() {
+    let payload = v9_payload();
+    assert_round_trip::<v9::CircuitData>(&payload, |d| d.get_bytes().unwrap());
+    assert_round_trip::<v9::CircuitData>(&payload, |d| {
+        let (data, report) = Circuit::from(d.clone()).into_v9();
+        assert!(report.is_lossless());
+        data.get_bytes().unwrap()
+    });
+}
+
+#[test]
+fn v8_payload_round_trips() {
+    let payload = v8_payload();
+    assert_round_trip::<v8::CircuitData>(&payload, |d| d.get_bytes().unwrap());
+    assert_round_trip::<v8::CircuitData>(&payload, |d| {
+        let (data, report) = Circuit::from(d.clone()).into_v8();
+        assert!(report.is_lossless());
+        data.get_bytes().unwrap()
+    });
+}
+
+#[test]
+fn v7_payload_round_trips() {
+    let payload = v7_payload();
+    assert_round_trip::<v7::CircuitData>(&payload, |d| d.get_bytes().unwrap());
+    assert_round_trip::<v7::CircuitData>(&payload, |d| {
+        let (data, report) = Circuit::from(d.clone()).into_v7();
+        assert!(report.is_lossless());
+        data.get_bytes().unwrap()
+    });
+}
+
+#[test]
+fn files_round_trip() {
+    for (version, payload) in [(7, v7_payload()), (8, v8_payload()), (9, v9_payload())] {
+        let file = compress(version, &payload);
+        let cdf = CircuitDataFile::from_bytes(&file).unwrap();
+        assert_eq!(cdf.version, version);
+        assert_eq!(cdf.to_bytes().unwrap(), file);
+    }
+}
+
+// The custom component's explicit word sizes are followed by the wire count
+// and the 16-byte wire, so they start 40 bytes before the end of the payload.
+const WORD_SIZES_FROM_END: usize = 40;
+
+#[test]
+fn corrupt_lengths_report_their_field() {
+    let mut payload = v9_payload();
+    let items = payload.len() - WORD_SIZES_FROM_END;
+    payload[items - 2..items].copy_from_slice(&0xffffu16.to_le_bytes());
+    match CircuitDataFile::from_bytes(&compress(9, &payload)) {
+        Err(Error::InvalidLength { offset, path, len }) => {
+            assert_eq!(offset, items as u64);
+            assert_eq!(path, "components[2].custom.explicit_word_sizes");
+            assert_eq!(len, 0xffff);
+        }
+        other => panic!("expected an invalid length, got {:?}", other),
+    }
+}
+
+#[test]
+fn truncated_payloads_report_their_field() {
+    let mut payload = v9_payload();
+    // Cut the first word size's `b` in half.
+    let b = payload.len() - WORD_SIZES_FROM_END + 8;
+    payload.truncate(b + 4);
+    match CircuitDataFile::from_bytes(&compress(9, &payload)) {
+        Err(Error::Truncated { offset, path }) => {
+            assert_eq!(offset, b as u64);
+            assert_eq!(path, "components[2].custom.explicit_word_sizes[0].b");
+        }
+        other => panic!("expected truncated data, got {:?}", other),
+    }
+}
+
+#[test]
+fn strings_that_are_not_utf8_round_trip() {
+    let mut payload = v9_payload();
+    let at = payload.windows(5).position(|w| w == b"label").unwrap();
+    payload[at..at + 5].copy_from_slice(b"\xffab\xc3\x28");
+    assert_round_trip::<v9::CircuitData>(&payload, |d| {
+        let label = &d.components[0].custom_string;
+        assert_eq!(label.as_bytes(), b"\xffab\xc3\x28");
+        assert_eq!(label.as_str(), None);
+        assert_eq!(label.to_string_lossy(), "\u{fffd}ab\u{fffd}(");
+        assert_eq!(label.value(), "\u{fffd}ab\u{fffd}(");
+        assert_eq!(String::from(label.clone()), "\u{fffd}ab\u{fffd}(");
+        let (data, report) = Circuit::from(d.clone()).into_v9();
+        assert!(report.is_lossless());
+        data.get_bytes().unwrap()
+    });
+}
+
+#[test]
+fn strings_convert_to_and_from_text() {
+    let label = CDString::from(String::from("näive ✓"));
+    assert_eq!(label.value(), "näive ✓");
+    assert_eq!(label, CDString::from("näive ✓"));
+    assert_eq!(String::from(label), "näive ✓");
+}
+
+#[test]
+fn version_7_files_load_as_the_latest_version() {
+    let file = compress(7, &v7_payload());
+    let (data, _) = CircuitDataFile::from_bytes(&file)
+        .unwrap()
+        .into_latest()
+        .unwrap();
+    let kinds: Vec<_> = data.components.iter().map(|c| c.kind).collect();
+    assert_eq!(kinds, [Kind::AndBit, Kind::Custom, Kind::Rom]);
+    assert_eq!(data.components[2].selected_programs.len(), 1);
+}
+
+#[test]
+fn large_circuits_serialize() {
+    let mut data = v9::CircuitData::default();
+    for i in 0..2000 {
+        data.components.push(v9::Component {
+            kind: Kind::AndBit,
+            permanent_id: i,
+            custom_string: "a label long enough to matter".into(),
+            ..Default::default()
+        });
+    }
+    let payload = data.get_bytes().unwrap();
+    assert!(payload.len() > 8192);
+    let cdf = CircuitDataFile {
+        version: 9,
+        circuit: CircuitDataVersion::V9(data),
+    };
+    let file = cdf.to_bytes().unwrap();
+    let back = CircuitDataFile::from_bytes(&file).unwrap();
+    assert_eq!(back.to_bytes().unwrap(), file);
+}
+
+#[test]
+fn padding_fields_are_preserved() {
+    let v7 = v7::CircuitData::read_le(&mut Cursor::new(v7_payload())).unwrap();
+    assert_eq!(v7.dummy0, 0xa55a);
+    assert_eq!(v7.components[0].dummy0, 0x0102_0304);
+    assert_eq!(v7.components[1].custom.dummy0, 0xbeef);
+
+    let mut v8 = v8::CircuitData::read_le(&mut Cursor::new(v8_payload())).unwrap();
+    assert_eq!(v8.dummy0, 0xa5a5_5a5a);
+    assert_eq!(v8.components[0].dummy0, 0x1234);
+    assert_eq!(v8.components[1].linked_components.dummy0, 0x77);
+
+    v8.components[0].dummy0 = 0x5678;
+    let payload = v8.get_bytes().unwrap();
+    let back = v8::CircuitData::read_le(&mut Cursor::new(payload)).unwrap();
+    assert_eq!(back.components[0].dummy0, 0x5678);
+}
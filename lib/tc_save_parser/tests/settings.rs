@@ -0,0 +1,50 @@
+use tc_save_parser::{ComponentSettings, Kind, v9};
+
+fn component(kind: Kind, settings: Vec<u64>) -> v9::Component {
+    v9::Component {
+        kind,
+        settings,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn known_layouts_decode() {
+    assert_eq!(
+        component(Kind::Constant, vec![42]).typed_settings(),
+        ComponentSettings::Constant { value: 42 }
+    );
+    assert_eq!(
+        component(Kind::RamFast, vec![256]).typed_settings(),
+        ComponentSettings::Memory { size: 256 }
+    );
+    assert_eq!(
+        component(Kind::StaticIndexerConfig, vec![3]).typed_settings(),
+        ComponentSettings::StaticIndexer { index: 3 }
+    );
+}
+
+#[test]
+fn unknown_layouts_fall_back_to_raw() {
+    assert_eq!(
+        component(Kind::AndBit, vec![1, 2]).typed_settings(),
+        ComponentSettings::Raw(vec![1, 2])
+    );
+    assert_eq!(
+        component(Kind::Constant, vec![]).typed_settings(),
+        ComponentSettings::Raw(vec![])
+    );
+}
+
+#[test]
+fn setters_encode_into_settings() {
+    let mut counter = component(Kind::Counter, vec![1]);
+    assert!(counter.set_typed_settings(&ComponentSettings::Counter { increment: 4 }));
+    assert_eq!(counter.settings, vec![4]);
+
+    assert!(!counter.set_typed_settings(&ComponentSettings::Constant { value: 9 }));
+    assert_eq!(counter.settings, vec![4]);
+
+    assert!(counter.set_typed_settings(&ComponentSettings::Raw(vec![7, 8])));
+    assert_eq!(counter.settings, vec![7, 8]);
+}
@@ -0,0 +1,374 @@
+use tc_save_parser::{
+    Error, Kind, Point, Wire,
+    netlist::NetlistBuilder,
+    sim::{MAX_MEMORY_WORDS, Simulator},
+    v9,
+};
+
+#[derive(Default)]
+struct Circuit(v9::CircuitData);
+
+impl Circuit {
+    fn add(&mut self, kind: Kind, x: i16, label: &str, settings: Vec<u64>) -> usize {
+        self.0.components.push(v9::Component {
+            kind,
+            position: Point::new(x, 0),
+            custom_string: label.into(),
+            settings,
+            word_size: 8,
+            ..Default::default()
+        });
+        self.0.components.len() - 1
+    }
+
+    fn link(&mut self, component: usize, to: usize) {
+        let id = 1000 + to as u64;
+        self.0.components[to].permanent_id = id;
+        let links = &mut self.0.components[component].linked_components;
+        links.linked_components.push(id);
+    }
+
+    fn pin(&self, component: usize, name: &str) -> Point {
+        let c = &self.0.components[component];
+        let offset = c.kind.pin(name).unwrap().offset;
+        Point::new(c.position.x + offset.x, c.position.y + offset.y)
+    }
+
+    /// Wires two pins with a run down to a lane below everything, so wires
+    /// never end on each other by accident.
+    fn connect(&mut self, from: (usize, &str), to: (usize, &str)) {
+        let (a, b) = (self.pin(from.0, from.1), self.pin(to.0, to.1));
+        let lane = 20 + self.0.wires.len() as i16;
+        let points = [a, Point::new(a.x, lane), Point::new(b.x, lane), b];
+        self.0.wires.push(Wire::from_polyline(&points).unwrap());
+    }
+}
+
+#[test]
+fn adds_words_with_carry() {
+    let mut c = Circuit::default();
+    let a = c.add(Kind::CcInput, 0, "a", vec![]);
+    let b = c.add(Kind::CcInput, 10, "b", vec![]);
+    let add = c.add(Kind::Add, 20, "", vec![]);
+    let sum = c.add(Kind::CcOutput, 30, "sum", vec![]);
+    let carry = c.add(Kind::LevelOutput1, 40, "carry", vec![]);
+    c.connect((a, "out"), (add, "a"));
+    c.connect((b, "out"), (add, "b"));
+    c.connect((add, "out"), (sum, "in"));
+    c.connect((add, "carry_out"), (carry, "in"));
+
+    let mut sim = Simulator::new(&c.0);
+    assert!(sim.unsupported().is_empty());
+    let outputs = sim.step(&[("a", 200), ("b", 100)]).unwrap();
+    assert_eq!(outputs["sum"], Some(44));
+    assert_eq!(outputs["carry"], Some(1));
+
+    sim.set_input("b", 5).unwrap();
+    sim.tick();
+    assert_eq!(sim.output("sum").unwrap(), Some(205));
+    assert_eq!(sim.output("carry").unwrap(), Some(0));
+    assert!(sim.settled());
+}
+
+#[test]
+fn counters_and_registers_latch_at_the_end_of_a_tick() {
+    let mut c = Circuit::default();
+    let counter = c.add(Kind::Counter, 0, "", vec![3]);
+    let count = c.add(Kind::CcOutput, 10, "count", vec![]);
+    let on = c.add(Kind::On, 20, "", vec![]);
+    let save = c.add(Kind::LevelInput1, 30, "save", vec![]);
+    let value = c.add(Kind::CcInput, 40, "value", vec![]);
+    let register = c.add(Kind::RegisterWord, 50, "", vec![]);
+    let stored = c.add(Kind::CcOutput, 60, "stored", vec![]);
+    c.connect((counter, "out"), (count, "in"));
+    c.connect((on, "out"), (register, "load"));
+    c.connect((save, "out"), (register, "save"));
+    c.connect((value, "out"), (register, "value"));
+    c.connect((register, "out"), (stored, "in"));
+
+    let mut sim = Simulator::new(&c.0);
+    let counts: Vec<_> = (0..4)
+        .map(|_| {
+            sim.tick();
+            sim.output("count").unwrap().unwrap()
+        })
+        .collect();
+    assert_eq!(counts, [0, 3, 6, 9]);
+
+    let first = sim.step(&[("save", 1), ("value", 42)]).unwrap();
+    assert_eq!(first["stored"], Some(0));
+    let second = sim.step(&[("save", 0), ("value", 7)]).unwrap();
+    assert_eq!(second["stored"], Some(42));
+}
+
+#[test]
+fn multi_pin_levels_spread_and_gather_bits() {
+    let mut c = Circuit::default();
+    let input = c.add(Kind::LevelInput2Pin, 0, "select", vec![]);
+    let decoder = c.add(Kind::Decoder2, 10, "", vec![]);
+    let output = c.add(Kind::LevelOutput4Pin, 20, "lines", vec![]);
+    c.connect((input, "out0"), (decoder, "a"));
+    c.connect((input, "out1"), (decoder, "b"));
+    for i in 0..4 {
+        let (from, to) = (format!("out{}", i), format!("in{}", i));
+        c.connect((decoder, &from), (output, &to));
+    }
+
+    let mut sim = Simulator::new(&c.0);
+    for select in 0..4 {
+        let outputs = sim.step(&[("select", select)]).unwrap();
+        assert_eq!(outputs["lines"], Some(1 << select));
+    }
+}
+
+#[test]
+fn delay_lines_lag_by_a_tick() {
+    let mut c = Circuit::default();
+    let input = c.add(Kind::LevelInput1, 0, "in", vec![]);
+    let not = c.add(Kind::NotBit, 10, "", vec![]);
+    let delay = c.add(Kind::DelayLineBit, 20, "", vec![]);
+    let output = c.add(Kind::LevelOutput1, 30, "out", vec![]);
+    c.connect((input, "out"), (not, "a"));
+    c.connect((not, "out"), (delay, "a"));
+    c.connect((delay, "out"), (output, "in"));
+
+    let mut sim = Simulator::new(&c.0);
+    let outs: Vec<_> = [0, 1, 1, 0]
+        .into_iter()
+        .map(|v| sim.step(&[("in", v)]).unwrap()["out"].unwrap())
+        .collect();
+    assert_eq!(outs, [0, 1, 0, 0]);
+}
+
+#[test]
+fn shifts_of_a_word_or_more_clear_it() {
+    let mut c = Circuit::default();
+    let a = c.add(Kind::CcInput, 0, "a", vec![]);
+    let b = c.add(Kind::CcInput, 10, "b", vec![]);
+    let lsl = c.add(Kind::Lsl, 20, "", vec![]);
+    let lsr = c.add(Kind::Lsr, 30, "", vec![]);
+    let left = c.add(Kind::CcOutput, 40, "left", vec![]);
+    let right = c.add(Kind::CcOutput, 50, "right", vec![]);
+    for component in &mut c.0.components {
+        component.word_size = 64;
+    }
+    c.connect((a, "out"), (lsl, "a"));
+    c.connect((b, "out"), (lsl, "b"));
+    c.connect((a, "out"), (lsr, "a"));
+    c.connect((b, "out"), (lsr, "b"));
+    c.connect((lsl, "out"), (left, "in"));
+    c.connect((lsr, "out"), (right, "in"));
+
+    let mut sim = Simulator::new(&c.0);
+    let outputs = sim.step(&[("a", 0x30), ("b", 4)]).unwrap();
+    assert_eq!(outputs["left"], Some(0x300));
+    assert_eq!(outputs["right"], Some(0x3));
+    for shift in [64, 1 << 32, (1 << 32) + 4, u64::MAX] {
+        let outputs = sim.step(&[("a", 0x30), ("b", shift)]).unwrap();
+        assert_eq!(outputs["left"], Some(0), "shift {}", shift);
+        assert_eq!(outputs["right"], Some(0), "shift {}", shift);
+    }
+}
+
+#[test]
+fn parts_wider_than_a_net_are_unsupported() {
+    let mut c = Circuit::default();
+    let narrow = c.add(Kind::Concatenator8, 0, "", vec![]);
+    let wide = c.add(Kind::Concatenator8, 10, "", vec![]);
+    c.0.components[wide].word_size = 64;
+    let immediate = c.add(Kind::ImmRegisterWord, 20, "", vec![]);
+    let delay = c.add(Kind::ImmDelayLineBit, 30, "", vec![]);
+
+    let sim = Simulator::new(&c.0);
+    assert!(!sim.unsupported().contains(&narrow));
+    assert_eq!(sim.unsupported(), [wide, immediate, delay]);
+}
+
+#[test]
+fn unknown_labels_are_errors() {
+    let mut sim = Simulator::new(&v9::CircuitData::default());
+    assert!(matches!(
+        sim.set_input("nope", 1),
+        Err(Error::NoSuchPort(_))
+    ));
+    assert!(matches!(sim.output("nope"), Err(Error::NoSuchPort(_))));
+}
+
+#[test]
+fn shared_labels_are_errors() {
+    let mut c = Circuit::default();
+    c.add(Kind::CcInput, 0, "a", vec![]);
+    c.add(Kind::CcInput, 10, "a", vec![]);
+    c.add(Kind::CcOutput, 20, "a", vec![]);
+    c.add(Kind::CcOutput, 30, "out", vec![]);
+    c.add(Kind::CcOutput, 40, "out", vec![]);
+    c.add(Kind::CcOutput, 50, "", vec![]);
+
+    let mut sim = Simulator::new(&c.0);
+    assert!(matches!(
+        sim.set_input("a", 1),
+        Err(Error::DuplicatePort(label)) if label == "a"
+    ));
+    assert_eq!(sim.output("a").unwrap(), Some(0));
+    assert!(matches!(sim.output("out"), Err(Error::DuplicatePort(_))));
+    assert!(matches!(
+        sim.step(&[]),
+        Err(Error::DuplicatePort(label)) if label == "out"
+    ));
+    assert_eq!(sim.ticks(), 0);
+
+    c.0.components[4].custom_string = "other".into();
+    let outputs = Simulator::new(&c.0).step(&[]).unwrap();
+    let mut labels: Vec<_> = outputs.keys().map(String::as_str).collect();
+    labels.sort();
+    assert_eq!(labels, ["a", "other", "out"]);
+}
+
+#[test]
+fn missing_pins_read_as_unconnected() {
+    let mut c = Circuit::default();
+    let a = c.add(Kind::CcInput, 0, "a", vec![]);
+    let not = c.add(Kind::NotBit, 10, "", vec![]);
+    let out = c.add(Kind::CcOutput, 20, "out", vec![]);
+    c.connect((a, "out"), (not, "a"));
+    c.connect((not, "out"), (out, "in"));
+
+    // A netlist built for another layout, where the gate's input has a
+    // different name.
+    let mut netlist = NetlistBuilder::new(&c.0).build();
+    netlist.pins[not][0].name = "renamed".to_string();
+    let mut sim = Simulator::with_netlist(&c.0, netlist);
+    let outputs = sim.step(&[("a", 1)]).unwrap();
+    assert_eq!(outputs["out"], Some(1));
+}
+
+#[test]
+fn ram_stores_and_loads_words() {
+    let mut c = Circuit::default();
+    let inputs: Vec<_> = ["save", "load"]
+        .into_iter()
+        .enumerate()
+        .map(|(i, label)| c.add(Kind::LevelInput1, 10 * i as i16, label, vec![]))
+        .collect();
+    let address = c.add(Kind::CcInput, 20, "address", vec![]);
+    let value = c.add(Kind::CcInput, 30, "value", vec![]);
+    let ram = c.add(Kind::Ram, 40, "", vec![16]);
+    let out = c.add(Kind::CcOutput, 50, "out", vec![]);
+    c.connect((inputs[0], "out"), (ram, "save"));
+    c.connect((inputs[1], "out"), (ram, "load"));
+    c.connect((address, "out"), (ram, "address"));
+    c.connect((value, "out"), (ram, "value"));
+    c.connect((ram, "out"), (out, "in"));
+
+    let mut sim = Simulator::new(&c.0);
+    assert_eq!(sim.memory(ram).unwrap().len(), 16);
+    sim.step(&[("save", 1), ("address", 3), ("value", 99)])
+        .unwrap();
+    let read = sim
+        .step(&[("save", 0), ("load", 1), ("address", 19)])
+        .unwrap();
+    assert_eq!(read["out"], Some(99));
+    assert_eq!(sim.memory(ram).unwrap().read(3), 99);
+}
+
+#[test]
+fn roms_load_from_files() {
+    let mut c = Circuit::default();
+    let on = c.add(Kind::On, 0, "", vec![]);
+    let address = c.add(Kind::CcInput, 10, "address", vec![]);
+    let rom = c.add(Kind::Rom, 20, "", vec![4]);
+    let out = c.add(Kind::CcOutput, 30, "out", vec![]);
+    c.connect((on, "out"), (rom, "load"));
+    c.connect((address, "out"), (rom, "address"));
+    c.connect((rom, "out"), (out, "in"));
+
+    let path = std::env::temp_dir().join(format!("tc_rom_{}.bin", std::process::id()));
+    std::fs::write(&path, [10, 20, 30]).unwrap();
+    let mut sim = Simulator::new(&c.0);
+    sim.load_memory_file(rom, &path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let values: Vec<_> = (0..4)
+        .map(|a| sim.step(&[("address", a)]).unwrap()["out"].unwrap())
+        .collect();
+    assert_eq!(values, [10, 20, 30, 0]);
+    assert!(matches!(
+        sim.load_memory(on, &[1]),
+        Err(Error::NoSuchDevice(_))
+    ));
+}
+
+#[test]
+fn links_join_memories_in_either_direction() {
+    let mut c = Circuit::default();
+    let first = c.add(Kind::Ram, 0, "", vec![8]);
+    let second = c.add(Kind::Rom, 10, "", vec![]);
+    let third = c.add(Kind::RamLatency, 20, "", vec![]);
+    let assembler = c.add(Kind::Assembler, 30, "", vec![]);
+    let alone = c.add(Kind::Ram, 40, "", vec![8]);
+    // The lower index links to the higher one, and the chain continues
+    // through the assembler.
+    c.link(first, second);
+    c.link(assembler, second);
+    c.link(assembler, third);
+
+    let mut sim = Simulator::new(&c.0);
+    sim.load_memory(third, &[7]).unwrap();
+    for memory in [first, second, third, assembler] {
+        assert_eq!(sim.memory(memory).unwrap().read(0), 7, "{}", memory);
+        assert_eq!(sim.memory(memory).unwrap().len(), 8);
+    }
+    assert_eq!(sim.memory(alone).unwrap().read(0), 0);
+}
+
+#[test]
+fn memory_sizes_are_capped() {
+    let mut c = Circuit::default();
+    let ram = c.add(Kind::Ram, 0, "", vec![u64::MAX]);
+    let sim = Simulator::new(&c.0);
+    assert_eq!(sim.memory(ram).unwrap().len() as u64, MAX_MEMORY_WORDS);
+}
+
+#[test]
+fn linked_memories_and_consoles_share_storage() {
+    let mut c = Circuit::default();
+    let ram = c.add(Kind::Ram, 0, "", vec![8]);
+    let mirror = c.add(Kind::RamFast, 10, "", vec![]);
+    let console = c.add(Kind::Console, 20, "", vec![]);
+    let screen = c.add(Kind::PixelScreen, 30, "", vec![]);
+    c.link(mirror, ram);
+    c.link(console, ram);
+    c.link(screen, ram);
+
+    let mut sim = Simulator::new(&c.0);
+    sim.load_memory(mirror, b"hi").unwrap();
+    assert_eq!(sim.memory(ram).unwrap().read(1), u64::from(b'i'));
+    assert_eq!(sim.console_text(console).as_deref(), Some("hi"));
+    assert_eq!(sim.screen(screen).unwrap()[..3], [104, 105, 0]);
+    assert_eq!(sim.console_text(ram), None);
+}
+
+#[test]
+fn keyboards_feed_keys_until_halted() {
+    let mut c = Circuit::default();
+    let on = c.add(Kind::On, 0, "", vec![]);
+    let keyboard = c.add(Kind::Keyboard, 10, "", vec![]);
+    let key = c.add(Kind::CcOutput, 20, "key", vec![]);
+    let stop = c.add(Kind::LevelInput1, 30, "stop", vec![]);
+    let halt = c.add(Kind::Halt, 40, "", vec![]);
+    c.connect((on, "out"), (keyboard, "enable"));
+    c.connect((keyboard, "out"), (key, "in"));
+    c.connect((stop, "out"), (halt, "enable"));
+
+    let mut sim = Simulator::new(&c.0);
+    sim.press_keys(keyboard, b"ab").unwrap();
+    let keys: Vec<_> = (0..3)
+        .map(|_| sim.step(&[]).unwrap()["key"].unwrap())
+        .collect();
+    assert_eq!(keys, [97, 98, 0]);
+
+    assert_eq!(sim.run_until_halt(5), 5);
+    sim.set_input("stop", 1).unwrap();
+    assert_eq!(sim.run_until_halt(5), 1);
+    assert!(sim.halted());
+}
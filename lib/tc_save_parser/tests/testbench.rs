@@ -0,0 +1,102 @@
+use tc_save_parser::{Error, Kind, Point, Wire, testbench::Testbench, v9};
+
+/// `sum = a + b` and `carry = a & b` on 8-bit words.
+fn adder() -> v9::CircuitData {
+    let mut circuit = v9::CircuitData::default();
+    let mut add = |kind: Kind, x: i16, label: &str| {
+        circuit.components.push(v9::Component {
+            kind,
+            position: Point::new(x, 0),
+            custom_string: label.into(),
+            word_size: 8,
+            ..Default::default()
+        });
+    };
+    add(Kind::CcInput, 0, "a");
+    add(Kind::CcInput, 10, "b");
+    add(Kind::Add, 20, "");
+    add(Kind::CcOutput, 30, "sum");
+    add(Kind::AndWord, 40, "");
+    add(Kind::CcOutput, 50, "and");
+
+    let pin = |circuit: &v9::CircuitData, component: usize, name: &str| {
+        let c: &v9::Component = &circuit.components[component];
+        let offset = c.kind.pin(name).unwrap().offset;
+        Point::new(c.position.x + offset.x, c.position.y + offset.y)
+    };
+    let links = [
+        ((0, "out"), (2, "a")),
+        ((1, "out"), (2, "b")),
+        ((2, "out"), (3, "in")),
+        ((0, "out"), (4, "a")),
+        ((1, "out"), (4, "b")),
+        ((4, "out"), (5, "in")),
+    ];
+    for (lane, (from, to)) in links.into_iter().enumerate() {
+        let (a, b) = (pin(&circuit, from.0, from.1), pin(&circuit, to.0, to.1));
+        let lane = 20 + lane as i16;
+        let points = [a, Point::new(a.x, lane), Point::new(b.x, lane), b];
+        circuit.wires.push(Wire::from_polyline(&points).unwrap());
+    }
+    circuit
+}
+
+#[test]
+fn csv_vectors_report_failing_bits() {
+    let bench = Testbench::from_csv(
+        "a, b, sum, and\n\
+         1, 2, 3, 0\n\
+         # a comment\n\
+         0xff, 1, 0, 1\n\
+         0b1100, 0b1010, 0x10, \n",
+    )
+    .unwrap();
+    assert_eq!(bench.vectors.len(), 3);
+    assert!(!bench.vectors[2].values.contains_key("and"));
+
+    let report = bench.run(&adder()).unwrap();
+    assert!(!report.passed());
+    let failures: Vec<_> = report.failures().collect();
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].index, 2);
+    let mismatch = &failures[0].mismatches[0];
+    assert_eq!(mismatch.label, "sum");
+    assert_eq!(mismatch.actual, Some(22));
+    assert_eq!(mismatch.failing_bits(), [1, 2]);
+
+    let text = report.to_string();
+    assert!(text.contains("sum: expected 0x10, got 0x16, failing bits [1, 2]"));
+    assert!(text.ends_with("2 of 3 vectors passed"));
+}
+
+#[test]
+fn toml_vectors_pass() {
+    let bench = Testbench::from_toml(
+        r#"
+        [[vector]]
+        a = 3
+        b = 4
+        sum = 7
+        and = 0
+
+        [[vector]]
+        a = "0x0f"
+        b = -1
+        sum = 14
+        and = 15
+        "#,
+    )
+    .unwrap();
+    let report = bench.run(&adder()).unwrap();
+    assert!(report.passed(), "{}", report);
+}
+
+#[test]
+fn unknown_labels_and_bad_values_are_errors() {
+    let bench = Testbench::from_csv("a,carry\n1,0\n").unwrap();
+    assert!(matches!(bench.run(&adder()), Err(Error::NoSuchPort(l)) if l == "carry"));
+    assert!(matches!(
+        Testbench::from_csv("a,b\n1,2\nx,3\n"),
+        Err(Error::InvalidVectors { line: 3, .. })
+    ));
+}
@@ -0,0 +1,155 @@
+use tc_save_parser::{
+    CircuitDataFile, CircuitDataVersion, Error, Kind, Point, WireBuilder,
+    text::{data_to_text, text_to_data},
+    v9,
+};
+
+fn circuit() -> v9::CircuitData {
+    let mut ram = v9::Component {
+        kind: Kind::Ram,
+        position: Point::new(-4, 7),
+        rotation: 3,
+        permanent_id: u64::MAX,
+        custom_string: "memory \"1\"".into(),
+        settings: vec![0, u64::MAX],
+        word_size: 64,
+        ..Default::default()
+    };
+    ram.linked_components.linked_components = vec![7, 8];
+    ram.linked_components.dummy0 = 5;
+    let mut custom = v9::Component {
+        kind: Kind::Custom,
+        permanent_id: 2,
+        ..Default::default()
+    };
+    custom.custom.id = 0x1234_5678_9abc_def0;
+    v9::CircuitData {
+        gate: -12,
+        description: "näive ✓".into(),
+        player_data: vec![0, 1, 255],
+        components: vec![
+            ram,
+            custom,
+            v9::Component {
+                kind: Kind::Unmapped(900),
+                ..Default::default()
+            },
+        ],
+        wires: vec![
+            WireBuilder::new(Point::new(1, 2))
+                .right(40)
+                .up_left(3)
+                .color(4)
+                .comment("bus")
+                .build(),
+        ],
+        ..Default::default()
+    }
+}
+
+fn file(version: u8, circuit: CircuitDataVersion) -> Vec<u8> {
+    CircuitDataFile { version, circuit }.to_bytes().unwrap()
+}
+
+#[test]
+fn text_converts_back_to_the_same_bytes() {
+    let v9 = CircuitDataFile {
+        version: 9,
+        circuit: CircuitDataVersion::V9(circuit()),
+    };
+    let (v8, _) = v9.clone().convert_to(8).unwrap();
+    let (v7, _) = v9.clone().convert_to(7).unwrap();
+    for cdf in [v9, v8, v7] {
+        let data = cdf.to_bytes().unwrap();
+        let text = data_to_text(&data).unwrap();
+        assert_eq!(text_to_data(&text).unwrap(), data);
+    }
+}
+
+#[test]
+fn fixtures_convert_back_to_the_same_bytes() {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data");
+    let mut fixtures = 0;
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension() != Some("data".as_ref()) {
+            continue;
+        }
+        let data = std::fs::read(&path).unwrap();
+        let text = data_to_text(&data).unwrap();
+        assert_eq!(text_to_data(&text).unwrap(), data, "{}", path.display());
+        fixtures += 1;
+    }
+    assert_ne!(fixtures, 0);
+}
+
+/// Compressed with literals only, unlike this crate would, with bytes after
+/// the circuit data.
+const LITERAL_PAYLOAD: &[u8] = include_bytes!("data/literal_payload.data");
+
+#[test]
+fn edited_text_is_compressed_again() {
+    let text = data_to_text(LITERAL_PAYLOAD).unwrap();
+    assert!(text.contains("\"compressed\": \"e101ec"));
+    assert!(text.contains("\"trailing\": [\n    0,\n    7,"));
+
+    let edited = text.replacen("\"fixture\"", "\"edited\"", 1);
+    let data = text_to_data(&edited).unwrap();
+    assert_ne!(data.len(), LITERAL_PAYLOAD.len());
+    let payload = CircuitDataFile::debug_dump_bytes(&data).unwrap();
+    assert!(payload.ends_with(b"\0\x07end"));
+    let CircuitDataVersion::V9(circuit) = CircuitDataFile::from_bytes(&data).unwrap().circuit
+    else {
+        panic!("not version 9");
+    };
+    assert_eq!(circuit.description.as_str(), Some("edited"));
+}
+
+#[test]
+fn text_is_readable() {
+    let text = data_to_text(&file(9, CircuitDataVersion::V9(circuit()))).unwrap();
+    assert!(text.contains(r#""kind": "Ram""#));
+    assert!(text.contains(r#""Unmapped": 900"#));
+    assert!(text.contains(r#""custom_string": "memory \"1\"""#));
+    assert!(text.contains(r#""description": "näive ✓""#));
+    assert!(text.contains("\"direction\": \"UpLeft\",\n"));
+    assert!(!text.contains("_len"));
+}
+
+#[test]
+fn strings_that_are_not_utf8_round_trip() {
+    let mut circuit = circuit();
+    circuit.components[0].custom_string = vec![b'a', 0xff, 0x80].into();
+    circuit.wires[0].comment = vec![0xc3].into();
+    let data = file(9, CircuitDataVersion::V9(circuit.clone()));
+    let text = data_to_text(&data).unwrap();
+    assert!(text.contains("\"custom_string\": [\n"));
+    assert_eq!(text_to_data(&text).unwrap(), data);
+    let CircuitDataVersion::V9(read) = CircuitDataFile::from_text(&text).unwrap().circuit else {
+        panic!("not version 9");
+    };
+    assert_eq!(read, circuit);
+}
+
+#[test]
+fn unknown_versions_keep_their_payload() {
+    let data = file(12, CircuitDataVersion::Unknown(vec![1, 2, 3]));
+    let text = data_to_text(&data).unwrap();
+    assert_eq!(text_to_data(&text).unwrap(), data);
+}
+
+#[test]
+fn rejects_inconsistent_text() {
+    let text = data_to_text(&file(9, CircuitDataVersion::V9(circuit()))).unwrap();
+    let mislabelled = text.replacen("\"version\": 9", "\"version\": 8", 1);
+    assert!(matches!(
+        text_to_data(&mislabelled),
+        Err(Error::InvalidText(_))
+    ));
+    let too_long = text.replacen("\"length\": 31", "\"length\": 32", 1);
+    assert_ne!(too_long, text);
+    assert!(matches!(
+        text_to_data(&too_long),
+        Err(Error::InvalidText(_))
+    ));
+}
@@ -0,0 +1,112 @@
+use tc_save_parser::{
+    Kind, Point, Wire, WireBuilder, WireDirection,
+    transform::{Mirror, Rotation, Transform},
+    v9,
+};
+
+fn directions(wire: &Wire) -> Vec<(WireDirection, u8)> {
+    wire.segments
+        .iter()
+        .map(|s| (s.direction(), s.length()))
+        .collect()
+}
+
+#[test]
+fn points_rotate_clockwise_around_a_center() {
+    let center = Point::new(1, 1);
+    let mut p = Point::new(3, 1);
+    p.rotate(Rotation::R90, center);
+    assert_eq!(p, Point::new(1, 3));
+    p.rotate(Rotation::R180, center);
+    assert_eq!(p, Point::new(1, -1));
+    p.rotate(Rotation::R270, center);
+    assert_eq!(p, Point::new(-1, 1));
+    p.mirror(Mirror::Horizontal, center);
+    assert_eq!(p, Point::new(3, 1));
+}
+
+#[test]
+fn points_wrap_at_the_edges_of_the_grid() {
+    let mut p = Point::new(i16::MAX, i16::MIN);
+    p.translate(Point::new(1, -1));
+    assert_eq!(p, Point::new(i16::MIN, i16::MAX));
+
+    let center = Point::new(32760, 32760);
+    let mut p = Point::new(i16::MAX, 32760);
+    p.rotate(Rotation::R90, center);
+    assert_eq!(p, Point::new(32760, i16::MAX));
+    p.rotate(Rotation::R90, center);
+    assert_eq!(p, Point::new(32753, 32760));
+
+    let mut p = Point::new(i16::MIN, 5);
+    p.rotate(Rotation::R180, Point::new(0, 0));
+    assert_eq!(p, Point::new(i16::MIN, -5));
+    p.mirror(Mirror::Horizontal, Point::new(0, 0));
+    assert_eq!(p, Point::new(i16::MIN, -5));
+    p.mirror(Mirror::Vertical, Point::new(i16::MAX, i16::MAX));
+    assert_eq!(p, Point::new(i16::MIN, 3));
+}
+
+#[test]
+fn rotations_compose_and_wrap() {
+    assert_eq!(Rotation::R270 + Rotation::R180, Rotation::R90);
+    assert_eq!(-Rotation::R90, Rotation::R270);
+    assert_eq!(Rotation::from(5), Rotation::R90);
+    assert_eq!(u8::from(Rotation::R180), 2);
+}
+
+#[test]
+fn wire_segments_follow_the_transform() {
+    let mut wire = WireBuilder::new(Point::new(0, 0))
+        .right(2)
+        .down_right(1)
+        .up(3)
+        .right(0)
+        .build();
+    wire.rotate(Rotation::R90, Point::new(0, 0));
+    assert_eq!(
+        directions(&wire),
+        [
+            (WireDirection::Down, 2),
+            (WireDirection::DownLeft, 1),
+            (WireDirection::Right, 3),
+            (WireDirection::Right, 0),
+        ]
+    );
+
+    wire.mirror(Mirror::Vertical, Point::new(0, 5));
+    assert_eq!(wire.start, Point::new(0, 10));
+    assert_eq!(
+        directions(&wire),
+        [
+            (WireDirection::Up, 2),
+            (WireDirection::UpLeft, 1),
+            (WireDirection::Right, 3),
+            (WireDirection::Right, 0),
+        ]
+    );
+}
+
+#[test]
+fn a_rotated_selection_keeps_its_shape() {
+    let mut components: Vec<_> = [Point::new(0, 0), Point::new(4, 0)]
+        .into_iter()
+        .map(|position| v9::Component {
+            kind: Kind::AndBit,
+            position,
+            rotation: 3,
+            ..Default::default()
+        })
+        .collect();
+    components.rotate(Rotation::R90, Point::new(2, 0));
+    assert_eq!(components[0].position, Point::new(2, -2));
+    assert_eq!(components[1].position, Point::new(2, 2));
+    assert!(components.iter().all(|c| c.rotation() == Rotation::R0));
+
+    components.translate(Point::new(1, 1));
+    assert_eq!(components[0].position, Point::new(3, -1));
+
+    components[0].mirror(Mirror::Horizontal, Point::new(0, 0));
+    assert_eq!(components[0].position, Point::new(-3, -1));
+    assert_eq!(components[0].rotation(), Rotation::R180);
+}
@@ -0,0 +1,89 @@
+use tc_save_parser::{
+    CDString, Error, Kind, Point, Wire,
+    sim::vcd::{self, Recorder},
+    v9,
+};
+
+fn component(kind: Kind, x: i16, label: &str) -> v9::Component {
+    v9::Component {
+        kind,
+        position: Point::new(x, 0),
+        custom_string: label.into(),
+        word_size: 8,
+        ..Default::default()
+    }
+}
+
+fn pin(c: &v9::Component, name: &str) -> Point {
+    let offset = c.kind.pin(name).unwrap().offset;
+    Point::new(c.position.x + offset.x, c.position.y + offset.y)
+}
+
+/// A counter feeding an output, with a bit probe on an unrelated switch
+/// and a commented wire.
+fn counter() -> v9::CircuitData {
+    let mut circuit = v9::CircuitData {
+        components: vec![
+            component(Kind::Counter, 0, ""),
+            component(Kind::CcOutput, 10, "count"),
+            component(Kind::LevelInput1, 20, "enable"),
+            component(Kind::ProbeWireBit, 30, ""),
+        ],
+        ..Default::default()
+    };
+    let links = [((0, "out"), (1, "in")), ((2, "out"), (3, "in"))];
+    for (lane, (from, to)) in links.into_iter().enumerate() {
+        let a = pin(&circuit.components[from.0], from.1);
+        let b = pin(&circuit.components[to.0], to.1);
+        let lane = 20 + lane as i16;
+        let points = [a, Point::new(a.x, lane), Point::new(b.x, lane), b];
+        circuit.wires.push(Wire::from_polyline(&points).unwrap());
+    }
+    circuit.wires[0].comment = CDString::from("counter bus");
+    circuit
+}
+
+#[test]
+fn signals_are_named_by_labels_comments_and_probes() {
+    let circuit = counter();
+    let sim = tc_save_parser::sim::Simulator::new(&circuit);
+    let signals = vcd::signals(&circuit, sim.netlist());
+    let names: Vec<_> = signals.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, ["count", "enable", "probe3", "counter_bus"]);
+    assert_eq!(signals[0].net, signals[3].net);
+    assert_eq!(signals[0].width, 8);
+    assert_eq!(signals[1].width, 1);
+}
+
+#[test]
+fn records_value_changes() {
+    let recorder = vcd::record(&counter(), &[("enable", 1)], 3, &["count", "probe3"]).unwrap();
+    assert_eq!(recorder.values(0).collect::<Vec<_>>(), [0, 1, 2]);
+    assert_eq!(recorder.values(1).collect::<Vec<_>>(), [1, 1, 1]);
+
+    let text = recorder.to_vcd();
+    assert!(text.contains("$var reg 8 ! count $end"));
+    assert!(text.contains("$var wire 1 \" probe3 $end"));
+    assert!(text.contains("#0\n$dumpvars\nb0 !\n1\"\n$end\n#1\nb1 !\n#2\nb10 !\n#3\n"));
+}
+
+#[test]
+fn aliases_share_an_identifier_code() {
+    let circuit = counter();
+    let sim = tc_save_parser::sim::Simulator::new(&circuit);
+    let signals = vcd::signals(&circuit, sim.netlist());
+    let mut recorder = Recorder::new(signals);
+    recorder.sample(&sim);
+    let text = recorder.to_vcd();
+    assert!(text.contains("$var reg 8 ! count $end"));
+    assert!(text.contains("$var reg 8 ! counter_bus $end"));
+    assert_eq!(text.matches("b0 !").count(), 1);
+}
+
+#[test]
+fn unknown_signals_are_errors() {
+    assert!(matches!(
+        vcd::record(&counter(), &[], 1, &["nope"]),
+        Err(Error::NoSuchSignal(name)) if name == "nope"
+    ));
+}
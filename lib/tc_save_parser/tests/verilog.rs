@@ -0,0 +1,140 @@
+use tc_save_parser::{
+    Kind, Point, Wire,
+    catalog::{PinDirection, Width},
+    netlist::PinDef,
+    v9,
+    verilog::{self, VerilogExporter},
+};
+
+fn component(kind: Kind, x: i16, y: i16, label: &str) -> v9::Component {
+    v9::Component {
+        kind,
+        position: Point::new(x, y),
+        custom_string: label.into(),
+        word_size: 8,
+        ..Default::default()
+    }
+}
+
+fn pin(c: &v9::Component, name: &str) -> Point {
+    let offset = c.kind.pin(name).unwrap().offset;
+    Point::new(c.position.x + offset.x, c.position.y + offset.y)
+}
+
+/// Wires `from` to `to` through a horizontal lane at `y = lane`.
+fn link(circuit: &mut v9::CircuitData, from: (usize, &str), to: (usize, &str), lane: i16) {
+    let a = pin(&circuit.components[from.0], from.1);
+    let b = pin(&circuit.components[to.0], to.1);
+    let points = [a, Point::new(a.x, lane), Point::new(b.x, lane), b];
+    circuit.wires.push(Wire::from_polyline(&points).unwrap());
+}
+
+/// `sum = a + b`, and `late` is `sum` three ticks ago.
+fn adder() -> v9::CircuitData {
+    let mut circuit = v9::CircuitData {
+        components: vec![
+            component(Kind::CcInput, 0, 0, "a"),
+            component(Kind::VerilogInput, 0, 10, "b"),
+            component(Kind::Add, 20, 0, ""),
+            component(Kind::CcOutput, 40, 0, "sum"),
+            component(Kind::ConfigDelay, 40, 10, ""),
+            component(Kind::VerilogOutput, 60, 10, "late"),
+        ],
+        ..Default::default()
+    };
+    circuit.components[4].settings = vec![3];
+    link(&mut circuit, (0, "out"), (2, "a"), -20);
+    link(&mut circuit, (1, "out"), (2, "b"), -21);
+    link(&mut circuit, (2, "out"), (3, "in"), -22);
+    link(&mut circuit, (3, "in"), (4, "a"), -23);
+    link(&mut circuit, (4, "out"), (5, "in"), -24);
+    circuit
+}
+
+#[test]
+fn ports_come_from_inputs_and_outputs() {
+    let text = verilog::to_verilog(&adder(), "adder");
+    assert!(text.starts_with(
+        "module adder (\n    input clk,\n    input [7:0] a,\n    input [7:0] b,\n    output [7:0] sum,\n    output [7:0] late\n);\n"
+    ));
+    assert!(text.contains("assign c0_out = a;"));
+    assert!(text.contains("assign sum = n2;"));
+    assert!(text.ends_with("endmodule\n"));
+}
+
+#[test]
+fn components_become_assignments_and_registers() {
+    let text = verilog::to_verilog(&adder(), "adder");
+    assert!(text.contains("wire [7:0] n0;"));
+    assert!(text.contains("assign n0 = c0_out;"));
+    assert!(text.contains("assign {c2_carry_out, c2_out} = n0 + n1 + 1'd0;"));
+    // The delay is a bit wide, so it reads the low bit of the sum.
+    assert!(text.contains("reg c4_state [0:2];"));
+    assert!(text.contains("always @(posedge clk) c4_state[0] <= n2[0];"));
+    assert!(text.contains("assign c4_out = c4_state[2];"));
+}
+
+#[test]
+fn custom_components_become_instances() {
+    let mut circuit = v9::CircuitData {
+        components: vec![
+            component(Kind::CcInput, 0, 0, "x y"),
+            component(Kind::Custom, 20, 0, ""),
+            component(Kind::Ram, 40, 0, ""),
+        ],
+        ..Default::default()
+    };
+    circuit.components[1].custom.id = 42;
+    let a = pin(&circuit.components[0], "out");
+    circuit
+        .wires
+        .push(Wire::from_polyline(&[a, Point::new(19, 0)]).unwrap());
+
+    let text = VerilogExporter::new(&circuit, "top")
+        .custom_pins(|_| {
+            Some(vec![
+                PinDef {
+                    name: "in".into(),
+                    offset: Point::new(-1, 0),
+                    direction: PinDirection::Input,
+                    width: Width::Word,
+                },
+                PinDef {
+                    name: "result".into(),
+                    offset: Point::new(1, 0),
+                    direction: PinDirection::Output,
+                    width: Width::Bits(4),
+                },
+            ])
+        })
+        .export();
+    assert!(text.starts_with("module top (\n    input clk,\n    input [7:0] \\xy \n);"));
+    assert!(text.contains("wire [3:0] c1_result;"));
+    assert!(text.contains("custom_42 c1 (.clk(clk), .in(n0), .result(c1_result));"));
+    assert!(text.contains("// Not exported: c2: "));
+    assert_eq!(verilog::custom_module_name(42), "custom_42");
+}
+
+#[test]
+fn port_names_never_collide() {
+    let circuit = v9::CircuitData {
+        components: vec![
+            component(Kind::CcInput, 0, 0, "n0"),
+            component(Kind::CcInput, 0, 10, "c1_out"),
+            component(Kind::CcInput, 0, 20, "clk"),
+            component(Kind::CcInput, 0, 30, "x y"),
+            component(Kind::CcInput, 0, 40, "xy"),
+            component(Kind::CcInput, 0, 50, " "),
+            component(Kind::CcOutput, 20, 0, "_n0"),
+            component(Kind::CcOutput, 20, 10, "c"),
+        ],
+        ..Default::default()
+    };
+    let text = verilog::to_verilog(&circuit, "");
+    assert!(text.starts_with(
+        "module _ (\n    input clk,\n    input [7:0] _n0,\n    input [7:0] _c1_out,\n    input [7:0] _clk,\n    input [7:0] \\xy ,\n    input [7:0] xy_1,\n    input [7:0] in5,\n    output [7:0] _n0_1,\n    output [7:0] c\n);\n"
+    ));
+    assert!(text.contains("assign c0_out = _n0;"));
+    assert!(text.contains("assign c2_out = _clk;"));
+    assert!(text.contains("assign _n0_1 = 8'd0;"));
+}
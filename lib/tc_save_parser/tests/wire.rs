@@ -0,0 +1,90 @@
+use tc_save_parser::{Error, Point, Wire, WireBuilder, WireDirection};
+
+fn segments(wire: &Wire) -> Vec<(WireDirection, u8)> {
+    wire.segments
+        .iter()
+        .map(|s| (s.direction(), s.length()))
+        .collect()
+}
+
+#[test]
+fn cells_corners_and_endpoints() {
+    let wire = WireBuilder::new(Point::new(1, 1))
+        .right(2)
+        .right(1)
+        .down_left(2)
+        .build();
+    assert_eq!(
+        wire.cells(),
+        [
+            Point::new(1, 1),
+            Point::new(2, 1),
+            Point::new(3, 1),
+            Point::new(4, 1),
+            Point::new(3, 2),
+            Point::new(2, 3),
+        ]
+    );
+    assert_eq!(
+        wire.corners(),
+        [Point::new(1, 1), Point::new(4, 1), Point::new(2, 3)]
+    );
+    assert_eq!(wire.endpoints(), (Point::new(1, 1), Point::new(2, 3)));
+}
+
+#[test]
+fn the_builder_ends_every_wire_with_one_terminator() {
+    // A zero-length run would read back as the end of the wire, so the
+    // builder skips it and `build` adds the terminator instead.
+    let wire = WireBuilder::new(Point::new(0, 0))
+        .right(0)
+        .up(2)
+        .left(0)
+        .build();
+    assert_eq!(
+        segments(&wire),
+        [(WireDirection::Up, 2), (WireDirection::Right, 0)]
+    );
+    assert_eq!(
+        segments(&WireBuilder::new(Point::new(0, 0)).build()),
+        [(WireDirection::Right, 0)]
+    );
+}
+
+#[test]
+fn polylines_round_trip_through_corners() {
+    let points = [
+        Point::new(0, 0),
+        Point::new(0, -5),
+        Point::new(3, -2),
+        Point::new(-4, -2),
+    ];
+    let wire = Wire::from_polyline(&points).unwrap();
+    assert_eq!(wire.corners(), points);
+    assert!(wire.segments.last().unwrap().is_terminator());
+}
+
+#[test]
+fn long_runs_are_split() {
+    let wire = Wire::from_polyline(&[Point::new(0, 0), Point::new(0, 70)]).unwrap();
+    assert_eq!(
+        segments(&wire),
+        [
+            (WireDirection::Down, 31),
+            (WireDirection::Down, 31),
+            (WireDirection::Down, 8),
+            (WireDirection::Right, 0),
+        ]
+    );
+    assert_eq!(wire.corners(), [Point::new(0, 0), Point::new(0, 70)]);
+
+    let built = WireBuilder::new(Point::new(0, 0)).left(40).build();
+    assert_eq!(built.end(), Point::new(-40, 0));
+    assert_eq!(built.segments.len(), 3);
+}
+
+#[test]
+fn crooked_polylines_are_rejected() {
+    let err = Wire::from_polyline(&[Point::new(0, 0), Point::new(1, 2)]).unwrap_err();
+    assert!(matches!(err, Error::NotOctilinear { .. }));
+}
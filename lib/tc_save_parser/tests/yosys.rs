@@ -0,0 +1,322 @@
+use tc_save_parser::{
+    CircuitDataFile, Error, Kind, Point, Wire,
+    sim::Simulator,
+    transform::Transform,
+    v9,
+    yosys::{self, Netlist, PortDirection},
+};
+
+fn adder() -> Netlist {
+    Netlist::from_json(include_str!("data/adder.json")).unwrap()
+}
+
+#[test]
+fn imports_ports_cells_and_named_nets() {
+    let netlist = adder();
+    let (name, module) = netlist.top().unwrap();
+    assert_eq!(name, "adder");
+    let circuit = yosys::import(module).unwrap();
+    assert_ne!(circuit.custom_id, 0);
+
+    let labels = |kind: Kind| -> Vec<String> {
+        let mut labels: Vec<_> = circuit
+            .components
+            .iter()
+            .filter(|c| c.kind == kind)
+            .map(|c| c.custom_string.to_string_lossy().into_owned())
+            .collect();
+        labels.sort();
+        labels
+    };
+    assert_eq!(
+        labels(Kind::CcInput),
+        ["a[0]", "a[1]", "b[0]", "b[1]", "clk", "sel"]
+    );
+    assert_eq!(
+        labels(Kind::CcOutput),
+        ["held", "one", "pick", "sum[0]", "sum[1]", "sum[2]"]
+    );
+    assert_eq!(labels(Kind::DelayLineBit), ["$q"]);
+    assert_eq!(labels(Kind::On).len(), 1);
+    assert!(circuit.wires.iter().any(|w| w.comment == "carry0"));
+    assert!(
+        !circuit
+            .wires
+            .iter()
+            .any(|w| w.comment.as_bytes().starts_with(b"$"))
+    );
+
+    let sim = Simulator::new(&circuit);
+    assert!(sim.netlist().floating_wire_ends.is_empty());
+    let unconnected: Vec<_> = sim
+        .netlist()
+        .unconnected_pins
+        .iter()
+        .map(|p| {
+            circuit.components[p.component]
+                .custom_string
+                .as_str()
+                .unwrap()
+        })
+        .collect();
+    assert_eq!(unconnected, ["clk"]);
+}
+
+#[test]
+fn imported_logic_simulates_like_the_netlist() {
+    let circuit = yosys::import(adder().module("adder").unwrap()).unwrap();
+    let mut sim = Simulator::new(&circuit);
+    for a in 0..4 {
+        for b in 0..4 {
+            let outputs = sim
+                .step(&[
+                    ("a[0]", a & 1),
+                    ("a[1]", a >> 1),
+                    ("b[0]", b & 1),
+                    ("b[1]", b >> 1),
+                ])
+                .unwrap();
+            let sum: u64 = (0..3)
+                .map(|i| outputs[&format!("sum[{}]", i)].unwrap() << i)
+                .sum();
+            assert_eq!(sum, a + b, "{} + {}", a, b);
+            assert_eq!(outputs["one"], Some(1));
+        }
+    }
+
+    // `held` takes `pick` on ticks where `sel` is high, and keeps it
+    // otherwise.
+    let step = |sim: &mut Simulator, a, b, sel| {
+        let outputs = sim.step(&[("a[0]", a), ("b[0]", b), ("sel", sel)]).unwrap();
+        (outputs["pick"].unwrap(), outputs["held"].unwrap())
+    };
+    assert_eq!(step(&mut sim, 1, 0, 0), (1, 0));
+    assert_eq!(step(&mut sim, 1, 0, 1), (0, 0));
+    assert_eq!(step(&mut sim, 0, 1, 1), (1, 0));
+    assert_eq!(step(&mut sim, 0, 0, 0), (0, 1));
+    assert_eq!(step(&mut sim, 0, 0, 0), (0, 1));
+}
+
+#[test]
+fn saves_a_component_factory_circuit() {
+    let circuit = yosys::import(adder().module("adder").unwrap()).unwrap();
+    let dir = std::env::temp_dir().join(format!("tc_yosys_{}", std::process::id()));
+    let path = yosys::save_component(&circuit, dir.join("adder")).unwrap();
+    let (loaded, _) = CircuitDataFile::load(&path).unwrap().into_latest().unwrap();
+    assert_eq!(loaded.custom_id, circuit.custom_id);
+    assert_eq!(loaded.components.len(), circuit.components.len());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn unsupported_cells_are_errors() {
+    let netlist = Netlist::from_json(
+        r#"{"modules": {"m": {"cells": {"add": {"type": "$add", "connections": {}}}}}}"#,
+    )
+    .unwrap();
+    assert!(matches!(
+        netlist.module("nope"),
+        Err(Error::NoSuchModule(_))
+    ));
+    assert!(matches!(
+        yosys::import(netlist.module("m").unwrap()),
+        Err(Error::UnsupportedCell { cell, cell_type }) if cell == "add" && cell_type == "$add"
+    ));
+}
+
+#[test]
+fn reimport_keeps_matched_layout_and_rewires_changes() {
+    let mut circuit = yosys::import(adder().module("adder").unwrap()).unwrap();
+    // Tidy by hand: move everything, wires included.
+    circuit.components.translate(Point::new(5, -3));
+    for wire in &mut circuit.wires {
+        wire.translate(Point::new(5, -3));
+    }
+    let find = |circuit: &v9::CircuitData, kind: Kind, label: &str| {
+        circuit
+            .components
+            .iter()
+            .find(|c| c.kind == kind && c.custom_string == label)
+            .cloned()
+    };
+    let before = find(&circuit, Kind::XorBit, "$x0").unwrap();
+    let old_carry = find(&circuit, Kind::OrBit, "$c1").unwrap();
+
+    let v2 = Netlist::from_json(include_str!("data/adder_v2.json")).unwrap();
+    let (updated, report) = yosys::reimport(&circuit, v2.module("adder").unwrap()).unwrap();
+    assert_eq!(updated.custom_id, circuit.custom_id);
+
+    let after = find(&updated, Kind::XorBit, "$x0").unwrap();
+    assert_eq!(after.permanent_id, before.permanent_id);
+    assert_eq!(after.position, before.position);
+    assert!(find(&updated, Kind::OrBit, "$c1").is_none());
+    assert!(find(&updated, Kind::CcOutput, "held").is_none());
+    assert!(report.removed.contains(&old_carry.permanent_id));
+    let added: Vec<_> = updated
+        .components
+        .iter()
+        .filter(|c| report.added.contains(&c.permanent_id))
+        .map(|c| c.custom_string.as_str().unwrap())
+        .collect();
+    assert_eq!(added, ["$c1", "$n", "nand"]);
+    assert!(report.kept_wires > 0 && report.removed_wires > 0 && report.added_wires > 0);
+
+    let mut sim = Simulator::new(&updated);
+    assert!(sim.netlist().floating_wire_ends.is_empty());
+    let outputs = sim
+        .step(&[
+            ("a[0]", 1),
+            ("a[1]", 1),
+            ("b[0]", 1),
+            ("b[1]", 1),
+            ("sel", 1),
+        ])
+        .unwrap();
+    assert_eq!(outputs["sum[0]"], Some(0));
+    assert_eq!(outputs["sum[1]"], Some(1));
+    assert_eq!(outputs["sum[2]"], Some(1));
+    assert_eq!(outputs["nand"], Some(0));
+    assert_eq!(outputs["pick"], Some(1));
+    let outputs = sim.step(&[("a[0]", 0), ("sel", 1)]).unwrap();
+    assert_eq!(outputs["pick"], Some(0));
+    assert_eq!(outputs["nand"], Some(1));
+}
+
+fn placed(kind: Kind, x: i16, y: i16, label: &str, word_size: u8) -> v9::Component {
+    v9::Component {
+        kind,
+        position: Point::new(x, y),
+        custom_string: label.into(),
+        word_size: word_size.into(),
+        ..Default::default()
+    }
+}
+
+/// Wires `from` to `to` through a horizontal lane at `y = lane`.
+fn link(circuit: &mut v9::CircuitData, from: (usize, &str), to: (usize, &str), lane: i16) {
+    let pin = |(c, name): (usize, &str)| {
+        let c = &circuit.components[c];
+        let offset = c.kind.pin(name).unwrap().offset;
+        Point::new(c.position.x + offset.x, c.position.y + offset.y)
+    };
+    let (a, b) = (pin(from), pin(to));
+    let points = [a, Point::new(a.x, lane), Point::new(b.x, lane), b];
+    circuit.wires.push(Wire::from_polyline(&points).unwrap());
+}
+
+/// `sum = a + b` on 4-bit words, and `late` the low bit of `sum` a tick ago.
+fn word_adder() -> v9::CircuitData {
+    let mut circuit = v9::CircuitData {
+        components: vec![
+            placed(Kind::CcInput, 0, 0, "a", 4),
+            placed(Kind::CcInput, 0, 10, "b", 4),
+            placed(Kind::Add, 20, 0, "", 4),
+            placed(Kind::CcOutput, 40, 0, "sum", 4),
+            placed(Kind::DelayLineBit, 40, 10, "", 0),
+            placed(Kind::CcOutput, 60, 10, "late", 1),
+        ],
+        ..Default::default()
+    };
+    link(&mut circuit, (0, "out"), (2, "a"), -20);
+    link(&mut circuit, (1, "out"), (2, "b"), -21);
+    link(&mut circuit, (2, "out"), (3, "in"), -22);
+    link(&mut circuit, (3, "in"), (4, "a"), -23);
+    link(&mut circuit, (4, "out"), (5, "in"), -24);
+    circuit
+}
+
+#[test]
+fn exports_bit_blasted_cells() {
+    let netlist = yosys::export::export(&word_adder(), "adder").unwrap();
+    let (name, module) = netlist.top().unwrap();
+    assert_eq!(name, "adder");
+    let widths: Vec<_> = module
+        .ports
+        .iter()
+        .map(|(name, port)| (name.as_str(), port.direction, port.bits.len()))
+        .collect();
+    assert_eq!(
+        widths,
+        [
+            ("a", PortDirection::Input, 4),
+            ("b", PortDirection::Input, 4),
+            ("clk", PortDirection::Input, 1),
+            ("late", PortDirection::Output, 1),
+            ("sum", PortDirection::Output, 4),
+        ]
+    );
+    let count = |cell_type: &str| {
+        module
+            .cells
+            .values()
+            .filter(|c| c.cell_type == cell_type)
+            .count()
+    };
+    assert_eq!(count("$_XOR_"), 8);
+    assert_eq!(count("$_DFF_P_"), 1);
+    // The output port reads the sum's bits, which the adder drives.
+    let sum = &module.ports["sum"].bits;
+    assert!(
+        module
+            .cells
+            .values()
+            .any(|c| c.connections.get("Y") == Some(&vec![sum[3].clone()]))
+    );
+}
+
+#[test]
+fn exported_json_imports_and_simulates_the_same() {
+    let text = yosys::export::export(&word_adder(), "adder")
+        .unwrap()
+        .to_json();
+    let netlist = Netlist::from_json(&text).unwrap();
+    let circuit = yosys::import(netlist.module("adder").unwrap()).unwrap();
+    let mut sim = Simulator::new(&circuit);
+    for (a, b) in [(3, 4), (9, 9), (15, 1)] {
+        let mut inputs = Vec::new();
+        for i in 0..4 {
+            inputs.push((format!("a[{}]", i), (a >> i) & 1));
+            inputs.push((format!("b[{}]", i), (b >> i) & 1));
+        }
+        let inputs: Vec<_> = inputs.iter().map(|(l, v)| (l.as_str(), *v)).collect();
+        let outputs = sim.step(&inputs).unwrap();
+        let sum: u64 = (0..4)
+            .map(|i| outputs[&format!("sum[{}]", i)].unwrap() << i)
+            .sum();
+        assert_eq!(sum, (a + b) & 15, "{} + {}", a, b);
+    }
+}
+
+#[cfg(feature = "yosys-netlist-json")]
+#[test]
+fn exported_json_parses_with_yosys_netlist_json() {
+    let text = yosys::export::export(&word_adder(), "adder")
+        .unwrap()
+        .to_json();
+    let parsed = yosys_netlist_json::Netlist::from_slice(text.as_bytes()).unwrap();
+    let netlist = Netlist::from(parsed);
+    let ours = Netlist::from_json(&text).unwrap();
+    assert_eq!(netlist.to_json(), ours.to_json());
+    assert!(yosys::import(netlist.module("adder").unwrap()).is_ok());
+}
+
+#[test]
+fn writes_blif() {
+    let netlist = yosys::export::export(&word_adder(), "adder").unwrap();
+    let blif = netlist.to_blif().unwrap();
+    assert!(blif.starts_with(
+        ".model adder\n.inputs a[0] a[1] a[2] a[3] b[0] b[1] b[2] b[3] clk\n.outputs late sum[0] sum[1] sum[2] sum[3]\n"
+    ));
+    assert!(blif.contains(".names a[0] b[0] "));
+    assert!(blif.contains(" re clk 0\n"));
+    assert!(blif.ends_with(".end\n"));
+
+    let unsupported = v9::CircuitData {
+        components: vec![placed(Kind::Ram, 0, 0, "", 8)],
+        ..Default::default()
+    };
+    assert!(matches!(
+        yosys::export::export(&unsupported, "ram"),
+        Err(Error::UnsupportedCell { cell, .. }) if cell == "c0"
+    ));
+}
@@ -1,12 +1,22 @@
-use std::mem::Discriminant;
-
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{ItemEnum, Variant, parse_macro_input};
+use syn::{
+    Expr, ItemEnum, ItemStruct, MetaNameValue, Token, Variant, parse_macro_input, parse_quote,
+    punctuated::Punctuated,
+};
 
 #[proc_macro_attribute]
-pub fn kind_mapper(args: TokenStream, input: TokenStream) -> TokenStream {
-    let ast = parse_macro_input!(input as ItemEnum);
+pub fn kind_mapper(_args: TokenStream, input: TokenStream) -> TokenStream {
+    let mut ast = parse_macro_input!(input as ItemEnum);
+    let info_variants = match ast
+        .variants
+        .iter_mut()
+        .map(take_info)
+        .collect::<syn::Result<Vec<_>>>()
+    {
+        Ok(infos) => infos,
+        Err(err) => return err.to_compile_error().into(),
+    };
     let orig_variants = ast.variants.clone();
     let enum_name = &ast.ident;
     let from_variants = orig_variants.iter().map(|variant| {
@@ -34,7 +44,19 @@ pub fn kind_mapper(args: TokenStream, input: TokenStream) -> TokenStream {
         }
     });
 
-    println!("Attrs: {:?}", ast.attrs);
+    let info_arms = info_variants.iter().map(|(ident, info)| {
+        let (name, category, layout) = (&info.name, &info.category, &info.layout);
+        quote! {
+            #enum_name::#ident => {
+                const INFO: KindInfo = KindInfo {
+                    name: #name,
+                    category: Category::#category,
+                    layout: &#layout,
+                };
+                &INFO
+            }
+        }
+    });
 
     let vis = &ast.vis;
     let enum_token = &ast.enum_token;
@@ -71,10 +93,81 @@ pub fn kind_mapper(args: TokenStream, input: TokenStream) -> TokenStream {
                 }
             }
         }
+        impl #ident {
+            /// Catalog entry for this kind, from its `#[info(...)]` attribute.
+            pub fn info(&self) -> &'static KindInfo {
+                match self {
+                    #(#info_arms)*
+                    #ident::Unmapped(_) => &KindInfo::UNMAPPED,
+                }
+            }
+        }
     };
     TokenStream::from(expanded)
 }
 
+/// Gives every named field of a binrw struct a `crate::located::Field`
+/// backtrace frame, so a parse error can name the fields it happened in.
+/// Goes above `#[binrw]` or the `BinRead` derive.
+#[proc_macro_attribute]
+pub fn located(_args: TokenStream, input: TokenStream) -> TokenStream {
+    let mut ast = parse_macro_input!(input as ItemStruct);
+    for field in ast.fields.iter_mut() {
+        let Some(ident) = &field.ident else {
+            continue;
+        };
+        let name = ident.to_string();
+        field
+            .attrs
+            .push(parse_quote!(#[br(err_context(crate::located::Field(#name)))]));
+    }
+    TokenStream::from(quote! { #ast })
+}
+
+struct Info {
+    name: Expr,
+    category: Expr,
+    layout: Expr,
+}
+
+/// Removes the `#[info(name = .., category = .., layout = ..)]` attribute from
+/// a variant and returns its values.
+fn take_info(variant: &mut Variant) -> syn::Result<(syn::Ident, Info)> {
+    let Some(index) = variant.attrs.iter().position(|a| a.path().is_ident("info")) else {
+        return Err(syn::Error::new_spanned(
+            &variant.ident,
+            "every variant needs an #[info(...)] attribute",
+        ));
+    };
+    let attr = variant.attrs.remove(index);
+    let args = attr.parse_args_with(Punctuated::<MetaNameValue, Token![,]>::parse_terminated)?;
+    let (mut name, mut category, mut layout) = (None, None, None);
+    for arg in args {
+        let slot = if arg.path.is_ident("name") {
+            &mut name
+        } else if arg.path.is_ident("category") {
+            &mut category
+        } else if arg.path.is_ident("layout") {
+            &mut layout
+        } else {
+            return Err(syn::Error::new_spanned(
+                arg.path,
+                "expected name, category or layout",
+            ));
+        };
+        *slot = Some(arg.value);
+    }
+    let missing = |what| syn::Error::new_spanned(&attr, format!("missing `{}`", what));
+    Ok((
+        variant.ident.clone(),
+        Info {
+            name: name.ok_or_else(|| missing("name"))?,
+            category: category.ok_or_else(|| missing("category"))?,
+            layout: layout.ok_or_else(|| missing("layout"))?,
+        },
+    ))
+}
+
 // TODO: Figure out how to make this work
 // #[proc_macro_attribute(ParserHelper)]
 // pub fn length_prefixed_array(args: TokenStream, input: TokenStream) -> TokenStream {
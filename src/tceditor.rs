@@ -1,21 +1,24 @@
 use std::{
     fs::{self, File},
     io::{Cursor, Read, Seek},
-    path::PathBuf,
+    path::{Path, PathBuf},
     ptr::write_bytes,
 };
 
 use egui::{TextEdit, Ui, scroll_area};
 use tc_save_parser::{
-    CircuitDataFile, CircuitDataVersion, Kind, Point, new_permament_id,
+    CircuitDataFile, CircuitDataVersion, Kind, Point, new_permament_id, render,
     v9::{CircuitData, Component, Wire, WireDirection, WireSegment},
+    yosys,
 };
-use yosys_netlist_json::Netlist;
 
 #[derive(Default)]
 pub struct TCEditor {
     // circuit: CircuitData,
     editors: Vec<TCCircuitEditor>,
+    /// Why the last file couldn't be opened or imported, shown until the
+    /// next attempt.
+    error: Option<String>,
 }
 
 impl TCEditor {
@@ -29,18 +32,19 @@ impl TCEditor {
 }
 
 impl TCEditor {
-    fn open(&mut self, path: &str) {
-        self.editors.push(TCCircuitEditor::new(path));
+    fn open(&mut self, path: &Path) {
+        match TCCircuitEditor::new(path) {
+            Ok(editor) => {
+                self.editors.push(editor);
+                self.error = None;
+            }
+            Err(err) => self.error = Some(format!("Couldn't open {}: {}", path.display(), err)),
+        }
     }
 }
 
 impl eframe::App for TCEditor {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        if self.editors.is_empty() {
-            if let Some(path) = generate() {
-                self.open(&path);
-            }
-        }
         egui::TopBottomPanel::top("menu_bar").show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
                 // file_menu_button(ui);
@@ -49,11 +53,16 @@ impl eframe::App for TCEditor {
                         let files = rfd::FileDialog::new()
                             .add_filter("circuit.data", &["data"])
                             .pick_file();
-                        if files.is_none() {
-                            return;
+                        if let Some(path) = files {
+                            self.open(&path);
+                        }
+                    }
+                    if ui.button("Import yosys JSON").clicked() {
+                        match import_yosys() {
+                            Ok(Some(path)) => self.open(&path),
+                            Ok(None) => {}
+                            Err(err) => self.error = Some(format!("Import failed: {}", err)),
                         }
-                        let path = files.unwrap().to_str().unwrap().to_string();
-                        self.open(&path);
                     }
                     // if ui.button("Save").clicked() {
                     //     // let path = files.unwrap().to_str().unwrap().to_string();
@@ -63,6 +72,11 @@ impl eframe::App for TCEditor {
                 })
             });
         });
+        if let Some(error) = &self.error {
+            egui::TopBottomPanel::top("error").show(ctx, |ui| {
+                ui.colored_label(ui.visuals().error_fg_color, error.as_str());
+            });
+        }
         egui::CentralPanel::default().show(ctx, |ui| {
             for editor in &mut self.editors {
                 editor.ui(ui);
@@ -73,73 +87,110 @@ impl eframe::App for TCEditor {
 
 pub struct TCCircuitEditor {
     circuit: CircuitData,
-    path: String,
+    path: PathBuf,
+    /// Version the file was loaded from, so saving doesn't force an upgrade.
+    version: u8,
+    /// Outcome of the last save, shown next to the button.
+    status: Option<String>,
 }
 
 impl TCCircuitEditor {
-    pub fn new(path: &str) -> Self {
-        let circuitfile = match CircuitDataFile::load(&path) {
-            Ok(circuitfile) => circuitfile,
-            Err(err) => {
-                match err {
-                    tc_save_parser::Error::UnsupportedVersion(version, data) => {
-                        eprintln!("Unsupported version: {} {:?}", version, data);
-                        fs::write("uncompressed.data", data).unwrap();
-                    }
-                    tc_save_parser::Error::Binrw(err) => {
-                        let data = CircuitDataFile::debug_dump(path).unwrap();
-                        fs::write("uncompressed.data", data).unwrap();
-                        eprintln!("Error: {:?}", err)
-                    }
-                    _ => eprintln!("Error: {:?}", err),
+    /// Loads the save at `path`, upgraded to the latest version. Saves that
+    /// can't be read leave their decompressed payload in
+    /// `uncompressed.data` for a closer look.
+    pub fn new(path: &Path) -> Result<Self, tc_save_parser::Error> {
+        let circuitfile = CircuitDataFile::load(path).inspect_err(|err| match err {
+            tc_save_parser::Error::UnsupportedVersion(_, data) => dump_payload(data),
+            tc_save_parser::Error::Binrw(_) => {
+                if let Ok(data) = CircuitDataFile::debug_dump(path) {
+                    dump_payload(&data);
                 }
-                std::process::exit(1);
-            }
-        };
-        let circuit = match circuitfile.circuit {
-            CircuitDataVersion::V9(circuit) => circuit,
-            CircuitDataVersion::Unknown(data) => {
-                fs::write("uncompressed.data", data).unwrap();
-                panic!("Unsupported version: {}", circuitfile.version)
             }
-            _ => {
-                panic!("Unsupported version: {}", circuitfile.version)
+            _ => {}
+        })?;
+        let version = circuitfile.version;
+        let (circuit, report) = circuitfile.into_latest().inspect_err(|err| {
+            if let tc_save_parser::Error::UnsupportedVersion(_, data) = err {
+                dump_payload(data);
             }
-        };
+        })?;
+        for change in &report.changes {
+            eprintln!("Upgrading from version {}: {}", version, change);
+        }
         let mut s = Self {
             circuit,
-            path: path.to_string(),
+            path: path.to_path_buf(),
+            version,
+            status: None,
         };
         s.init();
-        s
+        Ok(s)
     }
 
-    pub fn save(&self, path: &str) {
+    /// Saves in the version the file was loaded from. Refuses to save when
+    /// that version can't hold everything in the circuit.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
         let cdf = CircuitDataFile {
             version: 9,
             circuit: CircuitDataVersion::V9(self.circuit.clone()),
         };
-        cdf.save(path).unwrap();
+        let (cdf, report) = cdf.convert_to(self.version).map_err(|e| e.to_string())?;
+        if !report.is_lossless() {
+            let dropped: Vec<_> = report.dropped().collect();
+            return Err(format!(
+                "version {} can't hold {}",
+                self.version,
+                dropped.join(", ")
+            ));
+        }
+        cdf.save(path).map_err(|e| e.to_string())
     }
     fn ui(&mut self, ui: &mut Ui) {
         scroll_area::ScrollArea::vertical().show(ui, |ui| {
             let sort_button = ui.button("Sort Components Alphabetically");
             if sort_button.clicked() {
-                alphanumeric_sort::sort_slice_by_str_key(&mut self.circuit.components, |a| {
-                    &a.custom_string.value
+                self.circuit.components.sort_by(|a, b| {
+                    alphanumeric_sort::compare_str(
+                        a.custom_string.to_string_lossy(),
+                        b.custom_string.to_string_lossy(),
+                    )
                 });
             }
-            let save_button = ui.button("Save");
-            if save_button.clicked() {
-                self.save(&self.path);
+            ui.horizontal(|ui| {
+                if ui.button("Save").clicked() {
+                    self.status = Some(match self.save(&self.path) {
+                        Ok(()) => "Saved".to_string(),
+                        Err(err) => format!("Not saved: {}", err),
+                    });
+                }
+                if let Some(status) = &self.status {
+                    ui.label(status);
+                }
+            });
+            if ui.button("Update from yosys JSON").clicked() {
+                self.reimport_yosys();
+            }
+            if ui.button("Export SVG").clicked() {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("SVG", &["svg"])
+                    .save_file()
+                {
+                    let svg = render::to_svg(&self.circuit, &render::RenderOptions::default());
+                    if let Err(err) = fs::write(path, svg) {
+                        self.status = Some(format!("Export failed: {}", err));
+                    }
+                }
             }
             for component in self.circuit.components.iter_mut() {
                 ui.horizontal(|ui| {
                     ui.label(format!("Kind {:?}", component.kind));
                     ui.label(format!("Pos {:?}", component.position));
                     ui.label(format!("Perm ID {:?}", component.permanent_id));
-                    if (component.custom_string.value.len() > 0) {
-                        ui.label(format!("Custom {:?}", component.custom_string));
+                    if (component.custom_string.len() > 0) {
+                        ui.label(format!(
+                            "Custom {:?}",
+                            component.custom_string.to_string_lossy()
+                        ));
                     }
                     if (component.settings.len() > 0) {
                         ui.label(format!("Settings {:?}", component.settings));
@@ -171,6 +222,35 @@ impl TCCircuitEditor {
         //self.circuit.wires
     }
 
+    /// Updates the circuit from a new version of the yosys netlist it was
+    /// imported from, keeping the layout of what didn't change.
+    fn reimport_yosys(&mut self) {
+        let Some(json) = rfd::FileDialog::new()
+            .add_filter("yosys JSON", &["json"])
+            .pick_file()
+        else {
+            return;
+        };
+        let result = read_netlist(&json).and_then(|netlist| {
+            let (_, module) = netlist.top().ok_or_else(|| {
+                tc_save_parser::Error::InvalidNetlist("no top module".to_string())
+            })?;
+            yosys::reimport(&self.circuit, module)
+        });
+        match result {
+            Ok((circuit, report)) => {
+                eprintln!(
+                    "Added {} and removed {} components, rerouted {} wires",
+                    report.added.len(),
+                    report.removed.len(),
+                    report.added_wires
+                );
+                self.circuit = circuit;
+            }
+            Err(err) => self.status = Some(format!("Update failed: {}", err)),
+        }
+    }
+
     fn init(&mut self) {
         // self.circuit.camera_position = Point::new(0, 0);
         // self.circuit.components = vec![];
@@ -206,92 +286,44 @@ impl TCCircuitEditor {
         // self.circuit.components[0]
         //     .linked_components
         //     .linked_components = vec![id];
-
-        // self.save(&self.path);
-        // let mut circuit = CircuitData::default();
-        // circuit.custom_id = new_permament_id();
-
-        // let mut fh = File::open("input.json").unwrap();
-        // let nl = Netlist::from_reader(&mut fh).unwrap();
-        // let module = nl.modules.get("ibex_load_store_unit").unwrap();
-
-        // for (name, port) in &module.ports {
-        //     let mut comp = Component::default();
-        //     comp.kind = Kind::On;
-        //     comp.position = Point::new(0, 0);
-        //     comp.permanent_id = new_permament_id();
-        //     comp.custom_string = name.clone().into();
-        //     circuit.components.push(comp);
-        // }
     }
 }
 
-fn generate() -> Option<String> {
-    None
-    // let inputs = [
-    //     "lsu_req",
-    //     "data_pmp_err",
-    //     "lsu_we",
-    //     "data_gnt",
-    //     "split_misaligned_access",
-    // ];
-    // let outputs = [
-    //     "data_req",
-    //     "pmp_err",
-    //     "perf_load",
-    //     "perf_store",
-    //     "ctrl_update",
-    //     "addr_update",
-    //     "handle_misaligned",
-    //     "lsm_fsm",
-    // ];
-    // let name = "autogen";
-    // let path = PathBuf::from(
-    //     "C:\\Users\\ashum\\AppData\\Roaming\\godot\\app_userdata\\Turing Complete\\schematics\\component_factory\\auto",
-    // );
-    // let path = path.join(name);
-    // if let Ok(exists) = fs::exists(&path) {
-    //     // if !exists || true {
-    //     if true {
-    //         fs::create_dir_all(&path).unwrap();
-    //         let mut cd = CircuitData::default();
-    //         cd.camera_position = Point::new(0, 0);
-    //         cd.custom_id = new_permament_id();
-    //         cd.components = vec![];
-    //         let gen_input = |name: &str, pos: Point| {
-    //             let mut comp = Component::default();
-    //             comp.kind = Kind::CcInput;
-    //             comp.position = pos;
-    //             comp.permanent_id = new_permament_id();
-    //             comp.custom_string = name.into();
-    //             comp.word_size = i64::MIN;
-    //             comp
-    //         };
-    //         let gen_output = |name: &str, pos: Point| {
-    //             let mut comp = Component::default();
-    //             comp.kind = Kind::CcOutput;
-    //             comp.position = pos;
-    //             comp.permanent_id = new_permament_id();
-    //             comp.custom_string = name.into();
-    //             comp
-    //         };
+/// Imports the top module of a yosys JSON netlist as a new custom component,
+/// saved in a chosen component factory folder. `None` if either dialog was
+/// cancelled.
+fn import_yosys() -> Result<Option<PathBuf>, tc_save_parser::Error> {
+    let Some(json) = rfd::FileDialog::new()
+        .add_filter("yosys JSON", &["json"])
+        .pick_file()
+    else {
+        return Ok(None);
+    };
+    let Some(dir) = rfd::FileDialog::new()
+        .set_title("Component factory folder")
+        .pick_folder()
+    else {
+        return Ok(None);
+    };
+    let netlist = read_netlist(&json)?;
+    let (_, module) = netlist
+        .top()
+        .ok_or_else(|| tc_save_parser::Error::InvalidNetlist("no top module".to_string()))?;
+    yosys::save_component(&yosys::import(module)?, &dir).map(Some)
+}
 
-    //         for (i, name) in inputs.iter().enumerate() {
-    //             let comp = gen_input(name, Point::new(0, (i as i16) * 8));
-    //             cd.components.push(comp);
-    //         }
-    //         for (i, name) in outputs.iter().enumerate() {
-    //             let comp = gen_output(name, Point::new(24, (i as i16) * 8));
-    //             cd.components.push(comp);
-    //         }
+/// Leaves the payload of a save that couldn't be read in
+/// `uncompressed.data`.
+fn dump_payload(data: &[u8]) {
+    if let Err(err) = fs::write("uncompressed.data", data) {
+        eprintln!("Couldn't write uncompressed.data: {}", err);
+    }
+}
 
-    //         let cdf = CircuitDataFile {
-    //             version: 8,
-    //             circuit: CircuitDataVersion::V8(cd),
-    //         };
-    //         let path = path.join("circuit.data");
-    //         cdf.save(path.to_str().unwrap()).unwrap();
-    //     }
-    // }
-    // Some(path.join("circuit.data").to_str().unwrap().to_string())
+/// Reads a yosys JSON netlist with `yosys-netlist-json`.
+fn read_netlist(path: &Path) -> Result<yosys::Netlist, tc_save_parser::Error> {
+    let file = File::open(path)?;
+    let netlist = yosys_netlist_json::Netlist::from_reader(file)
+        .map_err(|e| tc_save_parser::Error::InvalidNetlist(e.to_string()))?;
+    Ok(netlist.into())
 }